@@ -1,4 +1,6 @@
+import shutil
 import subprocess
+import sys
 
 
 def build():
@@ -10,12 +12,15 @@ def build():
 
     # shell=True works around https://bugs.python.org/issue17023 (In windows)
     # See https://stackoverflow.com/questions/42572582/winerror-2-the-system-cannot-find-the-file-specified-python
+    # Same command (and so features) as the CI build.
     p = subprocess.Popen(["wasm-pack", "build", "--target", "nodejs"],
                          cwd="lapol-rs",
                          shell=True)
     p.wait()
     print("Finished building lapol-rs.")
 
+    build_napi()
+
     print("Installing node (NPM) dependencies for lapol-core."
           " This may take some time.")
     p2 = subprocess.Popen(["npm", "install"],
@@ -33,4 +38,28 @@ def build():
     print("Finished building lapol-core.")
 
 
+def build_napi():
+    """Builds lapol-napi-rs, the native Node addon, which lapol-core uses instead of lapol-rs
+    (WASM) for parsing when available. Optional: if it fails, lapol-rs is used."""
+    print("Building lapol-napi-rs (optional, native parser for Node)...")
+    # With shell=True, POSIX shells would drop the arguments.
+    p = subprocess.Popen(["cargo", "build", "--release"],
+                         cwd="lapol-napi-rs",
+                         shell=sys.platform == "win32")
+    p.wait()
+    if p.returncode != 0:
+        print("Could not build lapol-napi-rs, lapol-rs (WASM) will be used instead.")
+        return
+
+    if sys.platform == "win32":
+        lib = "lapol_napi_rs.dll"
+    elif sys.platform == "darwin":
+        lib = "liblapol_napi_rs.dylib"
+    else:
+        lib = "liblapol_napi_rs.so"
+    shutil.copyfile("lapol-napi-rs/target/release/" + lib,
+                    "lapol-napi-rs/lapol_napi_rs.node")
+    print("Finished building lapol-napi-rs.")
+
+
 build()
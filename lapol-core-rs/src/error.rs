@@ -0,0 +1,10 @@
+use thiserror::Error as TError;
+
+/// Errors in the native counterparts of lapol-core (mirrors `LapolError`).
+#[derive(Debug, TError)]
+pub enum LapolError {
+    #[error("LaPoL processing error --- {0}")]
+    ProcessError(String),
+    #[error("LaPoL output error --- {0}")]
+    OutputError(String),
+}
@@ -0,0 +1,13 @@
+//! # LaPoL core (native)
+//!
+//! `lapol-core-rs` implements parts of `lapol-core` natively, so that Rust
+//! tools can go beyond the raw AST produced by `lapol-parse-rs`.
+//!
+//! See `lapol-core/architecture.md` for an overview of the compilation steps.
+
+mod error;
+pub mod ltrf;
+pub mod output;
+pub mod process;
+
+pub use error::LapolError;
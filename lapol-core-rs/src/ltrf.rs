@@ -0,0 +1,212 @@
+//! LaPoL Text Representation Format
+//!
+//! Mirrors `lapol-core/src/internal/ltrf/ltrf.ts`. An LTRF tree is immutable:
+//! "modifying" a node creates a new node, which shares the unchanged parts
+//! with the old one. Cloning is cheap (reference counting).
+//!
+//! With serde, an `LtrfNode` has the same JSON shape that lapol-core's
+//! `dbgStringify` emits (`{"_tag": ..., "_kv": ..., "_elems": [...]}`), and
+//! an `LtrfStr` is a plain string, so trees can be exchanged with lapol-core.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Key-value data of an `LtrfNode` (`Record<string, unknown>` in lapol-core).
+pub type LtrfKv = serde_json::Map<String, serde_json::Value>;
+
+pub type LtrfStr = Arc<str>;
+
+/// An object in the LTRF Tree. Is immutable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LtrfObj {
+    Str(LtrfStr),
+    Node(LtrfNode),
+}
+
+impl LtrfObj {
+    pub fn is_str(&self) -> bool {
+        matches!(self, LtrfObj::Str(_))
+    }
+
+    pub fn is_node(&self) -> bool {
+        matches!(self, LtrfObj::Node(_))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            LtrfObj::Str(s) => Some(s),
+            LtrfObj::Node(_) => None,
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&LtrfNode> {
+        match self {
+            LtrfObj::Str(_) => None,
+            LtrfObj::Node(n) => Some(n),
+        }
+    }
+
+    /// Dispatches to `fs` or `fn_` depending on the kind of object (like
+    /// lapol-core's `ltrfObjLift`).
+    pub fn lift<T>(&self, fs: impl FnOnce(&str) -> T, fn_: impl FnOnce(&LtrfNode) -> T) -> T {
+        match self {
+            LtrfObj::Str(s) => fs(s),
+            LtrfObj::Node(n) => fn_(n),
+        }
+    }
+}
+
+impl From<&str> for LtrfObj {
+    fn from(s: &str) -> Self {
+        LtrfObj::Str(s.into())
+    }
+}
+
+impl From<String> for LtrfObj {
+    fn from(s: String) -> Self {
+        LtrfObj::Str(s.into())
+    }
+}
+
+impl From<LtrfNode> for LtrfObj {
+    fn from(n: LtrfNode) -> Self {
+        LtrfObj::Node(n)
+    }
+}
+
+/// LTRF Node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LtrfNode {
+    tag: Arc<str>,
+    kv: Arc<LtrfKv>,
+    elems: Arc<[LtrfObj]>,
+}
+
+impl LtrfNode {
+    pub fn make(tag: impl Into<Arc<str>>, kv: LtrfKv, elems: Vec<LtrfObj>) -> Self {
+        LtrfNode {
+            tag: tag.into(),
+            kv: Arc::new(kv),
+            elems: elems.into(),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn kv(&self) -> &LtrfKv {
+        &self.kv
+    }
+
+    pub fn elems(&self) -> &[LtrfObj] {
+        &self.elems
+    }
+
+    /// Shorthand for looking up a single key in `kv`.
+    pub fn kv_get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.kv.get(key)
+    }
+
+    pub fn with_tag(&self, new_tag: impl Into<Arc<str>>) -> Self {
+        LtrfNode {
+            tag: new_tag.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn update_tag(&self, f: impl FnOnce(&str) -> String) -> Self {
+        self.with_tag(f(&self.tag))
+    }
+
+    pub fn with_kv(&self, new_kv: LtrfKv) -> Self {
+        LtrfNode {
+            kv: Arc::new(new_kv),
+            ..self.clone()
+        }
+    }
+
+    pub fn update_kv(&self, f: impl FnOnce(&LtrfKv) -> LtrfKv) -> Self {
+        self.with_kv(f(&self.kv))
+    }
+
+    pub fn with_elems(&self, new_elems: Vec<LtrfObj>) -> Self {
+        LtrfNode {
+            elems: new_elems.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn update_elems(&self, f: impl FnOnce(&[LtrfObj]) -> Vec<LtrfObj>) -> Self {
+        self.with_elems(f(&self.elems))
+    }
+
+    /// `f` receives each element and its index.
+    pub fn map_elems(&self, mut f: impl FnMut(&LtrfObj, usize) -> LtrfObj) -> Self {
+        self.update_elems(|elems| elems.iter().enumerate().map(|(i, e)| f(e, i)).collect())
+    }
+
+    /// `f` receives each element and its index.
+    pub fn flat_map_elems<I: IntoIterator<Item = LtrfObj>>(
+        &self,
+        mut f: impl FnMut(&LtrfObj, usize) -> I,
+    ) -> Self {
+        self.update_elems(|elems| {
+            elems
+                .iter()
+                .enumerate()
+                .flat_map(|(i, e)| f(e, i))
+                .collect()
+        })
+    }
+
+    pub fn update(
+        &self,
+        ft: impl FnOnce(&str) -> String,
+        fk: impl FnOnce(&LtrfKv) -> LtrfKv,
+        fs: impl FnOnce(&[LtrfObj]) -> Vec<LtrfObj>,
+    ) -> Self {
+        LtrfNode::make(ft(&self.tag), fk(&self.kv), fs(&self.elems))
+    }
+
+    /// Same output as lapol-core's `LtrfNode.dbgStringify`.
+    pub fn dbg_stringify(&self) -> String {
+        serde_json::to_string_pretty(self).expect("LTRF trees are always serializable")
+    }
+}
+
+#[derive(Serialize)]
+struct LtrfNodeSer<'a> {
+    _tag: &'a str,
+    _kv: &'a LtrfKv,
+    _elems: &'a [LtrfObj],
+}
+
+#[derive(Deserialize)]
+struct LtrfNodeDe {
+    _tag: String,
+    #[serde(default)]
+    _kv: LtrfKv,
+    #[serde(default)]
+    _elems: Vec<LtrfObj>,
+}
+
+impl Serialize for LtrfNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LtrfNodeSer {
+            _tag: &self.tag,
+            _kv: &self.kv,
+            _elems: &self.elems,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LtrfNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let n = LtrfNodeDe::deserialize(deserializer)?;
+        Ok(LtrfNode::make(n._tag, n._kv, n._elems))
+    }
+}
@@ -0,0 +1,38 @@
+//! Output, AKA the "Backend"
+//!
+//! Mirrors `lapol-core/src/internal/out` and the outputters in
+//! `lapol-core/src/std/output`. A renderer turns a processed LTRF root node
+//! into code for a given target.
+
+mod blocks;
+pub mod epub;
+pub mod html;
+pub mod latex;
+pub mod markdown;
+pub mod requirements;
+pub mod text;
+
+pub use blocks::TextMapping;
+
+use crate::{error::LapolError, ltrf::LtrfNode};
+
+/// Tag of nodes holding already rendered code (e.g. from a plugin's
+/// outputter), which every target outputs as is. The node's elements must be
+/// strings; it is a block if its `isBlock` is true.
+pub const VERBATIM_TAG: &str = "__verbatim";
+
+/// The contents of a `VERBATIM_TAG` node.
+pub(crate) fn verbatim_text(n: &LtrfNode) -> Result<String, LapolError> {
+    n.elems()
+        .iter()
+        .map(|e| {
+            e.as_str().ok_or_else(|| {
+                LapolError::OutputError(format!("`{}` nodes can only contain strings.", n.tag()))
+            })
+        })
+        .collect()
+}
+
+fn is_block_node(n: &LtrfNode) -> bool {
+    n.kv_get("isBlock").and_then(|b| b.as_bool()) == Some(true)
+}
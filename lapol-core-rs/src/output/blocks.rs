@@ -0,0 +1,259 @@
+//! Shared layout for the line-oriented targets (plain text and Markdown).
+//!
+//! An LTRF tree is first flattened into a list of `Block`s (paragraphs,
+//! headings and quotes, with their inline content already rendered), which
+//! are then laid out as lines, wrapped at a given width.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use textwrap::{core::display_width, Options, WordSeparator, WordSplitter};
+
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfNode, LtrfObj},
+};
+
+use super::{is_block_node, verbatim_text};
+
+/// How an LTRF tag is rendered by the plain text and Markdown targets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextMapping {
+    /// The elements, between `before` and `after` (e.g. `**` for bold).
+    Inline { before: String, after: String },
+    /// A heading. `sec` is level 1.
+    Heading(usize),
+    /// A block quote.
+    Quote,
+    /// The elements, as a block of their own (inline content becomes a
+    /// paragraph).
+    Block,
+    /// Only the node's elements are rendered (e.g. for `__root`).
+    Transparent,
+    /// The node's elements are output as is (see `VERBATIM_TAG`). Blocks
+    /// aren't wrapped either.
+    Verbatim,
+}
+
+impl TextMapping {
+    pub fn inline(before: impl Into<String>, after: impl Into<String>) -> Self {
+        TextMapping::Inline {
+            before: before.into(),
+            after: after.into(),
+        }
+    }
+}
+
+/// What differs between the targets.
+pub(crate) trait Flavor {
+    fn name(&self) -> &'static str;
+
+    fn escape<'s>(&self, s: &'s str) -> Cow<'s, str>;
+
+    fn heading(&self, level: usize, text: &str) -> Vec<String>;
+
+    /// Called on every line of a paragraph, after wrapping.
+    fn escape_line_start(&self, line: String) -> String {
+        line
+    }
+}
+
+#[derive(Debug)]
+enum Block {
+    Para(String),
+    Verbatim(String),
+    Heading(usize, String),
+    Quote(Vec<Block>),
+}
+
+impl Block {
+    fn text(&self) -> String {
+        match self {
+            Block::Para(t) | Block::Verbatim(t) | Block::Heading(_, t) => t.clone(),
+            Block::Quote(bs) => bs.iter().map(Block::text).collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+pub(crate) fn render(
+    root: &LtrfNode,
+    tags: &HashMap<String, TextMapping>,
+    width: Option<usize>,
+    flavor: &dyn Flavor,
+) -> Result<String, LapolError> {
+    let collector = Collector { tags, flavor };
+    let mut ctx = Ctx::default();
+    collector.collect_node(root, &mut ctx)?;
+    ctx.flush();
+
+    let mut out = layout(&ctx.blocks, width, flavor).join("\n");
+    out.push('\n');
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Ctx {
+    blocks: Vec<Block>,
+    inline: String,
+}
+
+impl Ctx {
+    /// Ends the current paragraph.
+    fn flush(&mut self) {
+        let text = collapse_whitespace(&std::mem::take(&mut self.inline));
+        if !text.is_empty() {
+            self.blocks.push(Block::Para(text));
+        }
+    }
+}
+
+struct Collector<'o> {
+    tags: &'o HashMap<String, TextMapping>,
+    flavor: &'o dyn Flavor,
+}
+
+impl<'o> Collector<'o> {
+    fn collect_elems(&self, elems: &[LtrfObj], ctx: &mut Ctx) -> Result<(), LapolError> {
+        for e in elems {
+            match e {
+                LtrfObj::Str(s) => ctx.inline.push_str(&self.flavor.escape(s)),
+                LtrfObj::Node(n) => self.collect_node(n, ctx)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects `elems` as a separate list of blocks.
+    fn collect_blocks(&self, elems: &[LtrfObj]) -> Result<Vec<Block>, LapolError> {
+        let mut sub = Ctx::default();
+        self.collect_elems(elems, &mut sub)?;
+        sub.flush();
+        Ok(sub.blocks)
+    }
+
+    fn collect_node(&self, n: &LtrfNode, ctx: &mut Ctx) -> Result<(), LapolError> {
+        let mapping = self.tags.get(n.tag()).ok_or_else(|| {
+            LapolError::OutputError(format!(
+                "No {} mapping for LTRF tag `{}`.",
+                self.flavor.name(),
+                n.tag()
+            ))
+        })?;
+
+        match mapping {
+            TextMapping::Transparent => self.collect_elems(n.elems(), ctx)?,
+            TextMapping::Verbatim if is_block_node(n) => {
+                ctx.flush();
+                ctx.blocks.push(Block::Verbatim(verbatim_text(n)?));
+            }
+            TextMapping::Verbatim => ctx.inline.push_str(&verbatim_text(n)?),
+            TextMapping::Inline { before, after } => {
+                let start = ctx.inline.len();
+                self.collect_elems(n.elems(), ctx)?;
+
+                // Whitespace goes outside the delimiters (in Markdown, `** a**`
+                // isn't bold), and empty content gets no delimiters at all.
+                let inner = ctx.inline.split_off(start);
+                let trimmed = inner.trim_matches(is_space);
+                if trimmed.is_empty() {
+                    ctx.inline.push_str(&inner);
+                } else {
+                    let leading = &inner[..inner.len() - inner.trim_start_matches(is_space).len()];
+                    let trailing = &inner[inner.trim_end_matches(is_space).len()..];
+                    ctx.inline.push_str(leading);
+                    ctx.inline.push_str(before);
+                    ctx.inline.push_str(trimmed);
+                    ctx.inline.push_str(after);
+                    ctx.inline.push_str(trailing);
+                }
+            }
+            TextMapping::Block => {
+                ctx.flush();
+                ctx.blocks.extend(self.collect_blocks(n.elems())?);
+            }
+            TextMapping::Heading(level) => {
+                ctx.flush();
+                let text = self
+                    .collect_blocks(n.elems())?
+                    .iter()
+                    .map(Block::text)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                ctx.blocks.push(Block::Heading(*level, text));
+            }
+            TextMapping::Quote => {
+                ctx.flush();
+                let blocks = self.collect_blocks(n.elems())?;
+                ctx.blocks.push(Block::Quote(blocks));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn layout(blocks: &[Block], width: Option<usize>, flavor: &dyn Flavor) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            lines.push(String::new());
+        }
+
+        match block {
+            Block::Para(text) => lines.extend(
+                wrap(text, width)
+                    .into_iter()
+                    .map(|l| flavor.escape_line_start(l)),
+            ),
+            Block::Verbatim(text) => lines.extend(text.lines().map(str::to_owned)),
+            Block::Heading(level, text) => lines.extend(flavor.heading(*level, text)),
+            Block::Quote(sub) => {
+                let sub_width = width.map(|w| w.saturating_sub(2).max(1));
+                lines.extend(layout(sub, sub_width, flavor).into_iter().map(|l| {
+                    if l.is_empty() {
+                        ">".to_owned()
+                    } else {
+                        format!("> {}", l)
+                    }
+                }))
+            }
+        }
+    }
+
+    lines
+}
+
+/// Wraps at spaces only, so that e.g. Markdown escapes are never split.
+fn wrap(text: &str, width: Option<usize>) -> Vec<String> {
+    match width {
+        None => vec![text.to_owned()],
+        Some(width) => {
+            let options = Options::new(width)
+                .word_separator(WordSeparator::AsciiSpace)
+                .word_splitter(WordSplitter::NoHyphenation)
+                .break_words(false);
+            textwrap::wrap(text, options)
+                .into_iter()
+                .map(Cow::into_owned)
+                .collect()
+        }
+    }
+}
+
+/// Line breaks in a paragraph are not significant, so all runs of
+/// whitespace (except non-breaking spaces) become a single space.
+fn collapse_whitespace(s: &str) -> String {
+    s.split(is_space)
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r'
+}
+
+/// Width of `s` in columns, for underlines.
+pub(crate) fn width_of(s: &str) -> usize {
+    display_width(s)
+}
@@ -0,0 +1,529 @@
+//! EPUB 3 output.
+//!
+//! An EPUB is a zip file holding one XHTML document per chapter (rendered by
+//! `output::html`), a package document (`content.opf`) listing every file,
+//! and a navigation document (`nav.xhtml`, the table of contents, built from
+//! the `sec` and `subsec` headings).
+//!
+//! Chapters are made either from whole files (`EpubChapter::from_ltrf`), or
+//! by splitting a document at each section (`split_on_sections`).
+
+use std::{
+    fs,
+    io::{Seek, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+};
+
+use super::{
+    html::{escape_attr, render_html, HtmlOptions},
+    requirements::OutputRequirementReceiver,
+};
+
+/// `lapol-default.css`, embedded in every EPUB by default.
+pub const DEFAULT_CSS: &str = include_str!("../../../deps-lapol/lapol-default.css");
+
+const CSS_PATH: &str = "deps/lapol-default.css";
+
+#[derive(Debug, Clone)]
+pub struct EpubMetadata {
+    /// Unique identifier of the book (e.g. `urn:uuid:...`, or an ISBN).
+    pub identifier: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    /// BCP 47 language tag, e.g. `en`.
+    pub language: String,
+    /// Last modification time, as `CCYY-MM-DDThh:mm:ssZ`.
+    pub modified: String,
+}
+
+impl EpubMetadata {
+    /// English, modified now, with an identifier derived from the title.
+    pub fn new(title: impl Into<String>) -> Self {
+        let title = title.into();
+        let slug: String = title
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        EpubMetadata {
+            identifier: format!("urn:lapol:{}", slug),
+            title,
+            authors: Vec::new(),
+            language: "en".to_owned(),
+            modified: format_utc(secs),
+        }
+    }
+}
+
+/// A heading in a chapter, linked from the table of contents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavEntry {
+    pub title: String,
+    /// `id` of the heading element.
+    pub id: String,
+    /// 1 for `sec`, 2 for `subsec`.
+    pub level: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubChapter {
+    pub title: String,
+    /// Contents of the chapter's `<body>`, as XHTML.
+    pub body: String,
+    /// Headings in the chapter, except the one the title was taken from.
+    pub headings: Vec<NavEntry>,
+}
+
+impl EpubChapter {
+    /// Renders a processed LTRF root node as a chapter. The title is taken
+    /// from the first `sec` heading, if the chapter starts with one, or else
+    /// is `fallback_title`.
+    pub fn from_ltrf(
+        root: &LtrfNode,
+        fallback_title: &str,
+        options: &HtmlOptions,
+    ) -> Result<Self, LapolError> {
+        let mut headings = Vec::new();
+        let root = add_heading_ids(root, &mut headings);
+
+        let options = HtmlOptions {
+            standalone: false,
+            xhtml: true,
+            ..options.clone()
+        };
+        let body = render_html(&root, &options)?;
+
+        let title = match headings.first() {
+            Some(h) if h.level == 1 && starts_with_sec(&root) => headings.remove(0).title,
+            _ => fallback_title.to_owned(),
+        };
+
+        Ok(EpubChapter {
+            title,
+            body,
+            headings,
+        })
+    }
+}
+
+/// Splits a processed LTRF root node before each top-level `sec`, so that
+/// each section can become a chapter. Content before the first section (if
+/// any) is kept as a chapter of its own.
+pub fn split_on_sections(root: &LtrfNode) -> Vec<LtrfNode> {
+    let mut chunks: Vec<Vec<LtrfObj>> = Vec::new();
+    let mut current = Vec::new();
+
+    let docs = root.elems().iter().filter_map(LtrfObj::as_node);
+    for e in docs.flat_map(|doc| doc.elems()) {
+        if is_tag(e, "sec") && has_content(&current) {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push(e.clone());
+    }
+    if has_content(&current) {
+        chunks.push(current);
+    }
+
+    chunks
+        .into_iter()
+        .map(|elems| {
+            let doc = LtrfNode::make("__doc", LtrfKv::new(), elems);
+            root.with_elems(vec![doc.into()])
+        })
+        .collect()
+}
+
+/// An EPUB, ready to be written.
+#[derive(Debug, Clone)]
+pub struct Epub {
+    pub metadata: EpubMetadata,
+    pub chapters: Vec<EpubChapter>,
+    /// Stored as `deps/lapol-default.css`, and linked from every chapter.
+    pub stylesheet: String,
+    /// Other files (fonts, images, etc.), read when the EPUB is written.
+    pub requirements: OutputRequirementReceiver,
+}
+
+impl Epub {
+    pub fn new(metadata: EpubMetadata) -> Self {
+        Epub {
+            metadata,
+            chapters: Vec::new(),
+            stylesheet: DEFAULT_CSS.to_owned(),
+            requirements: OutputRequirementReceiver::new(),
+        }
+    }
+
+    /// Writes the EPUB (a zip file) to `w`, and returns `w`.
+    pub fn write<W: Write + Seek>(&self, w: W) -> Result<W, LapolError> {
+        let mut zip = ZipWriter::new(w);
+
+        // The mimetype must come first, uncompressed, so that the file type
+        // can be recognized from the first bytes.
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut add = |name: &str, options: FileOptions, contents: &[u8]| {
+            zip.start_file(name, options)
+                .map_err(zip_error)
+                .and_then(|_| zip.write_all(contents).map_err(io_error))
+        };
+
+        add("mimetype", stored, b"application/epub+zip")?;
+        add("META-INF/container.xml", deflated, CONTAINER_XML.as_bytes())?;
+        add(
+            "OEBPS/content.opf",
+            deflated,
+            self.package_document().as_bytes(),
+        )?;
+        add("OEBPS/nav.xhtml", deflated, self.nav_document().as_bytes())?;
+        add(
+            &format!("OEBPS/{}", CSS_PATH),
+            deflated,
+            self.stylesheet.as_bytes(),
+        )?;
+
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            let name = format!("OEBPS/{}", chapter_file(i));
+            add(&name, deflated, self.chapter_document(chapter).as_bytes())?;
+        }
+
+        for (target, source) in self.requirements.files() {
+            let contents = fs::read(source).map_err(io_error)?;
+            let name = format!("OEBPS/{}", href(target));
+            add(&name, deflated, &contents)?;
+        }
+
+        zip.finish().map_err(zip_error)
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), LapolError> {
+        let file = fs::File::create(path).map_err(io_error)?;
+        self.write(file)?;
+        Ok(())
+    }
+
+    fn package_document(&self) -> String {
+        let m = &self.metadata;
+        let mut opf = String::new();
+
+        opf.push_str(XML_DECLARATION);
+        opf.push_str(&format!(
+            "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" \
+             unique-identifier=\"book-id\" xml:lang=\"{}\">\n",
+            escape_attr(&m.language)
+        ));
+
+        opf.push_str("  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+        opf.push_str(&format!(
+            "    <dc:identifier id=\"book-id\">{}</dc:identifier>\n",
+            escape_attr(&m.identifier)
+        ));
+        opf.push_str(&format!(
+            "    <dc:title>{}</dc:title>\n",
+            escape_attr(&m.title)
+        ));
+        opf.push_str(&format!(
+            "    <dc:language>{}</dc:language>\n",
+            escape_attr(&m.language)
+        ));
+        for author in &m.authors {
+            opf.push_str(&format!(
+                "    <dc:creator>{}</dc:creator>\n",
+                escape_attr(author)
+            ));
+        }
+        opf.push_str(&format!(
+            "    <meta property=\"dcterms:modified\">{}</meta>\n",
+            escape_attr(&m.modified)
+        ));
+        opf.push_str("  </metadata>\n");
+
+        opf.push_str("  <manifest>\n");
+        opf.push_str(
+            "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" \
+             properties=\"nav\"/>\n",
+        );
+        opf.push_str(&format!(
+            "    <item id=\"css\" href=\"{}\" media-type=\"text/css\"/>\n",
+            CSS_PATH
+        ));
+        for i in 0..self.chapters.len() {
+            opf.push_str(&format!(
+                "    <item id=\"{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+                chapter_id(i),
+                chapter_file(i)
+            ));
+        }
+        for (i, (target, _)) in self.requirements.files().enumerate() {
+            opf.push_str(&format!(
+                "    <item id=\"asset-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+                i + 1,
+                escape_attr(&href(target)),
+                media_type(target)
+            ));
+        }
+        opf.push_str("  </manifest>\n");
+
+        opf.push_str("  <spine>\n");
+        for i in 0..self.chapters.len() {
+            opf.push_str(&format!("    <itemref idref=\"{}\"/>\n", chapter_id(i)));
+        }
+        opf.push_str("  </spine>\n");
+
+        opf.push_str("</package>\n");
+        opf
+    }
+
+    fn nav_document(&self) -> String {
+        let mut items = Vec::new();
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            let file = chapter_file(i);
+            items.push(NavItem {
+                level: 0,
+                title: &chapter.title,
+                href: file.clone(),
+            });
+            items.extend(chapter.headings.iter().map(|h| NavItem {
+                level: h.level,
+                title: &h.title,
+                href: format!("{}#{}", file, h.id),
+            }));
+        }
+
+        let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n");
+        write_nav_items(&mut body, &items);
+        body.push_str("</nav>");
+
+        self.xhtml_document(
+            &self.metadata.title,
+            " xmlns:epub=\"http://www.idpf.org/2007/ops\"",
+            &body,
+        )
+    }
+
+    fn chapter_document(&self, chapter: &EpubChapter) -> String {
+        let body = format!(
+            "<article class=\"page\">\n{}\n</article>",
+            chapter.body.trim_end()
+        );
+        self.xhtml_document(&chapter.title, "", &body)
+    }
+
+    fn xhtml_document(&self, title: &str, extra_ns: &str, body: &str) -> String {
+        let lang = escape_attr(&self.metadata.language);
+        format!(
+            "{}<!DOCTYPE html>\n\
+             <html xmlns=\"http://www.w3.org/1999/xhtml\"{} xml:lang=\"{}\" lang=\"{}\">\n\
+             <head>\n  <meta charset=\"utf-8\"/>\n  <title>{}</title>\n  \
+             <link rel=\"stylesheet\" href=\"{}\"/>\n</head>\n\
+             <body>\n{}\n</body>\n</html>\n",
+            XML_DECLARATION,
+            extra_ns,
+            lang,
+            lang,
+            escape_attr(title),
+            CSS_PATH,
+            body
+        )
+    }
+}
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+
+const CONTAINER_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+  <rootfiles>
+    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+  </rootfiles>
+</container>
+";
+
+struct NavItem<'a> {
+    level: usize,
+    title: &'a str,
+    href: String,
+}
+
+/// Writes `items` as nested lists, where each item contains the following
+/// items of a greater level.
+fn write_nav_items(out: &mut String, items: &[NavItem]) {
+    out.push_str("<ol>\n");
+
+    let mut i = 0;
+    while i < items.len() {
+        let level = items[i].level;
+        let end = items[i + 1..]
+            .iter()
+            .position(|it| it.level <= level)
+            .map_or(items.len(), |p| i + 1 + p);
+
+        out.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            escape_attr(&items[i].href),
+            escape_attr(items[i].title)
+        ));
+        if end > i + 1 {
+            out.push('\n');
+            write_nav_items(out, &items[i + 1..end]);
+        }
+        out.push_str("</li>\n");
+
+        i = end;
+    }
+
+    out.push_str("</ol>\n");
+}
+
+fn chapter_id(i: usize) -> String {
+    format!("chapter-{}", i + 1)
+}
+
+fn chapter_file(i: usize) -> String {
+    format!("{}.xhtml", chapter_id(i))
+}
+
+/// A relative path as a URL path (always with `/`).
+fn href(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn media_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("css") => "text/css",
+        Some("xhtml") => "application/xhtml+xml",
+        Some("js") => "application/javascript",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "audio/mp4",
+        Some("txt") | None => "text/plain",
+        Some(_) => "application/octet-stream",
+    }
+}
+
+/// Gives `sec` and `subsec` nodes an `id` (which the HTML renderer outputs
+/// as an attribute), and collects them.
+fn add_heading_ids(n: &LtrfNode, headings: &mut Vec<NavEntry>) -> LtrfNode {
+    let level = match n.tag() {
+        "sec" => Some(1),
+        "subsec" => Some(2),
+        _ => None,
+    };
+
+    if let Some(level) = level {
+        let id = format!("sec-{}", headings.len() + 1);
+        headings.push(NavEntry {
+            title: plain_text(n),
+            id: id.clone(),
+            level,
+        });
+        return n.update_kv(|kv| {
+            let mut kv = kv.clone();
+            kv.insert("id".to_owned(), id.into());
+            kv
+        });
+    }
+
+    n.map_elems(|e, _| match e {
+        LtrfObj::Node(child) => add_heading_ids(child, headings).into(),
+        s => s.clone(),
+    })
+}
+
+fn plain_text(n: &LtrfNode) -> String {
+    let mut out = String::new();
+    for e in n.elems() {
+        match e {
+            LtrfObj::Str(s) => out.push_str(s),
+            LtrfObj::Node(child) => out.push_str(&plain_text(child)),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_tag(obj: &LtrfObj, tag: &str) -> bool {
+    obj.as_node().is_some_and(|n| n.tag() == tag)
+}
+
+fn has_content(elems: &[LtrfObj]) -> bool {
+    elems
+        .iter()
+        .any(|e| e.as_str().map_or(true, |s| !s.trim().is_empty()))
+}
+
+/// Whether the first non-whitespace thing in the document is a `sec`.
+fn starts_with_sec(root: &LtrfNode) -> bool {
+    let first = root
+        .elems()
+        .iter()
+        .filter_map(LtrfObj::as_node)
+        .flat_map(|doc| doc.elems())
+        .find(|e| e.as_str().map_or(true, |s| !s.trim().is_empty()));
+    first.is_some_and(|e| is_tag(e, "sec"))
+}
+
+/// Formats seconds since the Unix epoch as `CCYY-MM-DDThh:mm:ssZ`.
+fn format_utc(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+fn zip_error(e: zip::result::ZipError) -> LapolError {
+    LapolError::OutputError(format!("Failed to write EPUB: {}", e))
+}
+
+fn io_error(e: std::io::Error) -> LapolError {
+    LapolError::OutputError(format!("Failed to write EPUB: {}", e))
+}
@@ -0,0 +1,452 @@
+//! HTML output.
+//!
+//! Each LTRF tag is rendered according to a mapping table (see
+//! `default_tag_map`). The defaults follow `std/*_html_output.ts`, except that
+//! `bold` and `italic` become `<strong>` and `<em>` (rather than `<b>` and
+//! `<i>`), and that `maketitle` isn't mapped: it needs the title and author,
+//! which aren't part of the tree, so callers map it themselves. Strings are
+//! escaped, and the `kv` of a node becomes the attributes of its element.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+};
+
+use super::{is_block_node, verbatim_text, VERBATIM_TAG};
+
+/// HTML elements that have no closing tag (and no content).
+pub const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// `kv` keys that are used by LaPoL itself, and so aren't output as
+/// attributes. (Keys starting with `_` are skipped too.)
+const INTERNAL_KV_KEYS: &[&str] = &["isBlock"];
+
+const INDENT: &str = "  ";
+
+/// An HTML element an LTRF node is rendered as.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlElement {
+    pub name: String,
+    /// Attributes added to every element. Attributes from the node's `kv`
+    /// take precedence.
+    pub attrs: Vec<(String, String)>,
+    /// Whether this is a block-level element. Only affects pretty-printing.
+    pub block: bool,
+}
+
+impl HtmlElement {
+    pub fn inline(name: impl Into<String>) -> Self {
+        HtmlElement {
+            name: name.into(),
+            attrs: Vec::new(),
+            block: false,
+        }
+    }
+
+    pub fn block(name: impl Into<String>) -> Self {
+        HtmlElement {
+            block: true,
+            ..HtmlElement::inline(name)
+        }
+    }
+
+    pub fn with_attr(mut self, attr: impl Into<String>, val: impl Into<String>) -> Self {
+        self.attrs.push((attr.into(), val.into()));
+        self
+    }
+
+    fn is_void(&self) -> bool {
+        VOID_ELEMENTS.contains(&self.name.as_str())
+    }
+}
+
+/// How an LTRF tag is rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlMapping {
+    Element(HtmlElement),
+    /// Only the node's elements are rendered (e.g. for `__root`).
+    Transparent,
+    /// The node's elements are output as is (see `VERBATIM_TAG`).
+    Verbatim,
+}
+
+impl From<HtmlElement> for HtmlMapping {
+    fn from(e: HtmlElement) -> Self {
+        HtmlMapping::Element(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// LTRF tag -> HTML. Rendering a tag that isn't in the table is an error.
+    pub tags: HashMap<String, HtmlMapping>,
+    /// Put block-level elements on their own (indented) lines. Whitespace
+    /// between block-level elements is dropped.
+    pub pretty: bool,
+    /// Output a full HTML document (with `<head>`, etc.), like lapol-core's
+    /// `__root` outputter, instead of a fragment.
+    pub standalone: bool,
+    /// Stylesheets linked from the `<head>` of a standalone document.
+    pub stylesheets: Vec<String>,
+    /// Output well-formed XHTML (e.g. for EPUB): void elements are closed
+    /// with `/>`, and boolean attributes get a value.
+    pub xhtml: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            tags: default_tag_map(),
+            pretty: false,
+            standalone: false,
+            stylesheets: vec![
+                "deps/hello-css-all.css".to_owned(),
+                "deps/lapol-default.css".to_owned(),
+            ],
+            xhtml: false,
+        }
+    }
+}
+
+/// Mappings for the tags produced by the `std` modules and the processing
+/// passes.
+pub fn default_tag_map() -> HashMap<String, HtmlMapping> {
+    let mut tags = HashMap::new();
+    let mut add = |tag: &str, mapping: HtmlMapping| {
+        tags.insert(tag.to_owned(), mapping);
+    };
+
+    add("__root", HtmlMapping::Transparent);
+    add(VERBATIM_TAG, HtmlMapping::Verbatim);
+    add("__doc", HtmlElement::block("div").into());
+    add("__p", HtmlElement::block("p").into());
+
+    add("sec", HtmlElement::block("h2").into());
+    add("subsec", HtmlElement::block("h3").into());
+    add("subsubsec", HtmlElement::block("h4").into());
+
+    add("bold", HtmlElement::inline("strong").into());
+    add("italic", HtmlElement::inline("em").into());
+
+    add("bquot", HtmlElement::block("blockquote").into());
+    add("marginnote", HtmlElement::inline("aside").into());
+
+    tags
+}
+
+/// Renders a (processed) LTRF root node as HTML.
+pub fn render_html(root: &LtrfNode, options: &HtmlOptions) -> Result<String, LapolError> {
+    let mut r = Renderer {
+        options,
+        out: String::new(),
+    };
+
+    if options.standalone {
+        r.out.push_str("<!DOCTYPE html>");
+        r.newline(0);
+        r.out.push_str(if options.xhtml {
+            r#"<html xmlns="http://www.w3.org/1999/xhtml">"#
+        } else {
+            "<html>"
+        });
+        r.newline(0);
+        r.out.push_str("<head>");
+        r.newline(1);
+        r.out.push_str(r#"<meta charset="utf-8""#);
+        r.out.push_str(r.void_end());
+        for href in &options.stylesheets {
+            r.newline(1);
+            r.out.push_str(&format!(
+                r#"<link rel="stylesheet" href="{}""#,
+                escape_attr(href)
+            ));
+            r.out.push_str(r.void_end());
+        }
+        r.newline(0);
+        r.out.push_str("</head>");
+        r.newline(0);
+        r.out.push_str(r#"<body><article class="page">"#);
+    }
+
+    r.write_node(root, 0)?;
+
+    if options.standalone {
+        r.newline(0);
+        r.out.push_str("</article></body>");
+        r.newline(0);
+        r.out.push_str("</html>");
+    }
+
+    let mut out = r.out;
+    if options.pretty {
+        out = out.trim_start_matches('\n').to_owned();
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Escapes text content.
+pub fn escape_text(s: &str) -> Cow<'_, str> {
+    escape(s, false)
+}
+
+/// Escapes a (double-quoted) attribute value.
+pub fn escape_attr(s: &str) -> Cow<'_, str> {
+    escape(s, true)
+}
+
+fn escape(s: &str, quotes: bool) -> Cow<'_, str> {
+    let needs_escape = |c: char| matches!(c, '&' | '<' | '>') || (quotes && c == '"');
+    if !s.contains(needs_escape) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if quotes => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+struct Renderer<'o> {
+    options: &'o HtmlOptions,
+    out: String,
+}
+
+impl<'o> Renderer<'o> {
+    fn mapping(&self, n: &LtrfNode) -> Result<&'o HtmlMapping, LapolError> {
+        self.options.tags.get(n.tag()).ok_or_else(|| {
+            LapolError::OutputError(format!("No HTML mapping for LTRF tag `{}`.", n.tag()))
+        })
+    }
+
+    fn is_block(&self, obj: &LtrfObj) -> Result<bool, LapolError> {
+        match obj {
+            LtrfObj::Str(_) => Ok(false),
+            LtrfObj::Node(n) => Ok(match self.mapping(n)? {
+                HtmlMapping::Element(e) => e.block || is_block_node(n),
+                HtmlMapping::Transparent => false,
+                HtmlMapping::Verbatim => is_block_node(n),
+            }),
+        }
+    }
+
+    /// Collects `elems`, replacing transparent nodes by their elements.
+    fn flatten<'t>(
+        &self,
+        elems: &'t [LtrfObj],
+        out: &mut Vec<&'t LtrfObj>,
+    ) -> Result<(), LapolError> {
+        for e in elems {
+            match e {
+                LtrfObj::Node(n) if *self.mapping(n)? == HtmlMapping::Transparent => {
+                    self.flatten(n.elems(), out)?
+                }
+                e => out.push(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn void_end(&self) -> &'static str {
+        if self.options.xhtml {
+            "/>"
+        } else {
+            ">"
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.options.pretty {
+            self.out.push('\n');
+            self.out.push_str(&INDENT.repeat(depth));
+        }
+    }
+
+    /// Returns whether the elements were laid out on their own lines (when
+    /// pretty-printing), in which case the closing tag needs a newline too.
+    fn write_elems(&mut self, elems: &[LtrfObj], depth: usize) -> Result<bool, LapolError> {
+        let mut flat = Vec::new();
+        self.flatten(elems, &mut flat)?;
+
+        let blocks = flat
+            .iter()
+            .map(|e| self.is_block(e))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !self.options.pretty || !blocks.contains(&true) {
+            for e in flat {
+                self.write_obj(e, depth)?;
+            }
+            return Ok(false);
+        }
+
+        let mut i = 0;
+        while i < flat.len() {
+            if blocks[i] {
+                self.newline(depth);
+                self.write_obj(flat[i], depth)?;
+                i += 1;
+                continue;
+            }
+
+            let end = (i..flat.len()).find(|&j| blocks[j]).unwrap_or(flat.len());
+            let run = &flat[i..end];
+            let blank = run
+                .iter()
+                .all(|e| e.as_str().is_some_and(|s| s.trim().is_empty()));
+            if !blank {
+                self.newline(depth);
+                for e in run {
+                    self.write_obj(e, depth)?;
+                }
+            }
+            i = end;
+        }
+
+        Ok(true)
+    }
+
+    fn write_obj(&mut self, obj: &LtrfObj, depth: usize) -> Result<(), LapolError> {
+        match obj {
+            LtrfObj::Str(s) => {
+                self.out.push_str(&escape_text(s));
+                Ok(())
+            }
+            LtrfObj::Node(n) => self.write_node(n, depth),
+        }
+    }
+
+    fn write_node(&mut self, n: &LtrfNode, depth: usize) -> Result<(), LapolError> {
+        let element = match self.mapping(n)? {
+            HtmlMapping::Element(e) => e,
+            HtmlMapping::Transparent => {
+                self.write_elems(n.elems(), depth)?;
+                return Ok(());
+            }
+            HtmlMapping::Verbatim => {
+                self.out.push_str(&verbatim_text(n)?);
+                return Ok(());
+            }
+        };
+
+        self.out.push('<');
+        self.out.push_str(&element.name);
+        write_attrs(&mut self.out, element, n.kv(), self.options.xhtml)?;
+
+        if element.is_void() {
+            if !n.elems().is_empty() {
+                return Err(LapolError::OutputError(format!(
+                    "LTRF tag `{}` is output as <{}>, which can't have content.",
+                    n.tag(),
+                    element.name
+                )));
+            }
+            self.out.push_str(self.void_end());
+            return Ok(());
+        }
+        self.out.push('>');
+
+        if self.write_elems(n.elems(), depth + 1)? {
+            self.newline(depth);
+        }
+        self.out.push_str("</");
+        self.out.push_str(&element.name);
+        self.out.push('>');
+        Ok(())
+    }
+}
+
+fn write_attrs(
+    out: &mut String,
+    element: &HtmlElement,
+    kv: &LtrfKv,
+    xhtml: bool,
+) -> Result<(), LapolError> {
+    let mut attrs: Vec<(&str, Option<String>)> = element
+        .attrs
+        .iter()
+        .map(|(a, v)| (a.as_str(), Some(v.clone())))
+        .collect();
+
+    for (key, val) in kv {
+        if key.starts_with('_') || INTERNAL_KV_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        if !is_valid_attr_name(key) {
+            return Err(LapolError::OutputError(format!(
+                "`{}` is not a valid HTML attribute name.",
+                key
+            )));
+        }
+
+        // `None` is a boolean attribute (no value).
+        let val = match val {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            serde_json::Value::Bool(true) => None,
+            serde_json::Value::Bool(false) | serde_json::Value::Null => {
+                attrs.retain(|(a, _)| a != key);
+                continue;
+            }
+            serde_json::Value::Array(vs) => Some(
+                vs.iter()
+                    .map(|v| match v {
+                        serde_json::Value::String(s) => Ok(s.clone()),
+                        serde_json::Value::Number(n) => Ok(n.to_string()),
+                        _ => Err(bad_attr_value(key)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(" "),
+            ),
+            serde_json::Value::Object(_) => return Err(bad_attr_value(key)),
+        };
+
+        match attrs.iter_mut().find(|(a, _)| a == key) {
+            Some(existing) => existing.1 = val,
+            None => attrs.push((key, val)),
+        }
+    }
+
+    for (attr, val) in attrs {
+        out.push(' ');
+        out.push_str(attr);
+        // In XHTML, a boolean attribute's value is its name.
+        let val = val.or_else(|| xhtml.then(|| attr.to_owned()));
+        if let Some(val) = val {
+            out.push_str("=\"");
+            out.push_str(&escape_attr(&val));
+            out.push('"');
+        }
+    }
+    Ok(())
+}
+
+fn bad_attr_value(key: &str) -> LapolError {
+    LapolError::OutputError(format!(
+        "Can't output kv entry `{}` as an HTML attribute: values must be strings, numbers, \
+         booleans, or arrays of strings and numbers.",
+        key
+    ))
+}
+
+/// See the HTML spec, "13.1.2.3 Attributes".
+fn is_valid_attr_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace()
+                || c.is_control()
+                || matches!(c, '"' | '\'' | '>' | '/' | '=' | '<' | '&')
+        })
+}
@@ -0,0 +1,391 @@
+//! LaTeX output.
+//!
+//! Each LTRF tag is rendered according to a mapping table (see
+//! `default_tag_map`, which mirrors `std/*_latex_output.ts`). Strings are
+//! escaped with `escape`, so that any text comes out as written.
+//!
+//! Standalone documents use the class and package in `deps-lapol`, like
+//! lapol-core's `__root` outputter.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfNode, LtrfObj},
+};
+
+use super::{verbatim_text, VERBATIM_TAG};
+
+/// How an LTRF tag is rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatexMapping {
+    /// `\name{...}`, with the elements as the argument.
+    Command {
+        name: String,
+        /// Whether the command is put in its own paragraph (e.g. `\section`).
+        block: bool,
+    },
+    /// `\name`, without arguments (e.g. `\maketitle`). The node's elements
+    /// are ignored. Inline, it is `\name{}`, so following text isn't taken
+    /// as part of the name.
+    NoArgCommand { name: String, block: bool },
+    /// `\begin{name} ... \end{name}`, in its own paragraph.
+    Environment(String),
+    /// The elements, as a paragraph of their own.
+    Paragraph,
+    /// Only the node's elements are rendered (e.g. for `__root`).
+    Transparent,
+    /// The node's elements are output as is (see `VERBATIM_TAG`).
+    Verbatim,
+}
+
+impl LatexMapping {
+    pub fn command(name: impl Into<String>) -> Self {
+        LatexMapping::Command {
+            name: name.into(),
+            block: false,
+        }
+    }
+
+    pub fn block_command(name: impl Into<String>) -> Self {
+        LatexMapping::Command {
+            name: name.into(),
+            block: true,
+        }
+    }
+
+    pub fn block_no_arg_command(name: impl Into<String>) -> Self {
+        LatexMapping::NoArgCommand {
+            name: name.into(),
+            block: true,
+        }
+    }
+
+    pub fn environment(name: impl Into<String>) -> Self {
+        LatexMapping::Environment(name.into())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LatexOptions {
+    /// LTRF tag -> LaTeX. Rendering a tag that isn't in the table is an error.
+    pub tags: HashMap<String, LatexMapping>,
+    /// Output a full document (preamble, `\begin{document}`, etc.) instead of
+    /// just the body.
+    pub standalone: bool,
+    /// `\title` of a standalone document.
+    pub title: Vec<LtrfObj>,
+    /// `\author` of a standalone document.
+    pub author: Vec<LtrfObj>,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        LatexOptions {
+            tags: default_tag_map(),
+            standalone: false,
+            title: Vec::new(),
+            author: Vec::new(),
+        }
+    }
+}
+
+/// Mappings for the tags produced by the `std` modules and the processing
+/// passes.
+pub fn default_tag_map() -> HashMap<String, LatexMapping> {
+    let mut tags = HashMap::new();
+    let mut add = |tag: &str, mapping: LatexMapping| {
+        tags.insert(tag.to_owned(), mapping);
+    };
+
+    add("__root", LatexMapping::Transparent);
+    add(VERBATIM_TAG, LatexMapping::Verbatim);
+    add("__doc", LatexMapping::Paragraph);
+    add("__p", LatexMapping::Paragraph);
+
+    add("maketitle", LatexMapping::block_no_arg_command("maketitle"));
+
+    add("sec", LatexMapping::block_command("section"));
+    add("subsec", LatexMapping::block_command("subsection"));
+    add("subsubsec", LatexMapping::block_command("subsubsection"));
+
+    add("bold", LatexMapping::command("textbf"));
+    add("italic", LatexMapping::command("textit"));
+
+    add("bquot", LatexMapping::environment("lapoldefaultblockquote"));
+    add("marginnote", LatexMapping::command("lapoldefaultaside"));
+
+    tags
+}
+
+/// Renders a (processed) LTRF root node as LaTeX.
+pub fn render_latex(root: &LtrfNode, options: &LatexOptions) -> Result<String, LapolError> {
+    let mut r = Renderer {
+        options,
+        out: String::new(),
+    };
+
+    if options.standalone {
+        r.out.push_str(concat!(
+            "\\documentclass{deps/lapol_default_article}\n",
+            "\\usepackage{deps/lapol_default}\n",
+            "% Debugging help:\n",
+            "% Trick TeXstudio into loading the commands defined in this file.\n",
+            "\\begin{mcommenthack}\n",
+            "  \\input{deps/lapol_default_article.cls}\n",
+            "  \\input{deps/lapol_default.sty}\n",
+            "\\end{mcommenthack}\n",
+        ));
+        r.out.push_str("\\title{");
+        r.write_elems(&options.title)?;
+        r.out.push_str("}\n\\author{");
+        r.write_elems(&options.author)?;
+        r.out
+            .push_str("}\n\\date{Compiled on \\today}\n\\begin{document}\n");
+    }
+
+    r.write_node(root)?;
+
+    if options.standalone {
+        r.out.truncate(r.out.trim_end_matches('\n').len());
+        r.out.push_str("\n\\end{document}");
+    }
+
+    let mut out = r.out;
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    Ok(out)
+}
+
+/// Escapes text, so that LaTeX outputs it as is.
+///
+/// Besides the special characters (`# $ % & ~ _ ^ \ { }`), this takes care
+/// of `<`, `>` and `|` (which T1 and OT1 get wrong), `--` (which would become
+/// a dash), and Unicode characters that `inputenc` doesn't know.
+pub fn escape(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(needs_escape) && !s.contains("--") {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len() + 16);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '-' if chars.peek() == Some(&'-') => out.push_str("-{}"),
+            c => match escape_char(c) {
+                Some(e) => out.push_str(e),
+                None => out.push(c),
+            },
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn needs_escape(c: char) -> bool {
+    matches!(c, '&' | '%' | '$' | '#' | '_' | '{' | '}') || escape_char(c).is_some()
+}
+
+/// The escaping table, except for the characters that are just preceded by
+/// a backslash.
+fn escape_char(c: char) -> Option<&'static str> {
+    Some(match c {
+        '~' => "\\textasciitilde{}",
+        '^' => "\\textasciicircum{}",
+        '\\' => "\\textbackslash{}",
+        '<' => "\\textless{}",
+        '>' => "\\textgreater{}",
+        '|' => "\\textbar{}",
+
+        '\u{a0}' => "~",
+        '\u{200b}' => "\\hspace{0pt}",
+        '\u{2026}' => "\\dots{}",
+        '\u{2212}' => "\\ensuremath{-}",
+        '\u{2190}' => "\\ensuremath{\\leftarrow}",
+        '\u{2192}' => "\\ensuremath{\\rightarrow}",
+        '\u{2194}' => "\\ensuremath{\\leftrightarrow}",
+        '\u{21d0}' => "\\ensuremath{\\Leftarrow}",
+        '\u{21d2}' => "\\ensuremath{\\Rightarrow}",
+        '\u{21d4}' => "\\ensuremath{\\Leftrightarrow}",
+        '\u{2200}' => "\\ensuremath{\\forall}",
+        '\u{2203}' => "\\ensuremath{\\exists}",
+        '\u{2208}' => "\\ensuremath{\\in}",
+        '\u{221e}' => "\\ensuremath{\\infty}",
+        '\u{2248}' => "\\ensuremath{\\approx}",
+        '\u{2260}' => "\\ensuremath{\\neq}",
+        '\u{2261}' => "\\ensuremath{\\equiv}",
+        '\u{2264}' => "\\ensuremath{\\leq}",
+        '\u{2265}' => "\\ensuremath{\\geq}",
+
+        '\u{391}'..='\u{3c9}' => return greek_letter(c),
+        _ => return None,
+    })
+}
+
+/// Greek letters, as math symbols. (Capitals that look like Latin letters
+/// are output as such, like in math mode.)
+fn greek_letter(c: char) -> Option<&'static str> {
+    Some(match c {
+        'Α' => "A",
+        'Β' => "B",
+        'Ε' => "E",
+        'Ζ' => "Z",
+        'Η' => "H",
+        'Ι' => "I",
+        'Κ' => "K",
+        'Μ' => "M",
+        'Ν' => "N",
+        'Ο' => "O",
+        'Ρ' => "P",
+        'Τ' => "T",
+        'Χ' => "X",
+        'Γ' => "\\ensuremath{\\Gamma}",
+        'Δ' => "\\ensuremath{\\Delta}",
+        'Θ' => "\\ensuremath{\\Theta}",
+        'Λ' => "\\ensuremath{\\Lambda}",
+        'Ξ' => "\\ensuremath{\\Xi}",
+        'Π' => "\\ensuremath{\\Pi}",
+        'Σ' => "\\ensuremath{\\Sigma}",
+        'Υ' => "\\ensuremath{\\Upsilon}",
+        'Φ' => "\\ensuremath{\\Phi}",
+        'Ψ' => "\\ensuremath{\\Psi}",
+        'Ω' => "\\ensuremath{\\Omega}",
+        'α' => "\\ensuremath{\\alpha}",
+        'β' => "\\ensuremath{\\beta}",
+        'γ' => "\\ensuremath{\\gamma}",
+        'δ' => "\\ensuremath{\\delta}",
+        'ε' => "\\ensuremath{\\varepsilon}",
+        'ζ' => "\\ensuremath{\\zeta}",
+        'η' => "\\ensuremath{\\eta}",
+        'θ' => "\\ensuremath{\\theta}",
+        'ι' => "\\ensuremath{\\iota}",
+        'κ' => "\\ensuremath{\\kappa}",
+        'λ' => "\\ensuremath{\\lambda}",
+        'μ' => "\\ensuremath{\\mu}",
+        'ν' => "\\ensuremath{\\nu}",
+        'ξ' => "\\ensuremath{\\xi}",
+        'ο' => "o",
+        'π' => "\\ensuremath{\\pi}",
+        'ρ' => "\\ensuremath{\\rho}",
+        'ς' => "\\ensuremath{\\varsigma}",
+        'σ' => "\\ensuremath{\\sigma}",
+        'τ' => "\\ensuremath{\\tau}",
+        'υ' => "\\ensuremath{\\upsilon}",
+        'φ' => "\\ensuremath{\\varphi}",
+        'χ' => "\\ensuremath{\\chi}",
+        'ψ' => "\\ensuremath{\\psi}",
+        'ω' => "\\ensuremath{\\omega}",
+        _ => return None,
+    })
+}
+
+struct Renderer<'o> {
+    options: &'o LatexOptions,
+    out: String,
+}
+
+impl<'o> Renderer<'o> {
+    fn mapping(&self, n: &LtrfNode) -> Result<&'o LatexMapping, LapolError> {
+        self.options.tags.get(n.tag()).ok_or_else(|| {
+            LapolError::OutputError(format!("No LaTeX mapping for LTRF tag `{}`.", n.tag()))
+        })
+    }
+
+    /// Ends the current line, if it isn't empty.
+    fn end_line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    /// Ends the current paragraph, with a blank line.
+    fn end_paragraph(&mut self) {
+        self.end_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.at_group_start() {
+            self.out.push('\n');
+        }
+    }
+
+    /// Whether we are right after `\begin{...}` (where a blank line is
+    /// unnecessary).
+    fn at_group_start(&self) -> bool {
+        let line = self.out[..self.out.len() - 1]
+            .rsplit('\n')
+            .next()
+            .unwrap_or("");
+        line.starts_with("\\begin{")
+    }
+
+    fn write_elems(&mut self, elems: &[LtrfObj]) -> Result<(), LapolError> {
+        for e in elems {
+            match e {
+                LtrfObj::Str(s) => self.out.push_str(&escape(s)),
+                LtrfObj::Node(n) => self.write_node(n)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_node(&mut self, n: &LtrfNode) -> Result<(), LapolError> {
+        let is_block = n.kv_get("isBlock").and_then(|b| b.as_bool()) == Some(true);
+
+        match self.mapping(n)? {
+            LatexMapping::Transparent => self.write_elems(n.elems())?,
+            LatexMapping::Verbatim => {
+                if is_block {
+                    self.end_paragraph();
+                }
+                self.out.push_str(&verbatim_text(n)?);
+                if is_block {
+                    self.end_paragraph();
+                }
+            }
+            LatexMapping::Paragraph => {
+                self.end_paragraph();
+                self.write_elems(n.elems())?;
+                self.end_paragraph();
+            }
+            LatexMapping::Command { name, block } => {
+                let block = *block || is_block;
+                if block {
+                    self.end_paragraph();
+                }
+                self.out.push('\\');
+                self.out.push_str(name);
+                self.out.push('{');
+                self.write_elems(n.elems())?;
+                self.out.push('}');
+                if block {
+                    self.end_paragraph();
+                }
+            }
+            LatexMapping::NoArgCommand { name, block } => {
+                let block = *block || is_block;
+                if block {
+                    self.end_paragraph();
+                }
+                self.out.push('\\');
+                self.out.push_str(name);
+                if block {
+                    self.end_paragraph();
+                } else {
+                    // Ends the control word, so following text isn't part of it.
+                    self.out.push_str("{}");
+                }
+            }
+            LatexMapping::Environment(name) => {
+                self.end_paragraph();
+                self.out.push_str(&format!("\\begin{{{}}}\n", name));
+                self.write_elems(n.elems())?;
+                self.out.truncate(self.out.trim_end_matches('\n').len());
+                self.out.push_str(&format!("\n\\end{{{}}}", name));
+                self.end_paragraph();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Markdown (CommonMark) output.
+//!
+//! Text is escaped with `escape`, and lines of wrapped paragraphs are escaped
+//! so that they never start a block (list item, heading, etc.), so that any
+//! text comes out as written.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{error::LapolError, ltrf::LtrfNode};
+
+use super::{
+    blocks::{self, Flavor},
+    TextMapping, VERBATIM_TAG,
+};
+
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// LTRF tag -> Markdown. Rendering a tag that isn't in the table is an
+    /// error.
+    pub tags: HashMap<String, TextMapping>,
+    /// Wrap paragraphs at this many columns. `None` puts each paragraph on a
+    /// single line.
+    pub width: Option<usize>,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            tags: default_tag_map(),
+            width: Some(80),
+        }
+    }
+}
+
+/// Mappings for the tags produced by the `std` modules and the processing
+/// passes.
+pub fn default_tag_map() -> HashMap<String, TextMapping> {
+    let mut tags = HashMap::new();
+    let mut add = |tag: &str, mapping: TextMapping| {
+        tags.insert(tag.to_owned(), mapping);
+    };
+
+    add("__root", TextMapping::Transparent);
+    add(VERBATIM_TAG, TextMapping::Verbatim);
+    add("__doc", TextMapping::Block);
+    add("__p", TextMapping::Block);
+
+    // The title and author aren't part of the LTRF tree.
+    add("maketitle", TextMapping::Transparent);
+
+    // `#` is left for the document title.
+    add("sec", TextMapping::Heading(2));
+    add("subsec", TextMapping::Heading(3));
+    add("subsubsec", TextMapping::Heading(4));
+
+    add("bold", TextMapping::inline("**", "**"));
+    add("italic", TextMapping::inline("*", "*"));
+
+    add("bquot", TextMapping::Quote);
+    add("marginnote", TextMapping::inline("(", ")"));
+
+    tags
+}
+
+/// Renders a (processed) LTRF root node as Markdown.
+pub fn render_markdown(root: &LtrfNode, options: &MarkdownOptions) -> Result<String, LapolError> {
+    blocks::render(root, &options.tags, options.width, &Markdown)
+}
+
+/// Escapes the characters that are significant in inline Markdown. (Those
+/// that are only significant at the start of a line are dealt with when
+/// laying out paragraphs.)
+pub fn escape(s: &str) -> Cow<'_, str> {
+    let needs_escape = |c: char| matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '&');
+    if !s.contains(needs_escape) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        if needs_escape(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    Cow::Owned(out)
+}
+
+struct Markdown;
+
+impl Flavor for Markdown {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn escape<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        escape(s)
+    }
+
+    fn heading(&self, level: usize, text: &str) -> Vec<String> {
+        // A trailing `#` would be taken as a closing sequence.
+        let text = match text.strip_suffix('#') {
+            Some(t) => format!("{}\\#", t),
+            None => text.to_owned(),
+        };
+        vec![format!("{} {}", "#".repeat(level.clamp(1, 6)), text)]
+    }
+
+    fn escape_line_start(&self, line: String) -> String {
+        // Headings, quotes, bullet lists, thematic breaks, setext underlines
+        // and code fences.
+        if line.starts_with(['#', '>', '-', '+', '=', '~']) {
+            return format!("\\{}", line);
+        }
+
+        // Ordered lists (`1.` or `1)`).
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &line[digits..];
+        if (1..=9).contains(&digits) && rest.starts_with(['.', ')']) {
+            return format!("{}\\{}", &line[..digits], rest);
+        }
+
+        line
+    }
+}
@@ -0,0 +1,241 @@
+//! Files required by the output (stylesheets, fonts, images, etc.).
+//!
+//! Mirrors `lapol-core/src/internal/out/outRequirements`. Outputters call
+//! `require_file` for each file the output needs; the caller then copies the
+//! files next to the output (`copy_to`), or packages them (e.g. `epub`).
+//!
+//! `copy_to` only writes the files whose contents changed, and never leaves a
+//! half-written file behind, so that concurrent builds into the same
+//! directory don't step on each other. (lapol-core uses it too, through
+//! lapol-rs's `OutputRequirements`.)
+//!
+//! Files are accessed through `OutputFileSystem` (`require_file_with` and
+//! `copy_to_with`), so that e.g. the WASM build can use Node's `fs`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::LapolError;
+
+/// The file system used to compare and copy required files.
+pub trait OutputFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Used to name temporary files, so that concurrent builds don't share
+    /// them.
+    fn process_id(&self) -> u32;
+}
+
+/// Uses `std::fs`. (This won't work in wasm!)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeFileSystem;
+
+impl OutputFileSystem for NativeFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn process_id(&self) -> u32 {
+        std::process::id()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutputRequirementReceiver {
+    /// Target path (relative to the output) -> source path.
+    files: BTreeMap<PathBuf, PathBuf>,
+}
+
+/// What `OutputRequirementReceiver::copy_to` did, by target path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CopyReport {
+    pub copied: Vec<PathBuf>,
+    /// Targets that already had the right contents.
+    pub unchanged: Vec<PathBuf>,
+}
+
+impl OutputRequirementReceiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires that the file at `input` is available at `target` (relative
+    /// to the output). Requiring the same file at the same target again is
+    /// fine, and so is requiring a different file with the same contents, but
+    /// binding a target to files with different contents is an error.
+    pub fn require_file(
+        &mut self,
+        input: impl Into<PathBuf>,
+        target: impl AsRef<Path>,
+    ) -> Result<(), LapolError> {
+        self.require_file_with(&NativeFileSystem, input, target)
+    }
+
+    /// `require_file`, comparing contents through `fs`.
+    pub fn require_file_with(
+        &mut self,
+        fs: &dyn OutputFileSystem,
+        input: impl Into<PathBuf>,
+        target: impl AsRef<Path>,
+    ) -> Result<(), LapolError> {
+        let input = input.into();
+        let target = normalize_target(target.as_ref())?;
+
+        match self.files.get(&target) {
+            Some(existing) if *existing != input && !same_contents(fs, existing, &input) => {
+                Err(LapolError::OutputError(format!(
+                    "Inconsistent output requirements: required {} as {}, but {} is already bound to {}.",
+                    input.display(),
+                    target.display(),
+                    target.display(),
+                    existing.display()
+                )))
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.files.insert(target, input);
+                Ok(())
+            }
+        }
+    }
+
+    /// The required files, as `(target, source)`, sorted by target.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &Path)> {
+        self.files.iter().map(|(t, s)| (t.as_path(), s.as_path()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Copies the required files into `output_dir`, creating directories as
+    /// needed.
+    ///
+    /// Each source is read once, however many targets it is bound to. Targets
+    /// that already have the same contents (by SHA-256) are left alone, and
+    /// the others are written to a temporary file in the same directory,
+    /// which is then renamed over the target.
+    pub fn copy_to(&self, output_dir: impl AsRef<Path>) -> Result<CopyReport, LapolError> {
+        self.copy_to_with(&NativeFileSystem, output_dir)
+    }
+
+    /// `copy_to`, through `fs`.
+    pub fn copy_to_with(
+        &self,
+        fs: &dyn OutputFileSystem,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<CopyReport, LapolError> {
+        let output_dir = output_dir.as_ref();
+        let mut sources: HashMap<&Path, (Vec<u8>, String)> = HashMap::new();
+        let mut report = CopyReport::default();
+
+        for (target, source) in self.files() {
+            if !sources.contains_key(source) {
+                let contents = fs.read(source).map_err(|e| copy_error(source, e))?;
+                let hash = content_hash(&contents);
+                sources.insert(source, (contents, hash));
+            }
+            let (contents, hash) = &sources[source];
+
+            let dest = output_dir.join(target);
+            let up_to_date = fs.read(&dest).is_ok_and(|old| content_hash(&old) == *hash);
+            if up_to_date {
+                report.unchanged.push(target.to_owned());
+            } else {
+                write_atomically(fs, &dest, contents).map_err(|e| copy_error(&dest, e))?;
+                report.copied.push(target.to_owned());
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// SHA-256 of `contents`, as lowercase hex.
+pub fn content_hash(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn same_contents(fs: &dyn OutputFileSystem, a: &Path, b: &Path) -> bool {
+    match (fs.read(a), fs.read(b)) {
+        (Ok(a), Ok(b)) => content_hash(&a) == content_hash(&b),
+        _ => false,
+    }
+}
+
+fn write_atomically(fs: &dyn OutputFileSystem, dest: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = dest.parent().unwrap_or_else(|| Path::new("."));
+    fs.create_dir_all(dir)?;
+
+    // The process id keeps concurrent builds from sharing a temporary file.
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{}.{}.tmp", name, fs.process_id()));
+
+    fs.write(&tmp, contents)
+        .and_then(|_| fs.rename(&tmp, dest))
+        .inspect_err(|_| {
+            let _ = fs.remove_file(&tmp);
+        })
+}
+
+fn copy_error(path: &Path, e: io::Error) -> LapolError {
+    LapolError::OutputError(format!(
+        "Failed to copy output requirement {}: {}",
+        path.display(),
+        e
+    ))
+}
+
+/// Lexically normalizes a target path, which must stay inside the output
+/// directory.
+fn normalize_target(target: &Path) -> Result<PathBuf, LapolError> {
+    let mut out = PathBuf::new();
+
+    for component in target.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(c) => out.push(c),
+            Component::ParentDir if out.pop() => {}
+            _ => {
+                return Err(LapolError::OutputError(format!(
+                    "Output requirement target {} is outside the output directory.",
+                    target.display()
+                )))
+            }
+        }
+    }
+
+    if out.as_os_str().is_empty() {
+        return Err(LapolError::OutputError(
+            "Output requirement target is empty.".to_owned(),
+        ));
+    }
+    Ok(out)
+}
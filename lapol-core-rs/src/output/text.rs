@@ -0,0 +1,86 @@
+//! Plain text output (e.g. for emails, or reviewing diffs).
+//!
+//! Paragraphs are wrapped, headings are underlined, and quotes are prefixed
+//! with `> `. Text isn't escaped.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{error::LapolError, ltrf::LtrfNode};
+
+use super::{
+    blocks::{self, width_of, Flavor},
+    TextMapping, VERBATIM_TAG,
+};
+
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    /// LTRF tag -> text. Rendering a tag that isn't in the table is an error.
+    pub tags: HashMap<String, TextMapping>,
+    /// Wrap paragraphs at this many columns. `None` puts each paragraph on a
+    /// single line.
+    pub width: Option<usize>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            tags: default_tag_map(),
+            width: Some(80),
+        }
+    }
+}
+
+/// Mappings for the tags produced by the `std` modules and the processing
+/// passes.
+pub fn default_tag_map() -> HashMap<String, TextMapping> {
+    let mut tags = HashMap::new();
+    let mut add = |tag: &str, mapping: TextMapping| {
+        tags.insert(tag.to_owned(), mapping);
+    };
+
+    add("__root", TextMapping::Transparent);
+    add(VERBATIM_TAG, TextMapping::Verbatim);
+    add("__doc", TextMapping::Block);
+    add("__p", TextMapping::Block);
+
+    // The title and author aren't part of the LTRF tree.
+    add("maketitle", TextMapping::Transparent);
+
+    add("sec", TextMapping::Heading(1));
+    add("subsec", TextMapping::Heading(2));
+    add("subsubsec", TextMapping::Heading(3));
+
+    add("bold", TextMapping::inline("*", "*"));
+    add("italic", TextMapping::inline("_", "_"));
+
+    add("bquot", TextMapping::Quote);
+    add("marginnote", TextMapping::inline("[", "]"));
+
+    tags
+}
+
+/// Renders a (processed) LTRF root node as plain text.
+pub fn render_text(root: &LtrfNode, options: &TextOptions) -> Result<String, LapolError> {
+    blocks::render(root, &options.tags, options.width, &PlainText)
+}
+
+struct PlainText;
+
+impl Flavor for PlainText {
+    fn name(&self) -> &'static str {
+        "plain text"
+    }
+
+    fn escape<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        Cow::Borrowed(s)
+    }
+
+    /// Levels 1 and 2 are underlined with `=` and `-`, like in Setext.
+    fn heading(&self, level: usize, text: &str) -> Vec<String> {
+        match level {
+            1 => vec![text.to_owned(), "=".repeat(width_of(text))],
+            2 => vec![text.to_owned(), "-".repeat(width_of(text))],
+            _ => vec![text.to_owned()],
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Processing, AKA the "Middle Pass"
+//!
+//! Mirrors `lapol-core/src/internal/process`. The passes take in an LTRF
+//! root node, and return a possibly modified LTRF root node. They produce
+//! the same output as their TypeScript counterparts.
+
+use crate::{error::LapolError, ltrf::LtrfNode};
+
+mod process_linebreaks;
+mod process_paragraphs;
+mod process_root;
+mod rem_whitespace_lines;
+
+pub use process_linebreaks::{process_linebreaks, BREAK_MARKER_TAG};
+pub use process_paragraphs::{process_paragraphs, PARAGRAPH_TAG};
+pub use process_root::process_root;
+pub use rem_whitespace_lines::process_remove_whitespace_lines;
+
+pub type RootProcessingFunction = fn(&LtrfNode) -> Result<LtrfNode, LapolError>;
+
+/// The passes run by `process_pass`, in order.
+pub const PROCESSING_PASSES: &[RootProcessingFunction] = &[
+    process_root,
+    process_remove_whitespace_lines,
+    process_linebreaks,
+    process_paragraphs,
+];
+
+pub fn process_pass(ltrf_root_node: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    let mut out = ltrf_root_node.clone();
+
+    for pass in PROCESSING_PASSES {
+        out = pass(&out)?;
+    }
+
+    Ok(out)
+}
+
+/// Same as `isWhitespace` in lapol-core (`str.trim() === ""`).
+pub(crate) fn is_whitespace(s: &str) -> bool {
+    s.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+        .is_empty()
+}
@@ -0,0 +1,57 @@
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+};
+
+pub const BREAK_MARKER_TAG: &str = "__para_br_marker";
+
+pub fn process_linebreaks(root: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    Ok(process_linebreaks_helper(root))
+}
+
+fn process_linebreaks_helper(n: &LtrfNode) -> LtrfNode {
+    let n2 = n.map_elems(|e, _| match e {
+        LtrfObj::Node(n) => process_linebreaks_helper(n).into(),
+        s => s.clone(),
+    });
+    if n2.elems().len() <= 1 {
+        return n2;
+    }
+
+    // New elements helper. `None` indicates a linebreak.
+    let mut h: Vec<Option<LtrfObj>> = Vec::with_capacity(n2.elems().len());
+
+    let elems = n2.elems();
+    for (i, sub_node) in elems.iter().enumerate() {
+        if is_newline(sub_node) {
+            if elems.get(i + 1).is_some_and(is_newline) {
+                // If we have already emitted a linebreak, or if we are at the
+                // start of the node elements, either way we need not emit a
+                // linebreak.
+                let at_start = h.is_empty();
+                let last_elem_is_already_linebreak = matches!(h.last(), Some(None));
+                if !at_start && !last_elem_is_already_linebreak {
+                    h.push(None);
+                }
+            } else {
+                // Newline not followed by newline should become space.
+                h.push(Some(" ".into()));
+            }
+        } else {
+            h.push(Some(sub_node.clone()));
+        }
+    }
+
+    let final_elems = h
+        .into_iter()
+        .map(|v| {
+            v.unwrap_or_else(|| LtrfNode::make(BREAK_MARKER_TAG, LtrfKv::new(), vec![]).into())
+        })
+        .collect();
+
+    n2.with_elems(final_elems)
+}
+
+fn is_newline(o: &LtrfObj) -> bool {
+    o.as_str() == Some("\n")
+}
@@ -0,0 +1,114 @@
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+};
+
+use super::{is_whitespace, BREAK_MARKER_TAG};
+
+pub const PARAGRAPH_TAG: &str = "__p";
+
+pub fn process_paragraphs(root: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    process_paragraphs_helper(root)
+}
+
+fn process_paragraphs_helper(n: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    let elems = n
+        .elems()
+        .iter()
+        .map(|e| match e {
+            LtrfObj::Node(n) => process_paragraphs_helper(n).map(LtrfObj::from),
+            s => Ok(s.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out_elems = Vec::with_capacity(elems.len());
+    let mut para_accum = Vec::new();
+    let mut num_paras = 0;
+
+    for elem in elems {
+        if is_break_marker(&elem) {
+            if !para_accum.is_empty() {
+                out_elems.push(make_para(std::mem::take(&mut para_accum)));
+                num_paras += 1;
+            }
+        } else if is_block(&elem)? {
+            if !para_accum.is_empty() {
+                out_elems.push(make_para(std::mem::take(&mut para_accum)));
+                num_paras += 1;
+            }
+            out_elems.push(elem);
+        } else {
+            para_accum.push(elem);
+        }
+    }
+
+    // If there are still things remaining in the accumulator...
+    if !para_accum.is_empty() {
+        if num_paras >= 1 {
+            // And there is already a paragraph, make another one.
+            out_elems.push(make_para(para_accum));
+        } else {
+            // Else, don't make a paragraph if there is no need.
+            out_elems.extend(para_accum);
+        }
+    }
+
+    Ok(n.with_elems(trim_paras(out_elems)))
+}
+
+/// Trim paragraph nodes (remove starting and ending whitespace Str nodes),
+/// and filter out any paragraphs that become empty as a result (i.e. which
+/// were only whitespace before). Non paragraph nodes should be unaffected.
+fn trim_paras(n: Vec<LtrfObj>) -> Vec<LtrfObj> {
+    n.into_iter()
+        .map(trim_single_para_node)
+        .filter(is_not_empty_para)
+        .collect()
+}
+
+fn trim_single_para_node(n: LtrfObj) -> LtrfObj {
+    match &n {
+        LtrfObj::Node(node) if is_para(&n) => {
+            let is_ws = |o: &LtrfObj| o.as_str().is_some_and(is_whitespace);
+            let elems = node.elems();
+            let start = elems.iter().position(|o| !is_ws(o)).unwrap_or(elems.len());
+            let end = elems
+                .iter()
+                .rposition(|o| !is_ws(o))
+                .map_or(start, |i| i + 1);
+            node.with_elems(elems[start..end].to_vec()).into()
+        }
+        _ => n,
+    }
+}
+
+fn is_not_empty_para(n: &LtrfObj) -> bool {
+    !(is_para(n) && n.as_node().is_some_and(|n| n.elems().is_empty()))
+}
+
+fn make_para(elems: Vec<LtrfObj>) -> LtrfObj {
+    let mut kv = LtrfKv::new();
+    kv.insert("isBlock".to_owned(), true.into());
+    LtrfNode::make(PARAGRAPH_TAG, kv, elems).into()
+}
+
+fn is_para(n: &LtrfObj) -> bool {
+    matches!(n, LtrfObj::Node(n) if n.tag() == PARAGRAPH_TAG)
+}
+
+fn is_break_marker(n: &LtrfObj) -> bool {
+    matches!(n, LtrfObj::Node(n) if n.tag() == BREAK_MARKER_TAG)
+}
+
+fn is_block(n: &LtrfObj) -> Result<bool, LapolError> {
+    match n {
+        LtrfObj::Node(n) => match n.kv_get("isBlock") {
+            None | Some(serde_json::Value::Null) => Ok(false),
+            Some(serde_json::Value::Bool(b)) => Ok(*b),
+            Some(_) => Err(LapolError::ProcessError(
+                "LtrfNode KV isBlock should be a boolean.".to_owned(),
+            )),
+        },
+        LtrfObj::Str(_) => Ok(false),
+    }
+}
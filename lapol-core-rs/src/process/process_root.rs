@@ -0,0 +1,36 @@
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfNode, LtrfObj},
+};
+
+use super::is_whitespace;
+
+pub fn process_root(root: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    if root.tag() != "__root" {
+        return Err(LapolError::ProcessError("Expected __root node.".to_owned()));
+    }
+
+    // Check for potential mistakes
+    for n in root.elems() {
+        match n {
+            LtrfObj::Str(s) if !is_whitespace(s) => {
+                return Err(LapolError::ProcessError(
+                    "__root has string that isn't whitespace -- probably a mistake.".to_owned(),
+                ))
+            }
+            LtrfObj::Node(n) if n.tag() != "__doc" => {
+                return Err(LapolError::ProcessError(
+                    "__root has Node that isn't __doc -- probably a mistake.".to_owned(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(root.update_elems(|sub| {
+        sub.iter()
+            .filter(|o| matches!(o, LtrfObj::Node(n) if n.tag() == "__doc"))
+            .cloned()
+            .collect()
+    }))
+}
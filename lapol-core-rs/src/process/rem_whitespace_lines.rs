@@ -0,0 +1,19 @@
+use crate::{
+    error::LapolError,
+    ltrf::{LtrfNode, LtrfObj},
+};
+
+use super::is_whitespace;
+
+pub fn process_remove_whitespace_lines(root: &LtrfNode) -> Result<LtrfNode, LapolError> {
+    Ok(rem_whitespace(root))
+}
+
+fn rem_whitespace(n: &LtrfNode) -> LtrfNode {
+    n.flat_map_elems(|el, _| match el {
+        // Newlines aren't removed because they affect paragraph handling.
+        LtrfObj::Str(s) if &**s != "\n" && is_whitespace(s) => None,
+        LtrfObj::Str(_) => Some(el.clone()),
+        LtrfObj::Node(n) => Some(rem_whitespace(n).into()),
+    })
+}
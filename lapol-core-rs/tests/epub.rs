@@ -0,0 +1,165 @@
+use std::{
+    fs,
+    io::{Cursor, Read},
+};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::{
+        epub::{split_on_sections, Epub, EpubChapter, EpubMetadata, NavEntry},
+        html::HtmlOptions,
+    },
+};
+use zip::{CompressionMethod, ZipArchive};
+
+fn node(tag: &str, elems: Vec<LtrfObj>) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems).into()
+}
+
+fn para(text: &str) -> LtrfObj {
+    node("__p", vec![text.into()])
+}
+
+fn root(elems: Vec<LtrfObj>) -> LtrfNode {
+    LtrfNode::make("__root", LtrfKv::new(), vec![node("__doc", elems)])
+}
+
+fn metadata() -> EpubMetadata {
+    EpubMetadata {
+        identifier: "urn:lapol:test".to_owned(),
+        title: "A & B".to_owned(),
+        authors: vec!["Someone".to_owned()],
+        language: "en".to_owned(),
+        modified: "2020-01-01T00:00:00Z".to_owned(),
+    }
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut s = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut s)
+        .unwrap();
+    s
+}
+
+#[test]
+fn splits_on_sections() {
+    let r = root(vec![
+        para("Preface"),
+        node("sec", vec!["One".into()]),
+        para("a"),
+        node("sec", vec!["Two".into()]),
+        node("subsec", vec!["Two point one".into()]),
+        para("b"),
+    ]);
+
+    let parts = split_on_sections(&r);
+    assert_eq!(parts.len(), 3);
+
+    let options = HtmlOptions::default();
+    let chapters: Vec<_> = parts
+        .iter()
+        .map(|p| EpubChapter::from_ltrf(p, "Untitled", &options).unwrap())
+        .collect();
+
+    assert_eq!(chapters[0].title, "Untitled");
+    assert_eq!(chapters[0].body, "<div><p>Preface</p></div>");
+    assert_eq!(chapters[1].title, "One");
+    assert_eq!(
+        chapters[1].body,
+        r#"<div><h2 id="sec-1">One</h2><p>a</p></div>"#
+    );
+    assert_eq!(chapters[2].title, "Two");
+    assert_eq!(
+        chapters[2].headings,
+        vec![NavEntry {
+            title: "Two point one".to_owned(),
+            id: "sec-2".to_owned(),
+            level: 2,
+        }]
+    );
+}
+
+#[test]
+fn writes_epub_archive() {
+    let dir = tempfile::tempdir().unwrap();
+    let image = dir.path().join("cover.png");
+    fs::write(&image, b"not really a png").unwrap();
+
+    let options = HtmlOptions::default();
+    let mut epub = Epub::new(metadata());
+    for part in split_on_sections(&root(vec![
+        node("sec", vec!["One".into()]),
+        para("a"),
+        node("sec", vec!["Two".into()]),
+        node("subsec", vec!["Two point one".into()]),
+    ])) {
+        epub.chapters
+            .push(EpubChapter::from_ltrf(&part, "Untitled", &options).unwrap());
+    }
+    epub.requirements
+        .require_file(&image, "images/cover.png")
+        .unwrap();
+
+    let bytes = epub.write(Cursor::new(Vec::new())).unwrap().into_inner();
+
+    // The mimetype is the first entry, stored, so that it can be read at a
+    // fixed offset.
+    assert_eq!(&bytes[30..38], b"mimetype");
+    assert_eq!(&bytes[38..58], b"application/epub+zip");
+
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let names: Vec<_> = archive.file_names().map(str::to_owned).collect();
+    for name in [
+        "mimetype",
+        "META-INF/container.xml",
+        "OEBPS/content.opf",
+        "OEBPS/nav.xhtml",
+        "OEBPS/deps/lapol-default.css",
+        "OEBPS/chapter-1.xhtml",
+        "OEBPS/chapter-2.xhtml",
+        "OEBPS/images/cover.png",
+    ] {
+        assert!(names.iter().any(|n| n == name), "missing {}", name);
+    }
+    let first = archive.by_index(0).unwrap();
+    assert_eq!(first.name(), "mimetype");
+    assert_eq!(first.compression(), CompressionMethod::Stored);
+    drop(first);
+
+    let opf = read_entry(&mut archive, "OEBPS/content.opf");
+    assert!(opf.contains("<dc:title>A &amp; B</dc:title>"));
+    assert!(opf.contains("<dc:creator>Someone</dc:creator>"));
+    assert!(opf.contains(r#"<meta property="dcterms:modified">2020-01-01T00:00:00Z</meta>"#));
+    assert!(opf.contains(
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#
+    ));
+    assert!(opf.contains(r#"<item id="asset-1" href="images/cover.png" media-type="image/png"/>"#));
+    assert!(opf.contains(
+        "<spine>\n    <itemref idref=\"chapter-1\"/>\n    <itemref idref=\"chapter-2\"/>\n  </spine>"
+    ));
+
+    let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+    assert!(nav.contains(
+        "<ol>
+<li><a href=\"chapter-1.xhtml\">One</a></li>
+<li><a href=\"chapter-2.xhtml\">Two</a>
+<ol>
+<li><a href=\"chapter-2.xhtml#sec-2\">Two point one</a></li>
+</ol>
+</li>
+</ol>"
+    ));
+
+    let chapter = read_entry(&mut archive, "OEBPS/chapter-1.xhtml");
+    assert!(chapter.starts_with("<?xml"));
+    assert!(chapter.contains(r#"<link rel="stylesheet" href="deps/lapol-default.css"/>"#));
+    assert!(chapter.contains(r#"<h2 id="sec-1">One</h2>"#));
+
+    assert_eq!(
+        read_entry(&mut archive, "OEBPS/images/cover.png"),
+        "not really a png"
+    );
+}
@@ -0,0 +1,200 @@
+use std::{fs, path::Path};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::{
+        html::{escape_text, render_html, HtmlElement, HtmlMapping, HtmlOptions},
+        VERBATIM_TAG,
+    },
+};
+use serde_json::json;
+
+fn kv(v: serde_json::Value) -> LtrfKv {
+    match v {
+        serde_json::Value::Object(m) => m,
+        _ => panic!("kv must be an object"),
+    }
+}
+
+fn node(tag: &str, elems: Vec<LtrfObj>) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems).into()
+}
+
+fn root(elems: Vec<LtrfObj>) -> LtrfNode {
+    LtrfNode::make("__root", LtrfKv::new(), elems)
+}
+
+#[test]
+fn renders_mapped_tags() {
+    let r = root(vec![node(
+        "__doc",
+        vec![
+            node("sec", vec!["Title".into()]),
+            node(
+                "__p",
+                vec![
+                    "Some ".into(),
+                    node("bold", vec!["bold".into()]),
+                    " text".into(),
+                ],
+            ),
+        ],
+    )]);
+
+    assert_eq!(
+        render_html(&r, &HtmlOptions::default()).unwrap(),
+        "<div><h2>Title</h2><p>Some <strong>bold</strong> text</p></div>"
+    );
+}
+
+#[test]
+fn escapes_text_and_attributes() {
+    assert_eq!(
+        escape_text(r#"<This is ≠ a "tag" & more>"#),
+        r#"&lt;This is ≠ a "tag" &amp; more&gt;"#
+    );
+
+    let mut options = HtmlOptions::default();
+    options
+        .tags
+        .insert("link".to_owned(), HtmlElement::inline("a").into());
+    let r = root(vec![LtrfNode::make(
+        "link",
+        kv(json!({"href": "a?b=1&c=\"2\"", "isBlock": false, "_internal": 1})),
+        vec!["<x>".into()],
+    )
+    .into()]);
+
+    assert_eq!(
+        render_html(&r, &options).unwrap(),
+        r#"<a href="a?b=1&amp;c=&quot;2&quot;">&lt;x&gt;</a>"#
+    );
+}
+
+#[test]
+fn kv_becomes_attributes() {
+    let mut options = HtmlOptions::default();
+    options.tags.insert(
+        "img".to_owned(),
+        HtmlElement::inline("img").with_attr("alt", "").into(),
+    );
+    options.tags.insert(
+        "box".to_owned(),
+        HtmlElement::block("div").with_attr("class", "box").into(),
+    );
+
+    let r = root(vec![
+        LtrfNode::make(
+            "img",
+            kv(json!({"src": "a.png", "width": 10, "hidden": true, "alt": null})),
+            vec![],
+        )
+        .into(),
+        LtrfNode::make("box", kv(json!({"class": ["a", "b"]})), vec![]).into(),
+    ]);
+
+    assert_eq!(
+        render_html(&r, &options).unwrap(),
+        r#"<img src="a.png" width="10" hidden><div class="a b"></div>"#
+    );
+}
+
+#[test]
+fn rejects_bad_input() {
+    let options = HtmlOptions::default();
+
+    let unknown = root(vec![node("no_such_tag", vec![])]);
+    assert!(render_html(&unknown, &options).is_err());
+
+    let bad_attr = root(vec![LtrfNode::make(
+        "bold",
+        kv(json!({"on click": "x"})),
+        vec![],
+    )
+    .into()]);
+    assert!(render_html(&bad_attr, &options).is_err());
+
+    let mut options = HtmlOptions::default();
+    options
+        .tags
+        .insert("br".to_owned(), HtmlElement::inline("br").into());
+    let void_with_content = root(vec![node("br", vec!["x".into()])]);
+    assert!(render_html(&void_with_content, &options).is_err());
+}
+
+#[test]
+fn transparent_mapping_renders_only_elements() {
+    let mut options = HtmlOptions::default();
+    options
+        .tags
+        .insert("group".to_owned(), HtmlMapping::Transparent);
+    let r = root(vec![node(
+        "group",
+        vec!["a".into(), node("italic", vec!["b".into()])],
+    )]);
+
+    assert_eq!(render_html(&r, &options).unwrap(), "a<em>b</em>");
+}
+
+#[test]
+fn pretty_prints_processed_tree() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/process/blocks.out.json");
+    let r: LtrfNode = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+    let mut options = HtmlOptions {
+        pretty: true,
+        ..HtmlOptions::default()
+    };
+    // The real `maketitle` needs the title and author, which aren't in LTRF.
+    options
+        .tags
+        .insert("maketitle".to_owned(), HtmlElement::block("header").into());
+    assert_eq!(
+        render_html(&r, &options).unwrap(),
+        "<div>
+  <h2>Title</h2>
+  <p>Intro text <em>with newline</em></p>
+  <blockquote>
+    <p>Quoted one.</p>
+    <p>Quoted two.</p>
+  </blockquote>
+  <p>Trailing</p>
+  <header></header>
+  <p><aside>note</aside></p>
+</div>
+"
+    );
+}
+
+#[test]
+fn standalone_document() {
+    let options = HtmlOptions {
+        standalone: true,
+        stylesheets: vec!["style.css".to_owned()],
+        ..HtmlOptions::default()
+    };
+    let r = root(vec![node("__doc", vec!["hi".into()])]);
+
+    assert_eq!(
+        render_html(&r, &options).unwrap(),
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><link rel="stylesheet" href="style.css"></head><body><article class="page"><div>hi</div></article></body></html>"#
+    );
+}
+
+#[test]
+fn verbatim_nodes_are_not_escaped() {
+    let r = root(vec![node(
+        "__p",
+        vec![
+            "a < b ".into(),
+            node(VERBATIM_TAG, vec!["<hr class=\"x\">".into()]),
+        ],
+    )]);
+    assert_eq!(
+        render_html(&r, &HtmlOptions::default()).unwrap(),
+        "<p>a &lt; b <hr class=\"x\"></p>"
+    );
+
+    let bad = root(vec![node(VERBATIM_TAG, vec![node("bold", vec![])])]);
+    assert!(render_html(&bad, &HtmlOptions::default()).is_err());
+}
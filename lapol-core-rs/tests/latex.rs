@@ -0,0 +1,85 @@
+//! Golden tests: each `tests/fixtures/latex/<name>.in.json` (a processed LTRF
+//! tree) is rendered as a standalone document and compared with
+//! `<name>.tex`. Nothing is compiled.
+
+use std::{fs, path::Path};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode},
+    output::latex::{escape, render_latex, LatexMapping, LatexOptions},
+};
+
+#[test]
+fn render_latex_matches_golden_output() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/latex");
+    let options = LatexOptions {
+        standalone: true,
+        title: vec!["Golden & test".into()],
+        author: vec!["LaPoL".into()],
+        ..LatexOptions::default()
+    };
+    let mut count = 0;
+
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let fixture = match name.strip_suffix(".in.json") {
+            Some(f) => f,
+            None => continue,
+        };
+
+        let input: LtrfNode = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let out = render_latex(&input, &options).unwrap();
+
+        let expected = fs::read_to_string(dir.join(format!("{}.tex", fixture))).unwrap();
+        assert_eq!(out, expected, "fixture {}", fixture);
+        count += 1;
+    }
+
+    assert!(count > 0, "no fixtures found");
+}
+
+#[test]
+fn escapes_special_characters() {
+    assert_eq!(
+        escape("Hello, world!\nHello, LaTeX!"),
+        "Hello, world!\nHello, LaTeX!"
+    );
+    assert_eq!(
+        escape("& % $ # _ { } ~ ^ \\"),
+        r"\& \% \$ \# \_ \{ \} \textasciitilde{} \textasciicircum{} \textbackslash{}"
+    );
+    assert_eq!(escape("a--b"), "a-{}-b");
+    assert_eq!(escape("≠"), r"\ensuremath{\neq}");
+}
+
+#[test]
+fn custom_mappings() {
+    let mut options = LatexOptions::default();
+    options
+        .tags
+        .insert("code".to_owned(), LatexMapping::command("texttt"));
+    options
+        .tags
+        .insert("center".to_owned(), LatexMapping::environment("center"));
+
+    let code = LtrfNode::make("code", LtrfKv::new(), vec!["x_1".into()]);
+    let center = LtrfNode::make("center", LtrfKv::new(), vec!["Hi ".into(), code.into()]);
+    let root = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec!["Before".into(), center.into()],
+    );
+
+    assert_eq!(
+        render_latex(&root, &options).unwrap(),
+        "Before\n\n\\begin{center}\nHi \\texttt{x\\_1}\n\\end{center}\n"
+    );
+
+    let unknown = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec![LtrfNode::make("nope", LtrfKv::new(), vec![]).into()],
+    );
+    assert!(render_latex(&unknown, &options).is_err());
+}
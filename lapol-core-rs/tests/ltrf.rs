@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use lapol_core_rs::ltrf::{LtrfKv, LtrfNode, LtrfObj};
+use serde_json::json;
+
+fn kv(v: serde_json::Value) -> LtrfKv {
+    match v {
+        serde_json::Value::Object(m) => m,
+        _ => panic!("kv must be an object"),
+    }
+}
+
+#[test]
+fn can_be_updated_by_replacing_parts() {
+    let n = LtrfNode::make("abc", kv(json!({"a": "b"})), vec!["d".into()]);
+
+    let n2 = n.with_tag("def");
+    assert_eq!(n2.tag(), "def");
+    assert!(std::ptr::eq(n2.elems(), n.elems()));
+    assert!(std::ptr::eq(n2.kv(), n.kv()));
+
+    let n3 = n2.update_kv(|old| {
+        let mut new = old.clone();
+        new.insert("c".to_owned(), json!("d"));
+        new
+    });
+    assert_eq!(n3.kv(), &kv(json!({"a": "b", "c": "d"})));
+    assert!(std::ptr::eq(n3.elems(), n2.elems()));
+
+    let n4 = n3.flat_map_elems(|e, _| vec![e.clone(), "e".into()]);
+    assert_eq!(n4.elems(), &["d".into(), "e".into()] as &[LtrfObj]);
+    assert_eq!(n.elems(), &["d".into()] as &[LtrfObj]);
+}
+
+#[test]
+fn serializes_like_dbg_stringify() {
+    let n = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec![
+            "a".into(),
+            LtrfNode::make("sec", kv(json!({"isBlock": true})), vec!["b".into()]).into(),
+        ],
+    );
+
+    // Output of `JSON.stringify(node, null, "  ")` in lapol-core.
+    let expected = r#"{
+  "_tag": "__root",
+  "_kv": {},
+  "_elems": [
+    "a",
+    {
+      "_tag": "sec",
+      "_kv": {
+        "isBlock": true
+      },
+      "_elems": [
+        "b"
+      ]
+    }
+  ]
+}"#;
+    assert_eq!(n.dbg_stringify(), expected);
+
+    let back: LtrfNode = serde_json::from_str(expected).unwrap();
+    assert_eq!(back, n);
+    assert_eq!(
+        back.elems()[0],
+        LtrfObj::Str(Arc::from("a")),
+        "strings deserialize as LtrfStr"
+    );
+}
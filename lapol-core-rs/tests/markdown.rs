@@ -0,0 +1,97 @@
+use std::{fs, path::Path};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::{
+        markdown::{escape, render_markdown, MarkdownOptions},
+        TextMapping,
+    },
+};
+
+fn node(tag: &str, elems: Vec<LtrfObj>) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems).into()
+}
+
+fn root(elems: Vec<LtrfObj>) -> LtrfNode {
+    LtrfNode::make("__root", LtrfKv::new(), vec![node("__doc", elems)])
+}
+
+#[test]
+fn renders_std_tags() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/process/blocks.out.json");
+    let r: LtrfNode = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+    assert_eq!(
+        render_markdown(&r, &MarkdownOptions::default()).unwrap(),
+        "## Title
+
+Intro text *with newline*
+
+> Quoted one.
+>
+> Quoted two.
+
+Trailing
+
+(note)
+"
+    );
+}
+
+#[test]
+fn escapes_markdown() {
+    assert_eq!(
+        escape(r"*a* _b_ `c` [d](e) <f> & \"),
+        r"\*a\* \_b\_ \`c\` \[d\](e) \<f> \& \\"
+    );
+
+    let r = root(vec![
+        node("sec", vec!["C#".into()]),
+        node(
+            "__p",
+            vec![
+                node("bold", vec![" spaced ".into()]),
+                "x".into(),
+                node("italic", vec![" ".into()]),
+            ],
+        ),
+    ]);
+    assert_eq!(
+        render_markdown(&r, &MarkdownOptions::default()).unwrap(),
+        "## C\\#\n\n**spaced** x\n"
+    );
+}
+
+#[test]
+fn wrapped_lines_never_start_blocks() {
+    let r = root(vec![node(
+        "__p",
+        vec!["aaaa # bbbb - cccc 1. dddd > eeee 12) ffff".into()],
+    )]);
+    let options = MarkdownOptions {
+        width: Some(6),
+        ..MarkdownOptions::default()
+    };
+
+    assert_eq!(
+        render_markdown(&r, &options).unwrap(),
+        "aaaa\n\\# bbbb\n\\- cccc\n1\\.\ndddd\n\\> eeee\n12\\)\nffff\n"
+    );
+}
+
+#[test]
+fn custom_mappings() {
+    let mut options = MarkdownOptions::default();
+    options
+        .tags
+        .insert("code".to_owned(), TextMapping::inline("`", "`"));
+
+    let r = root(vec![node(
+        "__p",
+        vec!["Run ".into(), node("code", vec!["ls".into()])],
+    )]);
+    assert_eq!(render_markdown(&r, &options).unwrap(), "Run `ls`\n");
+
+    let unknown = root(vec![node("nope", vec![])]);
+    assert!(render_markdown(&unknown, &options).is_err());
+}
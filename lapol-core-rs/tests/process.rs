@@ -0,0 +1,31 @@
+//! Golden tests: the fixtures in `tests/fixtures/process` are shared with
+//! lapol-core (see `process.test.ts`), so that both implementations are
+//! checked against the same expected output.
+
+use std::{fs, path::Path};
+
+use lapol_core_rs::{ltrf::LtrfNode, process::process_pass};
+
+#[test]
+fn process_pass_matches_golden_output() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/process");
+    let mut count = 0;
+
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let fixture = match name.strip_suffix(".in.json") {
+            Some(f) => f,
+            None => continue,
+        };
+
+        let input: LtrfNode = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let expected = fs::read_to_string(dir.join(format!("{}.out.json", fixture))).unwrap();
+
+        let out = process_pass(&input).unwrap();
+        assert_eq!(out.dbg_stringify() + "\n", expected, "fixture {}", fixture);
+        count += 1;
+    }
+
+    assert!(count > 0, "no fixtures found");
+}
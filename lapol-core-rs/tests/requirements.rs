@@ -0,0 +1,158 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use lapol_core_rs::output::requirements::{
+    content_hash, CopyReport, OutputFileSystem, OutputRequirementReceiver,
+};
+
+#[test]
+fn normalizes_targets_and_rejects_conflicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.png");
+    let b = dir.path().join("b.png");
+    let a_copy = dir.path().join("a-copy.png");
+    fs::write(&a, "a").unwrap();
+    fs::write(&b, "b").unwrap();
+    fs::write(&a_copy, "a").unwrap();
+
+    let mut reqs = OutputRequirementReceiver::new();
+    reqs.require_file(&a, "img/a.png").unwrap();
+    reqs.require_file(&a, "./img/x/../a.png").unwrap();
+    // Same contents, so not a conflict.
+    reqs.require_file(&a_copy, "img/a.png").unwrap();
+
+    assert!(reqs.require_file(&b, "img/a.png").is_err());
+    assert!(reqs.require_file(&b, "../b.png").is_err());
+    assert!(reqs.require_file(&b, "img/..").is_err());
+
+    let files: Vec<_> = reqs.files().collect();
+    assert_eq!(
+        files,
+        vec![(std::path::Path::new("img/a.png"), a.as_path())]
+    );
+}
+
+#[test]
+fn copies_only_changed_files() {
+    let src = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let css = src.path().join("style.css");
+    let font = src.path().join("font.woff2");
+    fs::write(&css, "body {}").unwrap();
+    fs::write(&font, "font").unwrap();
+
+    let mut reqs = OutputRequirementReceiver::new();
+    reqs.require_file(&css, "deps/style.css").unwrap();
+    reqs.require_file(&css, "deps/style-copy.css").unwrap();
+    reqs.require_file(&font, "deps/fonts/font.woff2").unwrap();
+
+    let report = reqs.copy_to(out.path()).unwrap();
+    assert_eq!(report.copied.len(), 3);
+    assert!(report.unchanged.is_empty());
+    assert_eq!(
+        fs::read_to_string(out.path().join("deps/fonts/font.woff2")).unwrap(),
+        "font"
+    );
+
+    fs::write(&css, "body { margin: 0 }").unwrap();
+    let report = reqs.copy_to(out.path()).unwrap();
+    assert_eq!(
+        report,
+        CopyReport {
+            copied: vec!["deps/style-copy.css".into(), "deps/style.css".into()],
+            unchanged: vec!["deps/fonts/font.woff2".into()],
+        }
+    );
+    assert_eq!(
+        fs::read_to_string(out.path().join("deps/style.css")).unwrap(),
+        "body { margin: 0 }"
+    );
+
+    // No temporary files are left behind.
+    let names: Vec<_> = fs::read_dir(out.path().join("deps"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names.len(), 3);
+}
+
+#[test]
+fn missing_source_is_an_error() {
+    let out = tempfile::tempdir().unwrap();
+    let mut reqs = OutputRequirementReceiver::new();
+    reqs.require_file(out.path().join("nope.css"), "nope.css")
+        .unwrap();
+    assert!(reqs.copy_to(out.path()).is_err());
+}
+
+/// In-memory files (directories are implicit).
+#[derive(Default)]
+struct MemoryFs(RefCell<HashMap<PathBuf, Vec<u8>>>);
+
+impl OutputFileSystem for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.0
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found"))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.0
+            .borrow_mut()
+            .insert(path.to_owned(), contents.to_vec());
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let contents = self.read(from)?;
+        self.0.borrow_mut().remove(from);
+        self.write(to, &contents)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.0.borrow_mut().remove(path);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn process_id(&self) -> u32 {
+        1
+    }
+}
+
+#[test]
+fn uses_the_given_file_system() {
+    let fs = MemoryFs::default();
+    fs.write(Path::new("src/a.css"), b"a").unwrap();
+    fs.write(Path::new("src/a-copy.css"), b"a").unwrap();
+    fs.write(Path::new("src/b.css"), b"b").unwrap();
+
+    let mut reqs = OutputRequirementReceiver::new();
+    reqs.require_file_with(&fs, "src/a.css", "a.css").unwrap();
+    reqs.require_file_with(&fs, "src/a-copy.css", "a.css")
+        .unwrap();
+    assert!(reqs.require_file_with(&fs, "src/b.css", "a.css").is_err());
+
+    let report = reqs.copy_to_with(&fs, "out").unwrap();
+    assert_eq!(report.copied, vec![PathBuf::from("a.css")]);
+    assert_eq!(fs.read(Path::new("out/a.css")).unwrap(), b"a");
+    // Only the sources and the copy (no temporary file).
+    assert_eq!(fs.0.borrow().len(), 4);
+}
+
+#[test]
+fn hashes_contents() {
+    assert_eq!(
+        content_hash(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
@@ -0,0 +1,103 @@
+use std::{fs, path::Path};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::{
+        text::{render_text, TextOptions},
+        VERBATIM_TAG,
+    },
+};
+
+fn node(tag: &str, elems: Vec<LtrfObj>) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems).into()
+}
+
+fn processed_fixture(name: &str) -> LtrfNode {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/process")
+        .join(format!("{}.out.json", name));
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn renders_std_tags() {
+    let out = render_text(&processed_fixture("blocks"), &TextOptions::default()).unwrap();
+    assert_eq!(
+        out,
+        "Title
+=====
+
+Intro text _with newline_
+
+> Quoted one.
+>
+> Quoted two.
+
+Trailing
+
+[note]
+"
+    );
+}
+
+#[test]
+fn wraps_paragraphs_and_quotes() {
+    let words = "lorem ipsum dolor sit amet consectetur";
+    let root = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec![node(
+            "__doc",
+            vec![
+                node("__p", vec![words.into()]),
+                node("bquot", vec![node("__p", vec![words.into()])]),
+            ],
+        )],
+    );
+
+    let options = TextOptions {
+        width: Some(20),
+        ..TextOptions::default()
+    };
+    assert_eq!(
+        render_text(&root, &options).unwrap(),
+        "lorem ipsum dolor
+sit amet consectetur
+
+> lorem ipsum
+> dolor sit amet
+> consectetur
+"
+    );
+
+    let options = TextOptions {
+        width: None,
+        ..TextOptions::default()
+    };
+    assert!(render_text(&root, &options)
+        .unwrap()
+        .starts_with(&format!("{}\n\n", words)));
+}
+
+#[test]
+fn verbatim_blocks_are_not_wrapped() {
+    let mut kv = LtrfKv::new();
+    kv.insert("isBlock".to_owned(), true.into());
+    let table = "| a  | b  |\n|----|----|\n| 1  | 2  |";
+    let root = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec![
+            node("__p", vec!["Before.".into()]),
+            LtrfNode::make(VERBATIM_TAG, kv, vec![table.into()]).into(),
+        ],
+    );
+    let options = TextOptions {
+        width: Some(5),
+        ..TextOptions::default()
+    };
+    assert_eq!(
+        render_text(&root, &options).unwrap(),
+        format!("Before.\n\n{}\n", table)
+    );
+}
@@ -2,6 +2,7 @@
 //! See lapol-parse-rs file ast.rs for details on these types.
 
 export interface AstNodeMeta {
+    fileId: number;
     startOffset: number;
     startLine: number;
     startCol: number;
@@ -27,10 +28,34 @@ export interface AstRootNode {
     meta: AstNodeMeta;
 }
 
+export interface AstRawNode {
+    t: "AstRawNode";
+    content: string;
+    lang: string | null;
+    meta: AstNodeMeta;
+}
+
+/** Marks a blank line, within an `AstParagraphBreakNode`. */
+export interface AstBlankLineNode {
+    t: "AstBlankLineNode";
+    meta: AstNodeMeta;
+}
+
+/** Only emitted by the parser if paragraph break detection is enabled. */
+export interface AstParagraphBreakNode {
+    t: "AstParagraphBreakNode";
+    content: string;
+    blankLines: AstBlankLineNode[];
+    meta: AstNodeMeta;
+}
+
 export enum AstNodeKind {
     AstTextNode = "AstTextNode",
     AstCommandNode = "AstCommandNode",
     AstRootNode = "AstRootNode",
+    AstRawNode = "AstRawNode",
+    AstParagraphBreakNode = "AstParagraphBreakNode",
+    AstBlankLineNode = "AstBlankLineNode",
 }
 
 export interface SquareArgVal {
@@ -77,4 +102,10 @@ export type SquareEntry =
 
 export type SquareArg = SquareArgVal | SquareArgKeyVal;
 
-export type AstNode = AstTextNode | AstCommandNode | AstRootNode;
+export type AstNode =
+    | AstTextNode
+    | AstCommandNode
+    | AstRootNode
+    | AstRawNode
+    | AstParagraphBreakNode
+    | AstBlankLineNode;
@@ -1,10 +1,10 @@
-import { parse_file } from "lapol-rs";
 import { strict as assert } from "assert";
 import { AstRootNode } from "./ast";
 import { evaluatePass } from "./evaluate/evaluate";
 import { processPass } from "./process/process";
-import { copyFile, readFileBuffer, writeFile } from "./utils";
+import { readFileBuffer, writeFile } from "./utils";
 import { LaPath } from "./laPath";
+import { parseFileAsync } from "./parse";
 import { FileContext } from "./context/fileContext";
 import { LapolContext } from "./context/lapolContext";
 import { isLtrfNode, LtrfObj } from "./ltrf/ltrf";
@@ -35,7 +35,7 @@ async function compile(lctx: LapolContext, c: CompileInput): Promise<CompileOutp
     const t1 = Date.now();
     const textBuf = await readFileBuffer(c.inputFilePath);
     const t2 = Date.now();
-    const parsed = parse_file(c.inputFilePath.fullPath, textBuf) as AstRootNode;
+    const parsed = await parseFileAsync(c.inputFilePath, textBuf);
     assert(parsed.t === "AstRootNode");
     const t3 = Date.now();
 
@@ -64,47 +64,13 @@ async function compile(lctx: LapolContext, c: CompileInput): Promise<CompileOutp
     await writeFile(c.outputFilePath, output.code);
     const t7 = Date.now();
 
-    const copyPromises: Array<Promise<void>> = [];
-    for (const [tgtDepFileRelPath, srcDepFile] of outputRequirementReceiver.files) {
-        const f = async (): Promise<void> => {
-            // Checking if the copy was actually needed would be nice, but this is really tricky to
-            // synchronize correctly within NodeJs, unless we abandon asynchronous copies.
-            // So for now, we'll just always copy.
-            try {
-                await copyFile(
-                    srcDepFile, // source
-                    new LaPath(c.outputFolder.fullPath + c.outputFolder.sep + tgtDepFileRelPath) // target
-                );
-            } catch (e) {
-                // eslint-disable-next-line @typescript-eslint/no-unsafe-member-access
-                if (e.code === "EBUSY") {
-                    if (COMPILE_DBG_PRINT) {
-                        // Either you'd copy over the same file that is already being copied over,
-                        // in which case there is no issue, OR you'd copy over a different file,
-                        // which would cause issues with the first copy _anyways_, so you'd have a
-                        // bigger problem than this EBUSY. In general, as long as you never write
-                        // _distinct_ contents to the same file, you're good.
-                        //
-                        // Unfortunately, avoiding this EBUSY is not trivial at all. The simplest
-                        // solution is to do all copies sequentially, which is not great for
-                        // performance :(.
-                        console.log(`EBUSY Error when copying (this is probably NOT an issue).`);
-                        console.log(e);
-                    }
-                } else {
-                    throw e;
-                }
-            }
-        };
-
-        copyPromises.push(f());
-    }
-    /*
-    for (const p of copyPromises) {
-        await p;
+    const copyReport = outputRequirementReceiver.copyTo(c.outputFolder);
+    if (COMPILE_DBG_PRINT) {
+        console.log(
+            `Copied ${copyReport.copied.length} dependencies ` +
+                `(${copyReport.unchanged.length} unchanged).`
+        );
     }
-    */
-    await Promise.all(copyPromises);
     const t8 = Date.now();
 
     const dbgTimingInfo =
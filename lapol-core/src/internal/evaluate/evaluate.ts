@@ -4,6 +4,8 @@ import {
     AstCommandNode,
     AstNode,
     AstNodeKind,
+    AstParagraphBreakNode,
+    AstRawNode,
     AstRootNode,
     AstTextNode,
     SquareArg,
@@ -44,6 +46,13 @@ export function _evaluateNode(
             return evaluateTextNode(lctx, fctx, env, node);
         case AstNodeKind.AstCommandNode:
             return evaluateCommandNode(lctx, fctx, env, node);
+        case AstNodeKind.AstRawNode:
+            return evaluateRawNode(lctx, fctx, env, node);
+        case AstNodeKind.AstParagraphBreakNode:
+            return evaluateParagraphBreakNode(lctx, fctx, env, node);
+        case AstNodeKind.AstBlankLineNode:
+            // Only found within paragraph breaks.
+            return [];
         case AstNodeKind.AstRootNode:
             // return evaluateRootNode(lctx, fctx, env, node);
             throw new LapolError("Nested root currently unsupported.");
@@ -61,6 +70,25 @@ function evaluateTextNode(
     return [node.content];
 }
 
+function evaluateRawNode(
+    _lctx: LapolContext,
+    _fctx: FileContext,
+    _env: Environment,
+    node: AstRawNode
+): readonly LtrfObj[] {
+    return [node.content];
+}
+
+/** Two newlines are all the processing passes need to find the paragraph break. */
+function evaluateParagraphBreakNode(
+    _lctx: LapolContext,
+    _fctx: FileContext,
+    _env: Environment,
+    _node: AstParagraphBreakNode
+): readonly LtrfObj[] {
+    return ["\n", "\n"];
+}
+
 function evaluateCommandNode(
     lctx: LapolContext,
     fctx: FileContext,
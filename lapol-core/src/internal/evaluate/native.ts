@@ -0,0 +1,15 @@
+import { evaluateSource } from "lapol-rs";
+import { LtrfNode, ltrfObjFromJson } from "../ltrf/ltrf";
+
+/** Variables defined before native evaluation (see `@get` and `@if`). */
+export type NativeVariables = Record<string, number | boolean | string>;
+
+/** Parses and evaluates `source` natively (see `lapol_eval_rs`), with only the std modules and
+ * the native-only commands (`@define`, `@set`, `@get`, `@if`, `@for`). Returns the unprocessed
+ * LTRF root node.
+ */
+export function evaluateNative(source: string, variables: NativeVariables = {}): LtrfNode {
+    const root = ltrfObjFromJson(JSON.parse(evaluateSource(source, JSON.stringify(variables))));
+    if (typeof root === "string") throw new Error("Native evaluation did not return a node.");
+    return root;
+}
@@ -144,6 +144,18 @@ export function ltrfObjLiftArr(
     };
 }
 
+/** Inverse of `JSON.parse(JSON.stringify(o))` (see `dbgStringify`). This is the format in which
+ * LTRF trees are exchanged with lapol-rs.
+ */
+export function ltrfObjFromJson(o: unknown): LtrfObj {
+    if (typeof o === "string") return o;
+    if (typeof o === "object" && o !== null && "_tag" in o) {
+        const n = o as { _tag: string; _kv?: Record<string, unknown>; _elems?: unknown[] };
+        return LtrfNode.make(n._tag, n._kv ?? {}, (n._elems ?? []).map(ltrfObjFromJson));
+    }
+    throw new LtrfError("Value is not a serialized LtrfObj.");
+}
+
 export class LtrfError extends Error {
     constructor(m: string) {
         super(m);
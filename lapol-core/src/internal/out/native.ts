@@ -0,0 +1,47 @@
+import { renderHtml, renderLatex, renderMarkdown, renderText } from "lapol-rs";
+import { LtrfNode, LtrfObj } from "../ltrf/ltrf";
+
+export interface NativeHtmlOptions {
+    pretty?: boolean;
+    /** Output a full HTML document instead of a fragment. */
+    standalone?: boolean;
+}
+
+/** Renders a processed LTRF root node as HTML natively (see `lapol_core_rs::output::html`),
+ * bypassing the registered `LtrfNodeOutputter`s. Only the tags of the std modules are supported.
+ */
+export function outputHtmlNative(rootNode: LtrfNode, opts: NativeHtmlOptions = {}): string {
+    return renderHtml(rootNode.dbgStringify(), opts.pretty ?? false, opts.standalone ?? false);
+}
+
+export interface NativeLatexOptions {
+    /** Output a full document, using the default LaTeX class and package. */
+    standalone?: boolean;
+    title?: readonly LtrfObj[];
+    author?: readonly LtrfObj[];
+}
+
+/** Renders a processed LTRF root node as LaTeX natively (see `lapol_core_rs::output::latex`). */
+export function outputLatexNative(rootNode: LtrfNode, opts: NativeLatexOptions = {}): string {
+    return renderLatex(
+        rootNode.dbgStringify(),
+        opts.standalone ?? false,
+        JSON.stringify(opts.title ?? []),
+        JSON.stringify(opts.author ?? [])
+    );
+}
+
+/** Renders a processed LTRF root node as plain text natively (see `lapol_core_rs::output::text`).
+ * Paragraphs are wrapped at `width` columns, or not at all if `width` is `undefined`.
+ */
+export function outputTextNative(rootNode: LtrfNode, width?: number): string {
+    return renderText(rootNode.dbgStringify(), width);
+}
+
+/** Renders a processed LTRF root node as CommonMark natively (see
+ * `lapol_core_rs::output::markdown`). Paragraphs are wrapped at `width` columns, or not at all if
+ * `width` is `undefined`.
+ */
+export function outputMarkdownNative(rootNode: LtrfNode, width?: number): string {
+    return renderMarkdown(rootNode.dbgStringify(), width);
+}
@@ -1,13 +1,22 @@
-import { LapolError } from "../../errors";
+import { OutputRequirements } from "lapol-rs";
 import { LaPath } from "../../laPath";
 
-import * as path from "path";
+/** What `OutputRequirementReceiver.copyTo` did, by target path (relative to the output folder). */
+export interface CopyReport {
+    copied: string[];
+    /** Targets that already had the right contents. */
+    unchanged: string[];
+}
 
+/** Files required by the output. Backed by lapol-rs's `OutputRequirements`: binding a target to
+ *  files with different contents throws, and `copyTo` only writes the files whose contents
+ *  changed, atomically (so concurrent copies of the same file are fine).
+ */
 export class OutputRequirementReceiver {
-    readonly files: Map<string, LaPath>;
+    private readonly _inner: OutputRequirements;
 
     private constructor() {
-        this.files = new Map();
+        this._inner = new OutputRequirements();
     }
 
     public static make(): OutputRequirementReceiver {
@@ -15,15 +24,15 @@ export class OutputRequirementReceiver {
     }
 
     public requireFile(input: LaPath, as: string): void {
-        const targetPath = path.normalize(as);
-        const c = this.files.get(targetPath);
-        if (c !== undefined && c.fullPath !== input.fullPath) {
-            throw new LapolError(
-                `OutputRequirementReceiver.requireFile: Inconsistency - required` +
-                    `${input.fullPath} as ${targetPath}, but ${targetPath} already bound to ${c.fullPath}.`
-            );
-        } else {
-            this.files.set(targetPath, input);
-        }
+        this._inner.requireFile(input.fullPath, as);
+    }
+
+    /** The required files, as `[target, source]` pairs, sorted by target. */
+    get files(): Array<[string, string]> {
+        return JSON.parse(this._inner.files()) as Array<[string, string]>;
+    }
+
+    public copyTo(outputFolder: LaPath): CopyReport {
+        return JSON.parse(this._inner.copyTo(outputFolder.fullPath)) as CopyReport;
     }
 }
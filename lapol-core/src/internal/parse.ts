@@ -0,0 +1,133 @@
+import { parse_file as wasmParseFile } from "lapol-rs";
+import * as nodePath from "path";
+import { Worker } from "worker_threads";
+import { AstRootNode } from "./ast";
+import { getLapolFolder } from "./globalInit";
+import { LaPath } from "./laPath";
+import type { ParseRequest, ParseResponse } from "./parseWorker";
+
+/** The API of lapol-napi-rs (the native Node addon). */
+interface NativeParser {
+    init: () => void;
+    parse_file: (filePath: string, fileContentBuffer: Buffer) => unknown;
+    parse_file_async: (filePath: string, fileContentBuffer: Buffer) => Promise<unknown>;
+}
+
+export type ParserBackend = "native" | "wasm";
+
+let nativeParser: NativeParser | null | undefined;
+
+/** Loads lapol-napi-rs, if it has been built (see build.py).
+ *
+ * Set the environment variable `LAPOL_BACKEND` to `wasm` to always use lapol-rs (WASM), or to
+ * `native` to fail if the addon can't be loaded.
+ */
+function getNativeParser(): NativeParser | null {
+    if (nativeParser !== undefined) return nativeParser;
+
+    nativeParser = null;
+    const backend = process.env.LAPOL_BACKEND;
+    if (backend === "wasm") return nativeParser;
+
+    const addonPath = nodePath.join(
+        getLapolFolder().fullPath,
+        "lapol-napi-rs",
+        "lapol_napi_rs.node"
+    );
+    try {
+        // eslint-disable-next-line @typescript-eslint/no-var-requires
+        const addon = require(addonPath) as NativeParser;
+        addon.init();
+        nativeParser = addon;
+    } catch (e) {
+        if (backend === "native") throw e;
+    }
+    return nativeParser;
+}
+
+/** Which implementation `parseFile` and `parseFileAsync` use. */
+export function parserBackend(): ParserBackend {
+    return getNativeParser() === null ? "wasm" : "native";
+}
+
+export function parseFile(filePath: LaPath, fileContent: Buffer): AstRootNode {
+    const native = getNativeParser();
+    if (native !== null) return native.parse_file(filePath.fullPath, fileContent) as AstRootNode;
+    return wasmParseFile(filePath.fullPath, fileContent) as AstRootNode;
+}
+
+/** Like `parseFile`, but parses off the main thread: on the libuv thread pool with the native
+ * backend, or else in a worker thread (see `parseFileInWorker`).
+ */
+export async function parseFileAsync(filePath: LaPath, fileContent: Buffer): Promise<AstRootNode> {
+    const native = getNativeParser();
+    if (native !== null) {
+        return (await native.parse_file_async(filePath.fullPath, fileContent)) as AstRootNode;
+    }
+    return await parseFileInWorker(filePath, fileContent);
+}
+
+interface PendingParse {
+    chunks: Uint8Array[];
+    resolve: (root: AstRootNode) => void;
+    reject: (e: Error) => void;
+}
+
+let parseWorker: Worker | undefined;
+let nextParseId = 0;
+const pendingParses = new Map<number, PendingParse>();
+
+function getParseWorker(): Worker {
+    if (parseWorker !== undefined) return parseWorker;
+
+    const worker = new Worker(nodePath.join(__dirname, "parseWorker.js"));
+    worker.on("message", (res: ParseResponse) => {
+        const pending = pendingParses.get(res.id);
+        if (pending === undefined) return;
+        if ("chunk" in res) {
+            pending.chunks.push(res.chunk);
+            return;
+        }
+
+        pendingParses.delete(res.id);
+        if (pendingParses.size === 0) worker.unref();
+        if ("error" in res) {
+            const e = new Error(res.error.message);
+            e.name = res.error.name;
+            pending.reject(e);
+        } else {
+            const json = Buffer.concat(pending.chunks).toString("utf8");
+            pending.resolve(JSON.parse(json) as AstRootNode);
+        }
+    });
+    worker.on("error", (e) => {
+        for (const pending of pendingParses.values()) pending.reject(e);
+        pendingParses.clear();
+        parseWorker = undefined;
+    });
+    // The worker shouldn't keep the process alive when idle.
+    worker.unref();
+
+    parseWorker = worker;
+    return worker;
+}
+
+/** Parses with lapol-rs (WASM) in a worker thread, so the event loop isn't blocked while parsing
+ * big files. The worker is started on first use, and reused.
+ */
+export async function parseFileInWorker(
+    filePath: LaPath,
+    fileContent: Buffer
+): Promise<AstRootNode> {
+    const worker = getParseWorker();
+    const id = nextParseId++;
+    // Copied, since a Buffer may share its memory with others (so it can't be transferred).
+    const content = new Uint8Array(fileContent);
+
+    return await new Promise((resolve, reject) => {
+        pendingParses.set(id, { chunks: [], resolve, reject });
+        worker.ref();
+        const req: ParseRequest = { id, filePath: filePath.fullPath, content };
+        worker.postMessage(req, [content.buffer]);
+    });
+}
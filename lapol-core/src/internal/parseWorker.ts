@@ -0,0 +1,48 @@
+// Runs in a worker thread (see `parseFileInWorker` in parse.ts), so that parsing big files with
+// lapol-rs doesn't block the main thread.
+import { parentPort } from "worker_threads";
+import { init, ParseJob } from "lapol-rs";
+
+export interface ParseRequest {
+    id: number;
+    filePath: string;
+    /** Transferred to the worker. */
+    content: Uint8Array;
+}
+
+/** Chunks of the AST's JSON, then `done` (or an error, which is rebuilt on the main thread). */
+export type ParseResponse =
+    | { id: number; chunk: Uint8Array }
+    | { id: number; done: true }
+    | { id: number; error: { name: string; message: string } };
+
+const CHUNK_SIZE = 1 << 20;
+
+if (parentPort !== null) {
+    const port = parentPort;
+    init();
+
+    port.on("message", (req: ParseRequest) => {
+        let job: ParseJob | undefined;
+        try {
+            job = new ParseJob(req.filePath, req.content);
+            let chunk = job.nextChunk(CHUNK_SIZE);
+            while (chunk !== undefined) {
+                const res: ParseResponse = { id: req.id, chunk };
+                port.postMessage(res, [chunk.buffer]);
+                chunk = job.nextChunk(CHUNK_SIZE);
+            }
+            const res: ParseResponse = { id: req.id, done: true };
+            port.postMessage(res);
+        } catch (e) {
+            const error =
+                e instanceof Error
+                    ? { name: e.name, message: e.message }
+                    : { name: "Error", message: String(e) };
+            const res: ParseResponse = { id: req.id, error };
+            port.postMessage(res);
+        } finally {
+            job?.free();
+        }
+    });
+}
@@ -0,0 +1,29 @@
+import * as fs from "fs";
+import * as path from "path";
+import { FileContext } from "../context/fileContext";
+import { LapolContext } from "../context/lapolContext";
+import { isLtrfNode, ltrfObjFromJson } from "../ltrf/ltrf";
+import { processPass } from "./process";
+
+// These fixtures are shared with lapol-core-rs, whose processing passes must produce the same output.
+const FIXTURES_DIR = path.join(__dirname, "../../../../lapol-core-rs/tests/fixtures/process");
+
+describe("processPass", () => {
+    const fixtures = fs
+        .readdirSync(FIXTURES_DIR)
+        .filter((f) => f.endsWith(".in.json"))
+        .map((f) => f.slice(0, -".in.json".length));
+
+    it.each(fixtures)("matches golden output for %s", (fixture) => {
+        const input = ltrfObjFromJson(
+            JSON.parse(fs.readFileSync(path.join(FIXTURES_DIR, `${fixture}.in.json`), "utf8"))
+        );
+        const expected = fs.readFileSync(path.join(FIXTURES_DIR, `${fixture}.out.json`), "utf8");
+
+        if (!isLtrfNode(input)) throw new Error("Fixture root must be a node.");
+        // The processing passes don't currently use the contexts.
+        const out = processPass({} as LapolContext, {} as FileContext, input);
+
+        expect(out.dbgStringify() + "\n").toEqual(expected);
+    });
+});
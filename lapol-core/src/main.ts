@@ -10,7 +10,7 @@ if (!isNodePathInit) {
     isNodePathInit = true;
 }
 
-import { init as lapol_rs_init } from "lapol-rs";
+import { capabilities as lapolRsCapabilities, init as lapol_rs_init } from "lapol-rs";
 
 import { setLapolFolder } from "./internal/globalInit";
 import { LaPath } from "./internal/laPath";
@@ -21,8 +21,18 @@ if (!isLapolFolderInfoInit) {
     isLapolFolderInfoInit = true;
 }
 
+/** The lapol-rs API version (see `capabilities` in lapol-rs) this code is written against. */
+const LAPOL_RS_API_VERSION = 1;
+
 if (!isLapolRsInit) {
     lapol_rs_init(); // Sets up rust panic handler.
+    const { apiVersion } = lapolRsCapabilities() as { apiVersion: number };
+    if (apiVersion !== LAPOL_RS_API_VERSION) {
+        throw new Error(
+            `lapol-rs has API version ${apiVersion}, but ${LAPOL_RS_API_VERSION} is needed. ` +
+                `Rebuild lapol-rs (python build.py).`
+        );
+    }
     isLapolRsInit = true;
 }
 
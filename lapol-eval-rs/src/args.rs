@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use lapol_core_rs::ltrf::LtrfObj;
+use lapol_parse_rs::{AstNode, SquareEntry};
+
+use crate::{error::EvalError, value::Value};
+
+/// The evaluated arguments of a command invocation. Mirrors lapol-core's
+/// `EagerCommandArguments`.
+///
+/// In `@cmd[a, b, key=c]{x}{y}`, `a` and `b` are the positional (square)
+/// arguments, `key` is a keyword argument, and `x` and `y` are the curly
+/// arguments.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    pub square: Vec<Value>,
+    pub keyword: BTreeMap<String, Value>,
+    pub curly: Vec<Vec<LtrfObj>>,
+}
+
+impl Args {
+    pub fn square(&self, idx: usize) -> Option<&Value> {
+        self.square.get(idx)
+    }
+
+    pub fn keyword(&self, key: &str) -> Option<&Value> {
+        self.keyword.get(key)
+    }
+
+    pub fn curly(&self, idx: usize) -> Option<&[LtrfObj]> {
+        self.curly.get(idx).map(Vec::as_slice)
+    }
+
+    pub fn square_or_err(&self, idx: usize) -> Result<&Value, EvalError> {
+        self.square(idx).ok_or_else(|| missing_square(idx))
+    }
+
+    pub fn keyword_or_err(&self, key: &str) -> Result<&Value, EvalError> {
+        self.keyword(key).ok_or_else(|| missing_keyword(key))
+    }
+
+    pub fn curly_or_err(&self, idx: usize) -> Result<&[LtrfObj], EvalError> {
+        self.curly(idx).ok_or_else(|| missing_curly(idx))
+    }
+
+    /// The keyword argument `key` as a string (identifier or quoted), if
+    /// given.
+    pub fn keyword_str(&self, key: &str) -> Result<Option<&str>, EvalError> {
+        match self.keyword(key) {
+            None => Ok(None),
+            Some(v) => v.as_str().map(Some).ok_or_else(|| {
+                EvalError::msg(format!(
+                    "Keyword argument `{}` must be a string, not a {}.",
+                    key,
+                    v.type_name()
+                ))
+            }),
+        }
+    }
+}
+
+/// The unevaluated arguments of a command invocation, for `LazyCommand`s.
+/// Mirrors lapol-core's (unimplemented) `LazyCommandArguments`.
+///
+/// Keyword keys are evaluated; everything else is passed as parsed. Use
+/// `CommandContext::evaluate` and `CommandContext::evaluate_entry` to
+/// evaluate them.
+#[derive(Debug, Default)]
+pub struct LazyArgs<'a> {
+    pub square: Vec<&'a SquareEntry<'a>>,
+    pub keyword: BTreeMap<String, &'a SquareEntry<'a>>,
+    pub curly: Vec<&'a [AstNode<'a>]>,
+}
+
+impl<'a> LazyArgs<'a> {
+    pub fn square(&self, idx: usize) -> Option<&'a SquareEntry<'a>> {
+        self.square.get(idx).copied()
+    }
+
+    pub fn keyword(&self, key: &str) -> Option<&'a SquareEntry<'a>> {
+        self.keyword.get(key).copied()
+    }
+
+    pub fn curly(&self, idx: usize) -> Option<&'a [AstNode<'a>]> {
+        self.curly.get(idx).copied()
+    }
+
+    pub fn square_or_err(&self, idx: usize) -> Result<&'a SquareEntry<'a>, EvalError> {
+        self.square(idx).ok_or_else(|| missing_square(idx))
+    }
+
+    pub fn keyword_or_err(&self, key: &str) -> Result<&'a SquareEntry<'a>, EvalError> {
+        self.keyword(key).ok_or_else(|| missing_keyword(key))
+    }
+
+    pub fn curly_or_err(&self, idx: usize) -> Result<&'a [AstNode<'a>], EvalError> {
+        self.curly(idx).ok_or_else(|| missing_curly(idx))
+    }
+}
+
+fn missing_square(idx: usize) -> EvalError {
+    EvalError::msg(format!(
+        "Square argument {} undefined (Likely not provided).",
+        idx
+    ))
+}
+
+fn missing_keyword(key: &str) -> EvalError {
+    EvalError::msg(format!(
+        "Keyword argument `{}` undefined (Likely not provided).",
+        key
+    ))
+}
+
+fn missing_curly(idx: usize) -> EvalError {
+    EvalError::msg(format!(
+        "Curly argument {} undefined (Likely not provided).",
+        idx
+    ))
+}
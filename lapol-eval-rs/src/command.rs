@@ -0,0 +1,135 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use lapol_core_rs::ltrf::LtrfObj;
+use lapol_parse_rs::{AstNode, AstNodeMeta, SquareEntry};
+
+use crate::{
+    args::{Args, LazyArgs},
+    environment::Environment,
+    error::EvalError,
+    evaluate::{evaluate_nodes, evaluate_square_entry},
+    value::Value,
+};
+
+/// A command (`@name[...]{...}`). Mirrors lapol-core's `Command`.
+///
+/// Closures with the signature of `call` are commands too.
+pub trait Command {
+    fn call(&self, args: &Args, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError>;
+}
+
+impl<F> Command for F
+where
+    F: Fn(&Args, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError>,
+{
+    fn call(&self, args: &Args, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        self(args, ctx)
+    }
+}
+
+/// A command that receives its arguments unevaluated (lapol-core's
+/// `argumentEvaluation: "lazy"`), and evaluates them itself, if and as many
+/// times as it wants (see `CommandContext::evaluate`). This is how
+/// conditionals, loops, quoting and verbatim commands are written.
+///
+/// Closures with the signature of `call` are lazy commands too.
+pub trait LazyCommand {
+    fn call(&self, args: &LazyArgs, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError>;
+}
+
+impl<F> LazyCommand for F
+where
+    F: Fn(&LazyArgs, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError>,
+{
+    fn call(&self, args: &LazyArgs, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        self(args, ctx)
+    }
+}
+
+/// A command, as stored in an `Environment` or a `Module`.
+#[derive(Clone)]
+pub enum CommandRef {
+    Eager(Rc<dyn Command>),
+    Lazy(Rc<dyn LazyCommand>),
+}
+
+impl CommandRef {
+    pub fn is_lazy(&self) -> bool {
+        matches!(self, CommandRef::Lazy(_))
+    }
+}
+
+/// What a command can access while it runs.
+pub struct CommandContext<'c> {
+    pub env: &'c mut Environment,
+    pub file: &'c mut FileContext,
+    /// Where the command was invoked.
+    pub meta: &'c AstNodeMeta,
+}
+
+impl CommandContext<'_> {
+    /// Evaluates `nodes` (e.g. a curly argument of a lazy command) in the
+    /// current environment.
+    pub fn evaluate(&mut self, nodes: &[AstNode]) -> Result<Vec<LtrfObj>, EvalError> {
+        evaluate_nodes(nodes, self.env, self.file)
+    }
+
+    /// Evaluates `nodes` in a new scope of the current environment: what they
+    /// define is dropped afterwards.
+    pub fn evaluate_scoped(&mut self, nodes: &[AstNode]) -> Result<Vec<LtrfObj>, EvalError> {
+        self.env.push_scope();
+        let out = evaluate_nodes(nodes, self.env, self.file);
+        self.env.pop_scope();
+        out
+    }
+
+    /// Evaluates `nodes` in another environment (the file context is
+    /// shared).
+    pub fn evaluate_in(
+        &mut self,
+        env: &mut Environment,
+        nodes: &[AstNode],
+    ) -> Result<Vec<LtrfObj>, EvalError> {
+        evaluate_nodes(nodes, env, self.file)
+    }
+
+    /// Evaluates a square argument entry, as it would have been for an eager
+    /// command.
+    pub fn evaluate_entry(&mut self, entry: &SquareEntry) -> Result<Value, EvalError> {
+        evaluate_square_entry(entry, self.env, self.file)
+    }
+}
+
+/// Per-file state, shared by all commands (mirrors lapol-core's
+/// `FileContext`). Each module keeps its state in a type of its own, e.g.
+/// `stdlib::MainStorage`.
+#[derive(Default)]
+pub struct FileContext {
+    storage: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl FileContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The storage of type `T`, created (with `T::default()`) if needed.
+    pub fn storage<T: Any + Default>(&mut self) -> &mut T {
+        self.storage
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut()
+            .expect("Storage is keyed by its type")
+    }
+
+    /// The storage of type `T`, if any command created it.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.storage
+            .get(&TypeId::of::<T>())
+            .and_then(|s| s.downcast_ref())
+    }
+}
@@ -0,0 +1,220 @@
+//! Variables, conditionals and loops: `@set`, `@get`, `@if` and `@for`.
+//!
+//! Variables hold `Value`s, and live in the scopes of the `Environment`
+//! (see `Environment::set_var`). The host can define some before
+//! evaluation, e.g. `lapol build` defines `target` (`html` or `latex`).
+//!
+//! ```text
+//! @set[edition=2]
+//! Edition @get{edition}.
+//! @if[target=latex]{Printed}{Online} edition.
+//! @if[edition, ge=2]{New in this edition.}
+//! @for[i, from=1, to=3]{Chapter @get{i}. }
+//! ```
+//!
+//! Comparisons are typed: `@set[edition=2]` is a number, and so
+//! `@if[edition="2"]` is an error rather than false (see `Value::compare`).
+
+use std::cmp::Ordering;
+
+use lapol_core_rs::ltrf::LtrfObj;
+
+use crate::{
+    args::{Args, LazyArgs},
+    command::CommandContext,
+    error::EvalError,
+    value::Value,
+};
+
+/// More is surely an infinite loop.
+const MAX_ITERATIONS: usize = 100_000;
+
+/// `@set[name=value, ...]`: assigns to variables (see `Environment::set_var`).
+pub(crate) fn set(a: &Args, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+    if !a.square.is_empty() {
+        return Err(EvalError::msg(
+            "set: Variables must be given as keyword arguments, e.g. @set[edition=2]",
+        ));
+    }
+    for (name, value) in &a.keyword {
+        ctx.env.set_var(name.clone(), value.clone());
+    }
+    Ok(vec![])
+}
+
+/// `@get{name}`: the value of a variable.
+pub(crate) fn get(a: &Args, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+    let name: String = a
+        .curly_or_err(0)?
+        .iter()
+        .filter_map(LtrfObj::as_str)
+        .collect();
+    Ok(vec![var(ctx, name.trim())?.to_ltrf()])
+}
+
+/// `@if[conditions...]{then}{else}`, where all conditions must hold:
+///
+/// - `name=value`: the variable `name` equals `value`;
+/// - `name`: the variable `name` is `true`;
+/// - `name, op=value`, with `op` one of `eq`, `ne`, `lt`, `le`, `gt` and `ge`:
+///   compares the variable `name` with `value`. There can only be one
+///   positional argument then.
+///
+/// The `else` argument is optional.
+pub(crate) fn if_(a: &LazyArgs, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+    let then = a.curly_or_err(0)?;
+    if conditions_hold(a, ctx)? {
+        ctx.evaluate(then)
+    } else {
+        a.curly(1).map_or(Ok(vec![]), |e| ctx.evaluate(e))
+    }
+}
+
+fn conditions_hold(a: &LazyArgs, ctx: &mut CommandContext) -> Result<bool, EvalError> {
+    let is_op = |k: &str| Op::from_name(k).is_some();
+    let subject = if a.keyword.keys().any(|k| is_op(k)) {
+        if a.square.len() != 1 {
+            return Err(EvalError::msg(
+                "if: Comparisons (eq, ne, lt, le, gt, ge) need exactly one variable, \
+                 e.g. @if[edition, ge=2]",
+            ));
+        }
+        Some(variable_name(ctx.evaluate_entry(a.square[0])?)?)
+    } else {
+        None
+    };
+
+    let mut holds = true;
+    if subject.is_none() {
+        for entry in &a.square {
+            let value = match ctx.evaluate_entry(entry)? {
+                Value::Ident(name) => var(ctx, &name)?.clone(),
+                v => v,
+            };
+            holds &= value.as_bool().ok_or_else(|| {
+                EvalError::msg(format!(
+                    "if: Conditions must be booleans, not a {}",
+                    value.type_name()
+                ))
+            })?;
+        }
+    }
+    for (key, entry) in &a.keyword {
+        let expected = ctx.evaluate_entry(entry)?;
+        let (name, op) = match (&subject, Op::from_name(key)) {
+            (Some(subject), Some(op)) => (subject.as_str(), op),
+            _ => (key.as_str(), Op::Eq),
+        };
+        holds &= op.test(name, var(ctx, name)?, &expected)?;
+    }
+    Ok(holds)
+}
+
+/// `@for[i, from=1, to=3, step=1]{body}`: evaluates `body` for each number
+/// from `from` to `to` (inclusive), with `i` set to it. `step` defaults to 1.
+pub(crate) fn for_(a: &LazyArgs, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+    let name = variable_name(ctx.evaluate_entry(a.square_or_err(0)?)?)?;
+    let mut num = |key: &str, default: Option<f64>| -> Result<f64, EvalError> {
+        match a.keyword(key) {
+            Some(entry) => {
+                let v = ctx.evaluate_entry(entry)?;
+                v.as_num().ok_or_else(|| {
+                    EvalError::msg(format!(
+                        "for: `{}` must be a number, not a {}",
+                        key,
+                        v.type_name()
+                    ))
+                })
+            }
+            None => default.ok_or_else(|| EvalError::msg(format!("for: Must provide {}", key))),
+        }
+    };
+    let from = num("from", None)?;
+    let to = num("to", None)?;
+    let step = num("step", Some(1.0))?;
+    if step == 0.0 {
+        return Err(EvalError::msg("for: step can't be 0"));
+    }
+
+    let body = a.curly_or_err(0)?;
+    let mut out = Vec::new();
+    let mut i = from;
+    let mut iterations = 0;
+    while (step > 0.0 && i <= to) || (step < 0.0 && i >= to) {
+        iterations += 1;
+        if iterations > MAX_ITERATIONS {
+            return Err(EvalError::msg(format!(
+                "for: More than {} iterations",
+                MAX_ITERATIONS
+            )));
+        }
+
+        ctx.env.push_scope();
+        ctx.env.define_var(name.clone(), Value::Num(i));
+        let res = ctx.evaluate(body);
+        ctx.env.pop_scope();
+        out.extend(res?);
+        i += step;
+    }
+    Ok(out)
+}
+
+fn var<'e>(ctx: &'e CommandContext, name: &str) -> Result<&'e Value, EvalError> {
+    ctx.env
+        .var(name)
+        .ok_or_else(|| EvalError::msg(format!("Variable `{}` is not set.", name)))
+}
+
+fn variable_name(v: Value) -> Result<String, EvalError> {
+    match v {
+        Value::Ident(s) | Value::Str(s) => Ok(s),
+        v => Err(EvalError::msg(format!(
+            "Expected a variable name, not a {}",
+            v.type_name()
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn from_name(name: &str) -> Option<Op> {
+        Some(match name {
+            "eq" => Op::Eq,
+            "ne" => Op::Ne,
+            "lt" => Op::Lt,
+            "le" => Op::Le,
+            "gt" => Op::Gt,
+            "ge" => Op::Ge,
+            _ => return None,
+        })
+    }
+
+    /// `name` is only used in the error.
+    fn test(self, name: &str, value: &Value, expected: &Value) -> Result<bool, EvalError> {
+        let ord = value.compare(expected).ok_or_else(|| {
+            EvalError::msg(format!(
+                "Can't compare variable `{}` (a {}) with a {}",
+                name,
+                value.type_name(),
+                expected.type_name()
+            ))
+        })?;
+        Ok(match self {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Ne => ord != Ordering::Equal,
+            Op::Lt => ord == Ordering::Less,
+            Op::Le => ord != Ordering::Greater,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Ge => ord != Ordering::Less,
+        })
+    }
+}
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, rc::Rc};
+
+use lapol_core_rs::ltrf::LtrfObj;
+
+use crate::{
+    args::{Args, LazyArgs},
+    command::{Command, CommandContext, CommandRef, LazyCommand},
+    error::EvalError,
+    stdlib,
+    value::Value,
+};
+
+/// A named set of commands, e.g. `std::main`. Once loaded in an
+/// `Environment`, its commands can be called as `@module:command`, or be
+/// brought into scope (see `Environment::use_all`).
+#[derive(Clone)]
+pub struct Module {
+    name: String,
+    commands: Vec<(String, CommandRef)>,
+}
+
+impl Module {
+    pub fn new(name: impl Into<String>) -> Self {
+        Module {
+            name: name.into(),
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn define(&mut self, name: impl Into<String>, command: impl Command + 'static) {
+        self.define_ref(name, CommandRef::Eager(Rc::new(command)));
+    }
+
+    pub fn define_fn<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: Fn(&Args, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> + 'static,
+    {
+        self.define(name, f);
+    }
+
+    pub fn define_lazy(&mut self, name: impl Into<String>, command: impl LazyCommand + 'static) {
+        self.define_ref(name, CommandRef::Lazy(Rc::new(command)));
+    }
+
+    pub fn define_lazy_fn<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: Fn(&LazyArgs, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> + 'static,
+    {
+        self.define_lazy(name, f);
+    }
+
+    pub fn define_ref(&mut self, name: impl Into<String>, command: CommandRef) {
+        self.commands.push((name.into(), command));
+    }
+
+    pub fn get(&self, name: &str) -> Option<CommandRef> {
+        self.commands
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, c)| c.clone())
+    }
+
+    pub fn command_names(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(|(n, _)| n.as_str())
+    }
+}
+
+/// The commands and variables defined in a scope.
+#[derive(Default)]
+struct Scope {
+    commands: HashMap<String, CommandRef>,
+    variables: HashMap<String, Value>,
+}
+
+/// Which commands (and variables) are visible where (mirrors lapol-core's
+/// `Environment`).
+///
+/// Names are looked up from the innermost scope outwards; qualified names
+/// (`std::main:sec`) are looked up in the loaded modules.
+pub struct Environment {
+    scopes: Vec<Scope>,
+    /// Modules that can be loaded (with `load_module`, or `@__require`).
+    registry: HashMap<String, Rc<Module>>,
+    loaded: HashMap<String, Rc<Module>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment {
+            scopes: vec![Scope::default()],
+            registry: HashMap::new(),
+            loaded: HashMap::new(),
+        }
+    }
+}
+
+impl Environment {
+    /// An empty environment, with no commands at all.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An environment with `std::core` loaded and its commands in scope,
+    /// and `std::main` available (mirrors lapol-core's
+    /// `makeEnvironmentWithStdCoreSetup`).
+    pub fn with_std() -> Self {
+        let mut env = Self::new();
+        env.register_module(stdlib::core());
+        env.register_module(stdlib::main());
+        env.load_module(stdlib::STD_CORE)
+            .expect("std::core was just registered");
+        env.use_all(stdlib::STD_CORE, "")
+            .expect("std::core was just loaded");
+        env
+    }
+
+    /// Defines `name` in the innermost scope.
+    pub fn define(&mut self, name: impl Into<String>, command: impl Command + 'static) {
+        self.define_ref(name, CommandRef::Eager(Rc::new(command)));
+    }
+
+    pub fn define_fn<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: Fn(&Args, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> + 'static,
+    {
+        self.define(name, f);
+    }
+
+    /// Defines `name` in the innermost scope, as a command that gets its
+    /// arguments unevaluated.
+    pub fn define_lazy(&mut self, name: impl Into<String>, command: impl LazyCommand + 'static) {
+        self.define_ref(name, CommandRef::Lazy(Rc::new(command)));
+    }
+
+    pub fn define_lazy_fn<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: Fn(&LazyArgs, &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> + 'static,
+    {
+        self.define_lazy(name, f);
+    }
+
+    pub fn define_ref(&mut self, name: impl Into<String>, command: CommandRef) {
+        self.scopes
+            .last_mut()
+            .expect("There is always a global scope")
+            .commands
+            .insert(name.into(), command);
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<CommandRef> {
+        if let Some(c) = self.scopes.iter().rev().find_map(|s| s.commands.get(name)) {
+            return Some(c.clone());
+        }
+
+        let (module, item) = split_identifier(name)?;
+        self.loaded.get(module)?.get(item)
+    }
+
+    /// Defines the variable `name` in the innermost scope (`@set` may
+    /// assign to an outer one instead, see `set_var`).
+    pub fn define_var(&mut self, name: impl Into<String>, value: Value) {
+        self.scopes
+            .last_mut()
+            .expect("There is always a global scope")
+            .variables
+            .insert(name.into(), value);
+    }
+
+    /// Assigns to the variable `name` in the innermost scope that has it, or
+    /// defines it in the innermost scope.
+    pub fn set_var(&mut self, name: impl Into<String>, value: Value) {
+        let name = name.into();
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|s| s.variables.get_mut(&name))
+        {
+            Some(v) => *v = value,
+            None => self.define_var(name, value),
+        }
+    }
+
+    pub fn var(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|s| s.variables.get(name))
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    /// Drops the innermost scope, and everything (commands and variables)
+    /// defined in it. The global scope is never dropped.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Number of scopes, including the global one.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    pub fn register_module(&mut self, module: Module) {
+        self.registry.insert(module.name.clone(), Rc::new(module));
+    }
+
+    /// Makes the commands of a registered module available, as
+    /// `@module:command`.
+    pub fn load_module(&mut self, name: &str) -> Result<(), EvalError> {
+        let module = self.registry.get(name).ok_or_else(|| {
+            EvalError::msg(format!(
+                "Module {} was required: you need to register it in the Environment.",
+                name
+            ))
+        })?;
+        self.loaded.insert(name.to_owned(), module.clone());
+        Ok(())
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.loaded.contains_key(name)
+    }
+
+    /// Brings all the commands of a loaded module into the innermost scope,
+    /// each named `prefix` + its name.
+    pub fn use_all(&mut self, module: &str, prefix: &str) -> Result<(), EvalError> {
+        let module = self
+            .loaded
+            .get(module)
+            .cloned()
+            .ok_or_else(|| EvalError::msg(format!("Module {} is not loaded.", module)))?;
+        for (name, command) in &module.commands {
+            self.define_ref(format!("{}{}", prefix, name), command.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Splits `a::b:c` (or `:a::b:c`) into `a::b` and `c`: a single `:` separates
+/// the module from the name, `::` is part of the module name. Mirrors
+/// lapol-core's `parseIdentifier`.
+fn split_identifier(id: &str) -> Option<(&str, &str)> {
+    let id = id.strip_prefix(':').unwrap_or(id);
+    let bytes = id.as_bytes();
+    let pos = (0..bytes.len()).rev().find(|&i| {
+        bytes[i] == b':'
+            && (i == 0 || bytes[i - 1] != b':')
+            && bytes.get(i + 1).is_some_and(|&c| c != b':')
+    })?;
+    Some((&id[..pos], &id[pos + 1..]))
+}
@@ -0,0 +1,70 @@
+use lapol_core_rs::LapolError;
+use lapol_parse_rs::{AstNodeMeta, SourceMap};
+use thiserror::Error as TError;
+
+#[derive(Debug, TError)]
+pub enum EvalError {
+    #[error("LaPoL evaluation error --- Unknown command `{name}` ({})", position(.meta))]
+    UnknownCommand { name: String, meta: AstNodeMeta },
+    /// Raised by a command (see `EvalError::msg`).
+    #[error("LaPoL evaluation error --- {0}")]
+    Message(String),
+    #[error(transparent)]
+    Lapol(#[from] LapolError),
+    /// An error while evaluating a command invocation (its arguments, or the
+    /// command itself). Nested invocations give a trace, innermost first.
+    #[error("{inner}\n    in @{name} ({})", position(.meta))]
+    InCommand {
+        name: String,
+        meta: AstNodeMeta,
+        inner: Box<EvalError>,
+    },
+}
+
+impl EvalError {
+    pub fn msg(message: impl Into<String>) -> Self {
+        EvalError::Message(message.into())
+    }
+
+    /// The error, with its positions resolved through `source_map` to the
+    /// file (which may be an `@include`d one), line and column, and the source
+    /// line of the innermost one (see `SourceMap::diagnostic`). The files must
+    /// be in `source_map`.
+    pub fn diagnostic(&self, source_map: &SourceMap) -> String {
+        match self {
+            EvalError::UnknownCommand { name, meta } => source_map.diagnostic(
+                meta,
+                &format!("LaPoL evaluation error --- Unknown command `{}`", name),
+            ),
+            EvalError::InCommand { name, meta, inner } => {
+                let inner = match **inner {
+                    EvalError::UnknownCommand { .. } | EvalError::InCommand { .. } => {
+                        inner.diagnostic(source_map)
+                    }
+                    // Errors raised by the command itself are shown at the
+                    // invocation.
+                    _ => source_map.diagnostic(meta, &inner.to_string()),
+                };
+                format!(
+                    "{}\n    in @{} ({})",
+                    inner,
+                    name,
+                    source_map.location(meta)
+                )
+            }
+            e => e.to_string(),
+        }
+    }
+
+    /// The error, without the `InCommand` trace.
+    pub fn root_cause(&self) -> &EvalError {
+        match self {
+            EvalError::InCommand { inner, .. } => inner.root_cause(),
+            e => e,
+        }
+    }
+}
+
+fn position(meta: &AstNodeMeta) -> String {
+    format!("line {}, column {}", meta.start_line, meta.start_col)
+}
@@ -0,0 +1,151 @@
+//! Mirrors `lapol-core/src/internal/evaluate/evaluate.ts`.
+
+use lapol_core_rs::ltrf::{LtrfKv, LtrfNode, LtrfObj};
+use lapol_parse_rs::{AstNode, SquareArg, SquareEntry};
+
+use crate::{
+    args::{Args, LazyArgs},
+    command::{CommandContext, CommandRef, FileContext},
+    environment::Environment,
+    error::EvalError,
+    value::Value,
+};
+
+const ROOT_TAG: &str = "__root";
+
+/// Evaluates a parsed file into an LTRF root node.
+pub fn evaluate(
+    root: &AstNode,
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<LtrfNode, EvalError> {
+    let sub_nodes = match root {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+        _ => return Err(EvalError::msg("Expected a root node.")),
+    };
+
+    let elems = evaluate_nodes(sub_nodes, env, file)?;
+    Ok(LtrfNode::make(ROOT_TAG, LtrfKv::new(), elems))
+}
+
+pub(crate) fn evaluate_nodes(
+    nodes: &[AstNode],
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<Vec<LtrfObj>, EvalError> {
+    let mut out = Vec::new();
+    for n in nodes {
+        out.extend(evaluate_node(n, env, file)?);
+    }
+    Ok(out)
+}
+
+fn evaluate_node(
+    node: &AstNode,
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<Vec<LtrfObj>, EvalError> {
+    match node {
+        AstNode::AstTextNode { content, .. } => Ok(vec![content.as_ref().into()]),
+        AstNode::AstRawNode { content, .. } => Ok(vec![content.as_ref().into()]),
+        // Two newlines are all the processing passes need to find the
+        // paragraph break.
+        AstNode::AstParagraphBreakNode { .. } => Ok(vec!["\n".into(), "\n".into()]),
+        // Only found within paragraph breaks.
+        AstNode::AstBlankLineNode { .. } => Ok(vec![]),
+        AstNode::AstRootNode { .. } => Err(EvalError::msg("Nested root currently unsupported.")),
+        AstNode::AstCommandNode {
+            command_name,
+            square_args,
+            curly_args,
+            meta,
+        } => {
+            let command = env
+                .lookup(command_name)
+                .ok_or_else(|| EvalError::UnknownCommand {
+                    name: command_name.to_string(),
+                    meta: meta.clone(),
+                })?;
+
+            let call = |env: &mut Environment, file: &mut FileContext| match command {
+                CommandRef::Eager(command) => {
+                    let mut args = Args::default();
+                    for arg in square_args.iter().flatten() {
+                        match arg {
+                            SquareArg::Val(v) => {
+                                args.square.push(evaluate_square_entry(v, env, file)?)
+                            }
+                            SquareArg::KeyVal(k, v) => {
+                                let key = evaluate_key(k, env, file)?;
+                                let val = evaluate_square_entry(v, env, file)?;
+                                args.keyword.insert(key, val);
+                            }
+                        }
+                    }
+                    for arg in curly_args {
+                        args.curly.push(evaluate_nodes(arg, env, file)?);
+                    }
+
+                    command.call(&args, &mut CommandContext { env, file, meta })
+                }
+                CommandRef::Lazy(command) => {
+                    let mut args = LazyArgs::default();
+                    for arg in square_args.iter().flatten() {
+                        match arg {
+                            SquareArg::Val(v) => args.square.push(v),
+                            SquareArg::KeyVal(k, v) => {
+                                args.keyword.insert(evaluate_key(k, env, file)?, v);
+                            }
+                        }
+                    }
+                    args.curly = curly_args.iter().map(Vec::as_slice).collect();
+
+                    command.call(&args, &mut CommandContext { env, file, meta })
+                }
+            };
+
+            call(env, file).map_err(|e| EvalError::InCommand {
+                name: command_name.to_string(),
+                meta: meta.clone(),
+                inner: Box::new(e),
+            })
+        }
+    }
+}
+
+fn evaluate_key(
+    key: &SquareEntry,
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<String, EvalError> {
+    match evaluate_square_entry(key, env, file)? {
+        Value::Ident(s) | Value::Str(s) => Ok(s),
+        Value::Ltrf(LtrfObj::Str(s)) => Ok(s.to_string()),
+        _ => Err(EvalError::msg(
+            "Key for Keyword argument must evaluate to string.",
+        )),
+    }
+}
+
+pub(crate) fn evaluate_square_entry(
+    entry: &SquareEntry,
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<Value, EvalError> {
+    match entry {
+        SquareEntry::Num(n) => Ok(Value::Num(*n)),
+        SquareEntry::Bool(b) => Ok(Value::Bool(*b)),
+        SquareEntry::Ident(s) => Ok(Value::Ident(s.to_string())),
+        SquareEntry::QuotedStr(s) => Ok(Value::Str(s.clone())),
+        SquareEntry::AstNode(n) => {
+            let mut o = evaluate_node(n, env, file)?;
+            if o.len() != 1 {
+                // TODO: Fix this arbitrary restriction (from lapol-core).
+                return Err(EvalError::msg(
+                    "Square Entries currently only support a single sub node",
+                ));
+            }
+            Ok(Value::Ltrf(o.remove(0)))
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! # LaPoL eval (native)
+//!
+//! `lapol-eval-rs` evaluates the AST produced by `lapol-parse-rs` into an
+//! LTRF tree (see `lapol-core-rs`), with commands written in Rust. It mirrors
+//! `lapol-core/src/internal/evaluate`, so that Rust applications can embed
+//! LaPoL as a document language.
+//!
+//! ```
+//! use lapol_eval_rs::{evaluate, Environment, FileContext};
+//!
+//! let mut env = Environment::with_std();
+//! env.define_fn("shout", |args, _| {
+//!     let text: String = args.curly_or_err(0)?.iter().filter_map(|o| o.as_str()).collect();
+//!     Ok(vec![text.to_uppercase().into()])
+//! });
+//!
+//! let ast = lapol_parse_rs::parse("@__doc{@shout{hi}}").unwrap();
+//! let root = evaluate(&ast, &mut env, &mut FileContext::new()).unwrap();
+//! let doc = root.elems()[0].as_node().unwrap();
+//! assert_eq!(doc.tag(), "__doc");
+//! assert_eq!(doc.elems()[0].as_str(), Some("HI"));
+//! ```
+
+mod args;
+mod command;
+mod control;
+mod environment;
+mod error;
+mod evaluate;
+mod macros;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod stdlib;
+mod value;
+
+pub use args::{Args, LazyArgs};
+pub use command::{Command, CommandContext, CommandRef, FileContext, LazyCommand};
+pub use environment::{Environment, Module};
+pub use error::EvalError;
+pub use evaluate::evaluate;
+pub use macros::{Macro, MacroStorage, DEFAULT_MAX_DEPTH};
+pub use value::Value;
@@ -0,0 +1,216 @@
+//! Commands defined in LaPoL itself, with `@define`.
+//!
+//! `@define[greet, who, greeting="Hello"]{@arg{greeting}, @arg{who}!}`
+//! defines `@greet`, with a required parameter `who` and an optional one,
+//! `greeting`. `@greet[World]` then outputs `Hello, World!`. In the body,
+//! `@arg{name}` is the parameter `name`, and `@arg{1}`, `@arg{2}`, ... are the
+//! curly arguments (so `@define[emph]{@bf{@arg{1}}}` defines `@emph{...}`).
+//!
+//! The arguments of a macro are evaluated where it is invoked, and its body
+//! is evaluated in a scope of its own: `@arg` always refers to the innermost
+//! macro being expanded, and what the body defines (e.g. with a nested
+//! `@define`) is dropped once the expansion is done.
+
+use std::{collections::BTreeMap, rc::Rc};
+
+use lapol_core_rs::ltrf::LtrfObj;
+use lapol_parse_rs::AstNode;
+
+use crate::{
+    args::{Args, LazyArgs},
+    command::{Command, CommandContext},
+    error::EvalError,
+    value::Value,
+};
+
+/// Maximum number of nested macro expansions, by default.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Macro expansion state (see `FileContext::storage`). Set `max_depth` to
+/// change the recursion limit.
+#[derive(Debug, Clone)]
+pub struct MacroStorage {
+    pub depth: usize,
+    pub max_depth: usize,
+}
+
+impl Default for MacroStorage {
+    fn default() -> Self {
+        MacroStorage {
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// A command defined with `@define`.
+#[derive(Debug, Clone)]
+pub struct Macro {
+    name: String,
+    /// Required parameters (without default) first, in order. Optional ones
+    /// can only be given as keyword arguments.
+    params: Vec<(String, Option<Value>)>,
+    body: Rc<Vec<AstNode<'static>>>,
+}
+
+impl Macro {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The parameters, with their default values (if optional).
+    pub fn params(&self) -> &[(String, Option<Value>)] {
+        &self.params
+    }
+
+    /// Matches the square arguments of an invocation with the parameters.
+    fn bind(&self, args: &Args) -> Result<BTreeMap<String, Value>, EvalError> {
+        let required = self.params.iter().filter(|(_, d)| d.is_none()).count();
+        if args.square.len() > required {
+            return Err(EvalError::msg(format!(
+                "@{} takes at most {} positional square arguments, got {}.",
+                self.name,
+                required,
+                args.square.len()
+            )));
+        }
+        if let Some(key) = args
+            .keyword
+            .keys()
+            .find(|k| !self.params.iter().any(|(p, _)| p == *k))
+        {
+            return Err(EvalError::msg(format!(
+                "@{} has no parameter `{}`.",
+                self.name, key
+            )));
+        }
+
+        let mut bound = BTreeMap::new();
+        for (idx, (param, default)) in self.params.iter().enumerate() {
+            let value = args
+                .square(idx)
+                .or_else(|| args.keyword(param))
+                .or(default.as_ref())
+                .ok_or_else(|| {
+                    EvalError::msg(format!(
+                        "@{}: Parameter `{}` must be given.",
+                        self.name, param
+                    ))
+                })?;
+            bound.insert(param.clone(), value.clone());
+        }
+        Ok(bound)
+    }
+}
+
+impl Command for Macro {
+    fn call(&self, args: &Args, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        let params = self.bind(args)?;
+        let curly = args.curly.clone();
+
+        let storage = ctx.file.storage::<MacroStorage>();
+        if storage.depth >= storage.max_depth {
+            return Err(EvalError::msg(format!(
+                "Macros nested more than {} deep (is @{} infinitely recursive?)",
+                storage.max_depth, self.name
+            )));
+        }
+        storage.depth += 1;
+
+        ctx.env.push_scope();
+        ctx.env.define_fn("arg", move |a, _| {
+            let which: String = a
+                .curly_or_err(0)?
+                .iter()
+                .filter_map(LtrfObj::as_str)
+                .collect();
+            let which = which.trim();
+            if let Ok(n) = which.parse::<usize>() {
+                return n
+                    .checked_sub(1)
+                    .and_then(|idx| curly.get(idx))
+                    .cloned()
+                    .ok_or_else(|| {
+                        EvalError::msg(format!("arg: No curly argument {} was given.", n))
+                    });
+            }
+            params
+                .get(which)
+                .map(|v| vec![v.to_ltrf()])
+                .ok_or_else(|| EvalError::msg(format!("arg: No parameter `{}`.", which)))
+        });
+        let out = ctx.evaluate(&self.body);
+        ctx.env.pop_scope();
+
+        ctx.file.storage::<MacroStorage>().depth -= 1;
+        out
+    }
+}
+
+/// `@define[name, params...]{body}`: defines a macro in the current scope.
+pub(crate) fn define(a: &LazyArgs, ctx: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+    let mut square = a.square.iter();
+    let name = match square.next() {
+        Some(entry) => ctx.evaluate_entry(entry)?,
+        None => {
+            return Err(EvalError::msg(
+                "define: Must provide the name of the macro (as 0th square argument)",
+            ))
+        }
+    };
+    let name = name
+        .as_str()
+        .ok_or_else(|| EvalError::msg("define: The name of the macro must be a string"))?
+        .to_owned();
+
+    let mut params = Vec::new();
+    for entry in square {
+        match ctx.evaluate_entry(entry)? {
+            Value::Ident(p) | Value::Str(p) => params.push((p, None)),
+            v => {
+                return Err(EvalError::msg(format!(
+                    "define: Parameter names must be identifiers, not a {}",
+                    v.type_name()
+                )))
+            }
+        }
+    }
+    for (param, default) in &a.keyword {
+        params.push((param.clone(), Some(ctx.evaluate_entry(default)?)));
+    }
+    check_params(&params)?;
+
+    let body = a
+        .curly_or_err(0)?
+        .iter()
+        .cloned()
+        .map(AstNode::into_owned)
+        .collect();
+    ctx.env.define(
+        name.clone(),
+        Macro {
+            name,
+            params,
+            body: Rc::new(body),
+        },
+    );
+    Ok(vec![])
+}
+
+fn check_params(params: &[(String, Option<Value>)]) -> Result<(), EvalError> {
+    for (idx, (p, _)) in params.iter().enumerate() {
+        if p.parse::<usize>().is_ok() {
+            return Err(EvalError::msg(format!(
+                "define: Parameter `{}` would be shadowed by the curly argument of that number",
+                p
+            )));
+        }
+        if params[..idx].iter().any(|(q, _)| q == p) {
+            return Err(EvalError::msg(format!(
+                "define: Parameter `{}` is declared twice",
+                p
+            )));
+        }
+    }
+    Ok(())
+}
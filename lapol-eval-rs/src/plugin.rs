@@ -0,0 +1,408 @@
+//! Plugins: WebAssembly modules providing commands and outputters, so that
+//! they can be written in any language that compiles to WASM, and be used by
+//! the native compiler without Node. Only with the `plugins` feature.
+//!
+//! # ABI (version 1)
+//!
+//! Data is exchanged as UTF-8 JSON in the plugin's memory. The host copies
+//! its input into buffers from `lapol_alloc`; a plugin returns a buffer as an
+//! `i64`, `(ptr << 32) | len`. The host never frees anything: a plugin may
+//! reuse its memory once a call returns. A plugin exports:
+//!
+//! - `memory`.
+//! - `lapol_abi_version() -> i32`, which returns 1.
+//! - `lapol_alloc(len: i32) -> i32`: a buffer of `len` bytes.
+//! - `lapol_manifest() -> i64`: what the plugin provides, e.g.
+//!   `{"name": "diagrams", "commands": [{"name": "plot"}, {"name": "verb",
+//!   "lazy": true}], "outputters": [{"target": "html", "tag": "plot"}]}`.
+//!   Its commands make up a module named `name` (see `Plugin::module`).
+//! - `lapol_call(name: i32, name_len: i32, args: i32, args_len: i32) -> i64`,
+//!   if it has commands. The arguments are
+//!   `{"square": [...], "keyword": {...}, "curly": [[...], ...]}`: values and
+//!   LTRF (in the JSON format of `lapol_core_rs::ltrf`), or for `lazy`
+//!   commands, the `SquareEntry`s and `AstNode`s (in the JSON format of
+//!   `lapol_parse_rs`). Returns `{"ok": [LTRF...]}` or `{"err": "message"}`.
+//! - `lapol_output(target: i32, target_len: i32, node: i32, node_len: i32,
+//!   content: i32, content_len: i32) -> i64`, if it has outputters. Gets the
+//!   target name, the LTRF node and its elements, already rendered (as
+//!   code, not JSON). Returns `{"ok": "code"}` or `{"err": "message"}`.
+//!
+//! Plugins are sandboxed: they can't import anything (so, no file system or
+//! network access), and each call is limited by `PluginLimits`.
+
+use std::{cell::RefCell, convert::TryFrom, fs, path::Path, rc::Rc};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::VERBATIM_TAG,
+    LapolError,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Map};
+use wasmtime::{Config, Engine, Memory, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::{
+    args::{Args, LazyArgs},
+    command::{Command, CommandContext, LazyCommand},
+    environment::Module,
+    error::EvalError,
+    value::Value,
+};
+
+pub const ABI_VERSION: i32 = 1;
+
+/// Resource limits for plugins.
+#[derive(Debug, Clone)]
+pub struct PluginLimits {
+    /// Fuel (roughly, WASM instructions) per call.
+    pub fuel: u64,
+    /// In bytes.
+    pub max_memory: usize,
+    /// The size of a call's result, in bytes.
+    pub max_response: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        PluginLimits {
+            fuel: 1_000_000_000,
+            max_memory: 256 << 20,
+            max_response: 64 << 20,
+        }
+    }
+}
+
+/// What a plugin provides (see the ABI in the module docs).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub commands: Vec<CommandDecl>,
+    #[serde(default)]
+    pub outputters: Vec<OutputterDecl>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandDecl {
+    pub name: String,
+    /// Whether the command gets its arguments unevaluated.
+    #[serde(default)]
+    pub lazy: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputterDecl {
+    pub target: String,
+    pub tag: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Response<T> {
+    Ok(T),
+    Err(String),
+}
+
+/// A loaded plugin. Cloning it is cheap (clones share the instance).
+#[derive(Clone)]
+pub struct Plugin {
+    manifest: Rc<Manifest>,
+    instance: Rc<RefCell<Instance>>,
+}
+
+impl Plugin {
+    pub fn load(path: &Path, limits: &PluginLimits) -> Result<Plugin, EvalError> {
+        let bytes = fs::read(path).map_err(|e| {
+            EvalError::msg(format!("Could not read plugin {}: {}", path.display(), e))
+        })?;
+        Plugin::from_bytes(&bytes, limits)
+    }
+
+    /// Instantiates a plugin from its WASM (binary) code.
+    pub fn from_bytes(wasm: &[u8], limits: &PluginLimits) -> Result<Plugin, EvalError> {
+        let instance = Instance::new(wasm, limits)
+            .map_err(|e| EvalError::msg(format!("Could not instantiate plugin: {:#}", e)))?;
+        let instance = Rc::new(RefCell::new(instance));
+        let manifest: Manifest = {
+            let mut i = instance.borrow_mut();
+            let bytes = i
+                .manifest()
+                .map_err(|e| EvalError::msg(format!("Plugin manifest: {:#}", e)))?;
+            serde_json::from_slice(&bytes)
+                .map_err(|e| EvalError::msg(format!("Bad plugin manifest: {}", e)))?
+        };
+
+        Ok(Plugin {
+            manifest: Rc::new(manifest),
+            instance,
+        })
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    /// A module with the plugin's commands, named after the plugin.
+    pub fn module(&self) -> Module {
+        let mut module = Module::new(self.name());
+        for decl in &self.manifest.commands {
+            let command = PluginCommand {
+                plugin: self.clone(),
+                name: decl.name.clone(),
+            };
+            if decl.lazy {
+                module.define_lazy(decl.name.clone(), command);
+            } else {
+                module.define(decl.name.clone(), command);
+            }
+        }
+        module
+    }
+
+    pub fn has_outputter(&self, target: &str, tag: &str) -> bool {
+        self.manifest
+            .outputters
+            .iter()
+            .any(|o| o.target == target && o.tag == tag)
+    }
+
+    /// Renders `node` (whose elements are rendered as `content`) for
+    /// `target`.
+    pub fn output(
+        &self,
+        target: &str,
+        node: &LtrfNode,
+        content: &str,
+    ) -> Result<String, EvalError> {
+        let node = serde_json::to_vec(node).expect("LTRF trees are always serializable");
+        let out = self
+            .instance
+            .borrow_mut()
+            .call(
+                "lapol_output",
+                &[target.as_bytes(), &node, content.as_bytes()],
+            )
+            .map_err(|e| self.error(&format!("{:#}", e)))?;
+        self.response(&out)
+    }
+
+    fn call(&self, name: &str, args: serde_json::Value) -> Result<Vec<LtrfObj>, EvalError> {
+        let args = serde_json::to_vec(&args).expect("Arguments are always serializable");
+        let out = self
+            .instance
+            .borrow_mut()
+            .call("lapol_call", &[name.as_bytes(), &args])
+            .map_err(|e| self.error(&format!("{:#}", e)))?;
+        self.response(&out)
+    }
+
+    fn response<T: DeserializeOwned>(&self, out: &[u8]) -> Result<T, EvalError> {
+        match serde_json::from_slice(out) {
+            Ok(Response::Ok(v)) => Ok(v),
+            Ok(Response::Err(e)) => Err(self.error(&e)),
+            Err(e) => Err(self.error(&format!("Bad response: {}", e))),
+        }
+    }
+
+    fn error(&self, message: &str) -> EvalError {
+        EvalError::msg(format!("Plugin {}: {}", self.name(), message))
+    }
+}
+
+/// Renders the nodes `plugins` have outputters for (for `target`), and
+/// replaces them by `VERBATIM_TAG` nodes with the result. `render` renders
+/// their elements (a `__root` node), with the native renderer for `target`.
+pub fn apply_outputters(
+    root: &LtrfNode,
+    target: &str,
+    plugins: &[Plugin],
+    render: &mut dyn FnMut(&LtrfNode) -> Result<String, LapolError>,
+) -> Result<LtrfNode, EvalError> {
+    let mut elems = Vec::with_capacity(root.elems().len());
+    for e in root.elems() {
+        elems.push(match e {
+            LtrfObj::Node(n) => apply_outputters(n, target, plugins, render)?.into(),
+            s => s.clone(),
+        });
+    }
+    let node = LtrfNode::make(root.tag(), root.kv().clone(), elems);
+
+    match plugins.iter().find(|p| p.has_outputter(target, node.tag())) {
+        None => Ok(node),
+        Some(plugin) => {
+            let content = render(&LtrfNode::make(
+                "__root",
+                LtrfKv::new(),
+                node.elems().to_vec(),
+            ))?;
+            let code = plugin.output(target, &node, &content)?;
+
+            let mut kv = LtrfKv::new();
+            if let Some(b) = node.kv_get("isBlock") {
+                kv.insert("isBlock".to_owned(), b.clone());
+            }
+            Ok(LtrfNode::make(VERBATIM_TAG, kv, vec![code.into()]))
+        }
+    }
+}
+
+struct PluginCommand {
+    plugin: Plugin,
+    name: String,
+}
+
+impl Command for PluginCommand {
+    fn call(&self, args: &Args, _: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        let keyword: Map<_, _> = args
+            .keyword
+            .iter()
+            .map(|(k, v)| (k.clone(), value_to_json(v)))
+            .collect();
+        let square: Vec<_> = args.square.iter().map(value_to_json).collect();
+        self.plugin.call(
+            &self.name,
+            json!({ "square": square, "keyword": keyword, "curly": args.curly }),
+        )
+    }
+}
+
+impl LazyCommand for PluginCommand {
+    fn call(&self, args: &LazyArgs, _: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        self.plugin.call(
+            &self.name,
+            json!({ "square": args.square, "keyword": args.keyword, "curly": args.curly }),
+        )
+    }
+}
+
+fn value_to_json(v: &Value) -> serde_json::Value {
+    match v {
+        Value::Num(n) => json!(n),
+        Value::Bool(b) => json!(b),
+        Value::Ident(s) | Value::Str(s) => json!(s),
+        Value::Ltrf(o) => json!(o),
+    }
+}
+
+/// An instantiated plugin, and its exports.
+struct Instance {
+    store: Store<StoreLimits>,
+    instance: wasmtime::Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    fuel: u64,
+    max_response: usize,
+}
+
+impl Instance {
+    fn new(wasm: &[u8], limits: &PluginLimits) -> wasmtime::Result<Instance> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = wasmtime::Module::new(&engine, wasm)?;
+        if let Some(import) = module.imports().next() {
+            return Err(wasmtime::Error::msg(format!(
+                "Plugins can't import anything, but this one imports {}::{}",
+                import.module(),
+                import.name()
+            )));
+        }
+
+        let mut store = Store::new(
+            &engine,
+            StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory)
+                .instances(1)
+                .build(),
+        );
+        store.limiter(|limits| limits);
+        store.set_fuel(limits.fuel)?;
+        let instance = wasmtime::Instance::new(&mut store, &module, &[])?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "lapol_abi_version")?
+            .call(&mut store, ())?;
+        if version != ABI_VERSION {
+            return Err(wasmtime::Error::msg(format!(
+                "Plugin ABI version {} is not supported (expected {})",
+                version, ABI_VERSION
+            )));
+        }
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("Plugins must export their memory"))?;
+        let alloc = instance.get_typed_func(&mut store, "lapol_alloc")?;
+        Ok(Instance {
+            store,
+            instance,
+            memory,
+            alloc,
+            fuel: limits.fuel,
+            max_response: limits.max_response,
+        })
+    }
+
+    fn manifest(&mut self) -> wasmtime::Result<Vec<u8>> {
+        self.store.set_fuel(self.fuel)?;
+        let f = self
+            .instance
+            .get_typed_func::<(), i64>(&mut self.store, "lapol_manifest")?;
+        let packed = f.call(&mut self.store, ())?;
+        self.read(packed)
+    }
+
+    /// Calls the export `name` with a (pointer, length) pair per input.
+    fn call(&mut self, name: &str, inputs: &[&[u8]]) -> wasmtime::Result<Vec<u8>> {
+        self.store.set_fuel(self.fuel)?;
+
+        let mut params = Vec::with_capacity(inputs.len() * 2);
+        for input in inputs {
+            let len = i32::try_from(input.len())?;
+            let ptr = self.alloc.call(&mut self.store, len)?;
+            self.memory
+                .write(&mut self.store, ptr as u32 as usize, input)?;
+            params.push(wasmtime::Val::I32(ptr));
+            params.push(wasmtime::Val::I32(len));
+        }
+
+        let f = self
+            .instance
+            .get_func(&mut self.store, name)
+            .ok_or_else(|| wasmtime::Error::msg(format!("Plugin doesn't export {}", name)))?;
+        let mut result = [wasmtime::Val::I64(0)];
+        f.call(&mut self.store, &params, &mut result)?;
+        match result[0] {
+            wasmtime::Val::I64(packed) => self.read(packed),
+            _ => Err(wasmtime::Error::msg(format!("{} must return an i64", name))),
+        }
+    }
+
+    /// Reads the buffer `(ptr << 32) | len`.
+    fn read(&self, packed: i64) -> wasmtime::Result<Vec<u8>> {
+        let packed = packed as u64;
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if len > self.max_response {
+            return Err(wasmtime::Error::msg(format!(
+                "Plugin returned {} bytes, more than the limit of {}",
+                len, self.max_response
+            )));
+        }
+        match ptr.checked_add(len) {
+            Some(end) if end <= self.memory.data_size(&self.store) => {}
+            _ => {
+                return Err(wasmtime::Error::msg(format!(
+                    "Plugin returned a buffer ({} bytes at {}) outside of its memory",
+                    len, ptr
+                )))
+            }
+        }
+        let mut out = vec![0; len];
+        self.memory.read(&self.store, ptr, &mut out)?;
+        Ok(out)
+    }
+}
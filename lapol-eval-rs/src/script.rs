@@ -0,0 +1,176 @@
+//! Commands written in [Rhai](https://rhai.rs), run in a sandbox, so that
+//! documents (and their modules) from untrusted authors can be compiled.
+//! Only with the `scripting` feature.
+//!
+//! Each function of a script that takes one parameter (and isn't `private`)
+//! becomes a command. The parameter holds the arguments, as a map:
+//! `#{ square: [...], keyword: #{...}, curly: [[...], ...] }`. LTRF nodes are
+//! maps in the JSON format of `lapol_core_rs::ltrf`, e.g.
+//! `#{ _tag: "bold", _elems: ["text"] }`. A command returns a string, a node,
+//! an array of strings and nodes, or nothing (`()`).
+//!
+//! ```text
+//! fn shout(args) {
+//!     let text = "";
+//!     for o in args.curly[0] { if type_of(o) == "string" { text += o; } }
+//!     text.to_upper()
+//! }
+//! ```
+//!
+//! Scripts have no file system or network access: `import` can't load
+//! anything, `eval` is disabled and `print`/`debug` output is dropped. Each
+//! command call is bounded by `ScriptLimits`.
+
+use std::rc::Rc;
+
+use lapol_core_rs::ltrf::LtrfObj;
+use rhai::{module_resolvers::DummyModuleResolver, Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::{
+    args::Args,
+    command::{Command, CommandContext},
+    environment::Module,
+    error::EvalError,
+    value::Value,
+};
+
+/// Resource limits for scripts. Memory use is bounded by limiting the size of
+/// strings, arrays and maps (so, approximately).
+#[derive(Debug, Clone)]
+pub struct ScriptLimits {
+    /// Operations (roughly, expressions evaluated) per command call.
+    pub max_operations: u64,
+    /// Nesting of function calls.
+    pub max_call_levels: usize,
+    /// In bytes.
+    pub max_string_size: usize,
+    pub max_array_size: usize,
+    pub max_map_size: usize,
+    /// Nesting of expressions, when compiling.
+    pub max_expr_depth: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        ScriptLimits {
+            max_operations: 1_000_000,
+            max_call_levels: 64,
+            max_string_size: 1 << 20,
+            max_array_size: 10_000,
+            max_map_size: 10_000,
+            max_expr_depth: 64,
+        }
+    }
+}
+
+/// Compiles `source` into a module named `name`, with a command per
+/// function (see the module docs).
+pub fn load_module(name: &str, source: &str, limits: &ScriptLimits) -> Result<Module, EvalError> {
+    let engine = sandboxed_engine(limits);
+    let ast = engine
+        .compile(source)
+        .map_err(|e| EvalError::msg(format!("Could not compile script module {}: {}", name, e)))?;
+
+    let script = Rc::new(Script { engine, ast });
+    let mut module = Module::new(name);
+    let commands: Vec<String> = script
+        .ast
+        .iter_functions()
+        .filter(|f| !f.access.is_private() && f.params.len() == 1)
+        .map(|f| f.name.to_owned())
+        .collect();
+    for command in commands {
+        module.define(
+            command.clone(),
+            ScriptCommand {
+                script: script.clone(),
+                name: command,
+            },
+        );
+    }
+    Ok(module)
+}
+
+fn sandboxed_engine(limits: &ScriptLimits) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_levels)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_array_size)
+        .set_max_map_size(limits.max_map_size)
+        .set_max_expr_depths(limits.max_expr_depth, limits.max_expr_depth)
+        // The default resolver loads files.
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+    engine
+}
+
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+struct ScriptCommand {
+    script: Rc<Script>,
+    name: String,
+}
+
+impl Command for ScriptCommand {
+    fn call(&self, args: &Args, _: &mut CommandContext) -> Result<Vec<LtrfObj>, EvalError> {
+        let err = |e: &dyn std::fmt::Display| EvalError::msg(format!("{}: {}", self.name, e));
+
+        let mut map = Map::new();
+        let square: Array = args
+            .square
+            .iter()
+            .map(to_dynamic)
+            .collect::<Result<_, _>>()?;
+        map.insert("square".into(), square.into());
+        let mut keyword = Map::new();
+        for (k, v) in &args.keyword {
+            keyword.insert(k.as_str().into(), to_dynamic(v)?);
+        }
+        map.insert("keyword".into(), keyword.into());
+        map.insert(
+            "curly".into(),
+            rhai::serde::to_dynamic(&args.curly).map_err(|e| err(&e))?,
+        );
+
+        let out: Dynamic = self
+            .script
+            .engine
+            .call_fn(&mut Scope::new(), &self.script.ast, &self.name, (map,))
+            .map_err(|e| err(&e))?;
+
+        let items = if out.is_unit() {
+            vec![]
+        } else if out.is_array() {
+            out.cast::<Array>()
+        } else {
+            vec![out]
+        };
+        items
+            .iter()
+            .map(|o| {
+                rhai::serde::from_dynamic::<LtrfObj>(o).map_err(|_| {
+                    err(&format!(
+                        "Commands must return strings or nodes, not {}",
+                        o.type_name()
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+fn to_dynamic(v: &Value) -> Result<Dynamic, EvalError> {
+    Ok(match v {
+        Value::Num(n) => (*n).into(),
+        Value::Bool(b) => (*b).into(),
+        Value::Ident(s) | Value::Str(s) => s.clone().into(),
+        Value::Ltrf(o) => rhai::serde::to_dynamic(o).map_err(|e| EvalError::msg(e.to_string()))?,
+    })
+}
@@ -0,0 +1,139 @@
+//! The built-in modules: `std::core` and `std::main`.
+//!
+//! Mirrors the commands of `lapol-core/src/std/core.ts` and
+//! `lapol-core/src/std/main.ts`. (The outputters are in
+//! `lapol_core_rs::output`.)
+
+use lapol_core_rs::ltrf::{LtrfKv, LtrfNode, LtrfObj};
+
+use crate::{control, environment::Module, error::EvalError, macros};
+
+pub const STD_CORE: &str = "std::core";
+pub const STD_MAIN: &str = "std::main";
+
+/// `std::main`'s per-file storage (see `FileContext::storage`).
+#[derive(Debug, Clone, Default)]
+pub struct MainStorage {
+    pub count: u64,
+    pub title: Vec<LtrfObj>,
+    pub author: Vec<LtrfObj>,
+}
+
+/// `__doc`, `__require`, `__using` and `__using_all`; and `define` (see
+/// `macros`), `set`, `get`, `if` and `for` (see `control`), which lapol-core
+/// doesn't have.
+pub fn core() -> Module {
+    let mut m = Module::new(STD_CORE);
+
+    m.define_fn("__doc", |a, _| {
+        Ok(vec![make_node("__doc", false, a.curly_or_err(0)?)])
+    });
+
+    // The first argument is the module name.
+    m.define_fn("__require", |a, ctx| {
+        let name = match a.curly_or_err(0)? {
+            [LtrfObj::Str(s)] => s.trim().to_owned(),
+            _ => {
+                return Err(EvalError::msg(
+                    "__require: Must pass in a single module name string",
+                ))
+            }
+        };
+        ctx.env.load_module(&name)?;
+        Ok(vec![])
+    });
+
+    m.define_fn("__using", |a, ctx| {
+        let thing = a.square(0).and_then(|v| v.as_str()).ok_or_else(|| {
+            EvalError::msg(
+                "__using: Must provide thing to use (as 0th square argument), as an identifier",
+            )
+        })?;
+        let full = match a.keyword_str("from")? {
+            Some(from) => format!("{}:{}", from, thing),
+            None => thing.to_owned(),
+        };
+        let as_ = a
+            .keyword_str("as")?
+            .ok_or_else(|| EvalError::msg("__using: Must provide as"))?;
+
+        let target = ctx
+            .env
+            .lookup(&full)
+            .ok_or_else(|| EvalError::msg(format!("__using: Could not find {}", full)))?;
+        ctx.env.define_ref(as_, target);
+        Ok(vec![])
+    });
+
+    m.define_fn("__using_all", |a, ctx| {
+        let from = a
+            .keyword_str("from")?
+            .ok_or_else(|| EvalError::msg("__using_all: Must provide from"))?;
+        let prefix = a.keyword_str("prefix_in")?.unwrap_or("");
+        ctx.env
+            .use_all(from.strip_prefix(':').unwrap_or(from), prefix)?;
+        Ok(vec![])
+    });
+
+    m.define_lazy_fn("define", macros::define);
+
+    m.define_fn("set", control::set);
+    m.define_fn("get", control::get);
+    m.define_lazy_fn("if", control::if_);
+    m.define_lazy_fn("for", control::for_);
+
+    m
+}
+
+/// The "defaults": sections, bold, italic, etc.
+pub fn main() -> Module {
+    let mut m = Module::new(STD_MAIN);
+
+    m.define_fn("count", |_, ctx| {
+        let s = ctx.file.storage::<MainStorage>();
+        s.count += 1;
+        Ok(vec![s.count.to_string().into()])
+    });
+
+    m.define_fn("title", |a, ctx| {
+        ctx.file.storage::<MainStorage>().title = a.curly_or_err(0)?.to_vec();
+        Ok(vec![])
+    });
+
+    m.define_fn("author", |a, ctx| {
+        ctx.file.storage::<MainStorage>().author = a.curly_or_err(0)?.to_vec();
+        Ok(vec![])
+    });
+
+    m.define_fn("maketitle", |_, _| {
+        Ok(vec![make_node("maketitle", true, &[])])
+    });
+
+    let wrap = |m: &mut Module, name: &str, tag: &'static str, is_block: bool| {
+        m.define_fn(name, move |a, _| {
+            Ok(vec![make_node(tag, is_block, a.curly_or_err(0)?)])
+        });
+    };
+
+    wrap(&mut m, "sec", "sec", true);
+    wrap(&mut m, "subsec", "subsec", true);
+    wrap(&mut m, "subsubsec", "subsubsec", true);
+
+    wrap(&mut m, "bf", "bold", false);
+    wrap(&mut m, "it", "italic", false);
+
+    // Block quote
+    wrap(&mut m, "bquot", "bquot", true);
+
+    wrap(&mut m, "marginnote", "marginnote", false);
+
+    m
+}
+
+fn make_node(tag: &str, is_block: bool, elems: &[LtrfObj]) -> LtrfObj {
+    let mut kv = LtrfKv::new();
+    if is_block {
+        kv.insert("isBlock".to_owned(), true.into());
+    }
+    LtrfNode::make(tag, kv, elems.to_vec()).into()
+}
@@ -0,0 +1,94 @@
+use std::{cmp::Ordering, fmt};
+
+use lapol_core_rs::ltrf::LtrfObj;
+
+/// An evaluated square argument entry (see `lapol_parse_rs::SquareEntry`).
+///
+/// lapol-core doesn't distinguish identifiers from quoted strings (both are
+/// strings); here they are kept apart, but `as_str` accepts either.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Num(f64),
+    Bool(bool),
+    /// `@cmd[name]`
+    Ident(String),
+    /// `@cmd["some text"]`
+    Str(String),
+    /// The output of a command (`@cmd[@other{...}]`).
+    Ltrf(LtrfObj),
+}
+
+impl Value {
+    /// The string, for identifiers, quoted strings and LTRF strings.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Ident(s) | Value::Str(s) => Some(s),
+            Value::Ltrf(o) => o.as_str(),
+            _ => None,
+        }
+    }
+
+    pub fn as_num(&self) -> Option<f64> {
+        match self {
+            Value::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The value as LTRF (e.g. to output it). Numbers and booleans become
+    /// strings.
+    pub fn to_ltrf(&self) -> LtrfObj {
+        match self {
+            Value::Ltrf(o) => o.clone(),
+            v => v.to_string().into(),
+        }
+    }
+
+    /// Compares values of the same type: numbers, booleans, or strings
+    /// (identifiers, quoted and LTRF strings are all strings). `None` for
+    /// values of different types, and for LTRF nodes.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a.partial_cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Ltrf(LtrfObj::Node(_)), _) | (_, Value::Ltrf(LtrfObj::Node(_))) => None,
+            (a, b) => Some(a.as_str()?.cmp(b.as_str()?)),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Num(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::Ident(_) => "identifier",
+            Value::Str(_) => "string",
+            Value::Ltrf(LtrfObj::Str(_)) => "string",
+            Value::Ltrf(LtrfObj::Node(_)) => "node",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Ident(s) | Value::Str(s) => f.write_str(s),
+            Value::Ltrf(LtrfObj::Str(s)) => f.write_str(s),
+            Value::Ltrf(LtrfObj::Node(n)) => write!(f, "@{}{{...}}", n.tag()),
+        }
+    }
+}
+
+impl From<LtrfObj> for Value {
+    fn from(o: LtrfObj) -> Self {
+        Value::Ltrf(o)
+    }
+}
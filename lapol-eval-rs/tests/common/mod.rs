@@ -0,0 +1,44 @@
+//! Helpers shared by the integration tests. (Not every test uses all of them.)
+#![allow(dead_code)]
+
+use lapol_core_rs::ltrf::{LtrfNode, LtrfObj};
+use lapol_eval_rs::{evaluate, Environment, EvalError, FileContext};
+
+pub fn eval_in(
+    src: &str,
+    env: &mut Environment,
+    file: &mut FileContext,
+) -> Result<LtrfNode, EvalError> {
+    let ast = lapol_parse_rs::parse(src).unwrap();
+    evaluate(&ast, env, file)
+}
+
+pub fn eval(src: &str, env: &mut Environment) -> Result<LtrfNode, EvalError> {
+    eval_in(src, env, &mut FileContext::new())
+}
+
+/// The elements of the root, as strings (nodes become `tag{...}`).
+pub fn flat(root: &LtrfNode) -> String {
+    fn go(o: &LtrfObj, out: &mut String) {
+        match o {
+            LtrfObj::Str(s) => out.push_str(s),
+            LtrfObj::Node(n) => {
+                out.push_str(n.tag());
+                out.push('{');
+                n.elems().iter().for_each(|e| go(e, out));
+                out.push('}');
+            }
+        }
+    }
+    let mut out = String::new();
+    root.elems().iter().for_each(|e| go(e, &mut out));
+    out
+}
+
+/// The message of the error raised (by a command) when evaluating `src`.
+pub fn root_cause(src: &str, env: &mut Environment) -> String {
+    match eval(src, env).unwrap_err().root_cause() {
+        EvalError::Message(m) => m.clone(),
+        e => panic!("unexpected error: {}", e),
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{eval, flat};
+use lapol_eval_rs::{Environment, Value};
+
+fn run(src: &str) -> String {
+    flat(&eval(src, &mut Environment::with_std()).unwrap())
+}
+
+fn root_cause(src: &str) -> String {
+    common::root_cause(src, &mut Environment::with_std())
+}
+
+#[test]
+fn sets_and_gets_variables() {
+    assert_eq!(
+        run(r#"@set[edition=2, name="Book", draft=true]@get{name}|@get{edition}|@get{draft}"#),
+        "Book|2|true"
+    );
+    assert_eq!(run("@set[x=1]@set[x=2]@get{x}"), "2");
+    assert_eq!(root_cause("@get{nope}"), "Variable `nope` is not set.");
+}
+
+#[test]
+fn variables_are_scoped() {
+    // Assigning to an existing variable changes it where it was defined; new
+    // variables are local to the macro expansion.
+    assert_eq!(run("@set[x=1]@define[m]{@set[x=2, y=3]}@m{}@get{x}"), "2");
+    assert_eq!(
+        root_cause("@define[m]{@set[y=3]}@m{}@get{y}"),
+        "Variable `y` is not set."
+    );
+
+    let mut env = Environment::with_std();
+    env.define_var("target", Value::Ident("latex".into()));
+    let root = eval("@if[target=latex]{print}{web}", &mut env).unwrap();
+    assert_eq!(flat(&root), "print");
+}
+
+#[test]
+fn conditionals_compare_typed_values() {
+    let doc = |cond: &str| {
+        run(&format!(
+            r#"@set[edition=2, name="Book", draft=false]@if[{}]{{yes}}{{no}}"#,
+            cond
+        ))
+    };
+
+    assert_eq!(doc("edition=2"), "yes");
+    assert_eq!(doc("edition=3"), "no");
+    assert_eq!(doc(r#"name="Book""#), "yes");
+    assert_eq!(doc("name=Book"), "yes");
+    assert_eq!(doc("draft"), "no");
+    assert_eq!(doc("draft=false"), "yes");
+    assert_eq!(doc("edition, ge=2"), "yes");
+    assert_eq!(doc("edition, gt=2"), "no");
+    assert_eq!(doc("edition, ne=1"), "yes");
+    assert_eq!(doc("edition, gt=1, lt=3"), "yes");
+    assert_eq!(doc("edition=2, draft=true"), "no");
+
+    assert_eq!(
+        root_cause(r#"@set[edition=2]@if[edition="2"]{}"#),
+        "Can't compare variable `edition` (a number) with a string"
+    );
+    assert_eq!(
+        root_cause("@set[edition=2]@if[edition]{}"),
+        "if: Conditions must be booleans, not a number"
+    );
+}
+
+#[test]
+fn branches_are_only_evaluated_if_taken() {
+    assert_eq!(run("@if[true]{a}{@missing{}}@if[false]{@missing{}}"), "a");
+}
+
+#[test]
+fn loops_over_numbers() {
+    assert_eq!(
+        run("@for[i, from=1, to=3]{@get{i}.}|@for[i, from=3, to=1, step=-1]{@get{i}}"),
+        "1.2.3.|321"
+    );
+    assert_eq!(run("@for[i, from=1, to=0]{x}"), "");
+    assert_eq!(
+        root_cause("@for[i, from=1, to=3, step=0]{}"),
+        "for: step can't be 0"
+    );
+
+    // The loop variable is gone afterwards.
+    assert_eq!(
+        root_cause("@for[i, from=1, to=1]{}@get{i}"),
+        "Variable `i` is not set."
+    );
+}
@@ -0,0 +1,137 @@
+use std::{collections::HashMap, path::PathBuf};
+
+mod common;
+
+use common::{eval, flat};
+use lapol_core_rs::ltrf::LtrfObj;
+use lapol_eval_rs::{evaluate, Environment, EvalError, FileContext, Module, Value};
+use lapol_parse_rs::{IncludeResolver, ParserOptions, SourceMap};
+
+#[test]
+fn evaluates_text_and_commands() {
+    let mut env = Environment::new();
+    env.define_fn("wrap", |a, _| {
+        let mut out = vec!["<".into()];
+        out.extend(a.curly_or_err(0)?.iter().cloned());
+        out.push(">".into());
+        Ok(out)
+    });
+
+    let root = eval("a @wrap{b @wrap{c}} d", &mut env).unwrap();
+    assert_eq!(root.tag(), "__root");
+    assert_eq!(flat(&root), "a <b <c>> d");
+}
+
+#[test]
+fn passes_square_and_keyword_args() {
+    let mut env = Environment::new();
+    env.define_fn("show", |a, _| {
+        let mut out: Vec<LtrfObj> = a
+            .square
+            .iter()
+            .map(|v| format!("{}:{};", v.type_name(), v).into())
+            .collect();
+        for (k, v) in &a.keyword {
+            out.push(format!("{}={};", k, v).into());
+        }
+        Ok(out)
+    });
+    env.define_fn("x", |_, _| Ok(vec!["X".into()]));
+
+    let root = eval(
+        r#"@show[1.5, true, ident, "quoted", @x{}, key=value]"#,
+        &mut env,
+    )
+    .unwrap();
+    assert_eq!(
+        flat(&root),
+        "number:1.5;boolean:true;identifier:ident;string:quoted;string:X;key=value;"
+    );
+}
+
+#[test]
+fn scopes_shadow_outer_definitions() {
+    let mut env = Environment::new();
+    env.define_fn("x", |_, _| Ok(vec!["outer".into()]));
+
+    env.push_scope();
+    env.define_fn("x", |_, _| Ok(vec!["inner".into()]));
+    assert_eq!(flat(&eval("@x{}", &mut env).unwrap()), "inner");
+
+    env.pop_scope();
+    assert_eq!(flat(&eval("@x{}", &mut env).unwrap()), "outer");
+
+    // The global scope stays.
+    env.pop_scope();
+    assert_eq!(env.depth(), 1);
+    assert!(env.lookup("x").is_some());
+}
+
+#[test]
+fn modules_are_qualified_until_used() {
+    let mut m = Module::new("my::mod");
+    m.define_fn("hi", |_, _| Ok(vec!["hello".into()]));
+
+    let mut env = Environment::new();
+    env.register_module(m);
+    assert!(env.lookup("my::mod:hi").is_none());
+
+    env.load_module("my::mod").unwrap();
+    assert_eq!(flat(&eval("@my::mod:hi{}", &mut env).unwrap()), "hello");
+    assert!(env.lookup("hi").is_none());
+
+    env.use_all("my::mod", "m_").unwrap();
+    assert_eq!(flat(&eval("@m_hi{}", &mut env).unwrap()), "hello");
+
+    assert!(env.load_module("nope").is_err());
+}
+
+#[test]
+fn errors_have_a_trace() {
+    let mut env = Environment::new();
+    env.define_fn("outer", |a, _| Ok(a.curly_or_err(0)?.to_vec()));
+    env.define_fn("fail", |_, _| Err(EvalError::msg("boom")));
+
+    let err = eval("@outer{\n  @fail{}\n}", &mut env).unwrap_err();
+    assert!(matches!(err.root_cause(), EvalError::Message(m) if m == "boom"));
+    assert_eq!(
+        err.to_string(),
+        "LaPoL evaluation error --- boom\n    in @fail (line 2, column 3)\n    in @outer (line 1, column 1)"
+    );
+
+    let err = eval("@nope{}", &mut env).unwrap_err();
+    assert!(matches!(err, EvalError::UnknownCommand { ref name, .. } if name == "nope"));
+}
+
+#[test]
+fn diagnostics_point_to_included_files() {
+    let fs: HashMap<PathBuf, String> = vec![
+        ("main.lap".into(), "@outer{@include{ch.lap}}".to_owned()),
+        ("ch.lap".into(), "text\n  @fail{}".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+    let source_map = SourceMap::new();
+    let ast = IncludeResolver::new(&fs, &source_map, ParserOptions::default())
+        .resolve("main.lap")
+        .unwrap();
+
+    let mut env = Environment::new();
+    env.define_fn("outer", |a, _| Ok(a.curly_or_err(0)?.to_vec()));
+    env.define_fn("fail", |_, _| Err(EvalError::msg("boom")));
+    let err = evaluate(&ast, &mut env, &mut FileContext::new()).unwrap_err();
+
+    assert_eq!(
+        err.diagnostic(&source_map),
+        "ch.lap:2:3: LaPoL evaluation error --- boom\n2 |   @fail{}\n  |   ^\n    in @fail \
+         (ch.lap:2:3)\n    in @outer (main.lap:1:1)"
+    );
+}
+
+#[test]
+fn values_convert_to_ltrf() {
+    assert_eq!(Value::Num(2.0).to_ltrf(), LtrfObj::from("2"));
+    assert_eq!(Value::Bool(false).to_ltrf(), LtrfObj::from("false"));
+    assert_eq!(Value::Ident("a".into()).as_str(), Some("a"));
+    assert_eq!(Value::Num(1.0).as_str(), None);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{eval, flat};
+use lapol_eval_rs::{Environment, EvalError, Value};
+use lapol_parse_rs::AstNode;
+
+/// `@when[cond]{then}`, where `cond` may be a command.
+fn env_with_when() -> Environment {
+    let mut env = Environment::with_std();
+    env.define_fn("boom", |_, _| Err(EvalError::msg("boom")));
+    env.define_fn("yes", |_, _| Ok(vec!["true".into()]));
+    env.define_lazy_fn("when", |a, ctx| {
+        let cond = match ctx.evaluate_entry(a.square_or_err(0)?)? {
+            Value::Bool(b) => b,
+            v => v.as_str() == Some("true"),
+        };
+        if cond {
+            ctx.evaluate(a.curly_or_err(0)?)
+        } else {
+            Ok(vec![])
+        }
+    });
+    env
+}
+
+#[test]
+fn lazy_arguments_are_only_evaluated_on_demand() {
+    let mut env = env_with_when();
+    let root = eval("a@when[false]{@boom{}}b@when[@yes{}]{c}", &mut env).unwrap();
+    assert_eq!(flat(&root), "abc");
+
+    let err = eval("@when[true]{@boom{}}", &mut env).unwrap_err();
+    assert!(matches!(err.root_cause(), EvalError::Message(m) if m == "boom"));
+}
+
+#[test]
+fn lazy_commands_can_evaluate_repeatedly() {
+    let mut env = Environment::with_std();
+    env.define_lazy_fn("repeat", |a, ctx| {
+        let n = ctx
+            .evaluate_entry(a.square_or_err(0)?)?
+            .as_num()
+            .ok_or_else(|| EvalError::msg("repeat: expected a number"))?;
+        let mut out = Vec::new();
+        for _ in 0..n as usize {
+            out.extend(ctx.evaluate(a.curly_or_err(0)?)?);
+        }
+        Ok(out)
+    });
+
+    let root = eval(
+        "@__require{std::main}@__using_all[from=std::main]@repeat[3]{@count{}.}",
+        &mut env,
+    )
+    .unwrap();
+    assert_eq!(flat(&root), "1.2.3.");
+}
+
+#[test]
+fn lazy_commands_see_the_ast() {
+    // Outputs its argument as written, without evaluating it.
+    fn verbatim(nodes: &[AstNode], out: &mut String) {
+        for n in nodes {
+            match n {
+                AstNode::AstTextNode { content, .. } => out.push_str(content),
+                AstNode::AstCommandNode {
+                    command_name,
+                    curly_args,
+                    ..
+                } => {
+                    out.push('@');
+                    out.push_str(command_name);
+                    for arg in curly_args {
+                        out.push('{');
+                        verbatim(arg, out);
+                        out.push('}');
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut env = Environment::new();
+    env.define_lazy_fn("verbatim", |a, _| {
+        let mut out = String::new();
+        verbatim(a.curly_or_err(0)?, &mut out);
+        Ok(vec![out.into()])
+    });
+
+    let root = eval("@verbatim{x @missing{y}}", &mut env).unwrap();
+    assert_eq!(flat(&root), "x @missing{y}");
+}
+
+#[test]
+fn evaluates_in_a_scope_or_another_environment() {
+    let mut env = Environment::new();
+    env.define_fn("defx", |_, ctx| {
+        ctx.env.define_fn("x", |_, _| Ok(vec!["x".into()]));
+        Ok(vec![])
+    });
+    env.define_lazy_fn("local", |a, ctx| ctx.evaluate_scoped(a.curly_or_err(0)?));
+    env.define_lazy_fn("shouting", |a, ctx| {
+        let mut other = Environment::new();
+        other.define_fn("x", |_, _| Ok(vec!["X".into()]));
+        ctx.evaluate_in(&mut other, a.curly_or_err(0)?)
+    });
+
+    let root = eval("@local{@defx{}@x{}}@shouting{@x{}}", &mut env).unwrap();
+    assert_eq!(flat(&root), "xX");
+
+    // `x` was only defined inside `@local`.
+    let err = eval("@local{@defx{}}@x{}", &mut env).unwrap_err();
+    assert!(matches!(err, EvalError::UnknownCommand { name, .. } if name == "x"));
+}
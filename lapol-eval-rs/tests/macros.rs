@@ -0,0 +1,85 @@
+mod common;
+
+use common::{eval_in, flat, root_cause};
+use lapol_core_rs::ltrf::LtrfNode;
+use lapol_eval_rs::{Environment, EvalError, FileContext, MacroStorage};
+
+fn eval(src: &str) -> Result<LtrfNode, EvalError> {
+    common::eval(src, &mut Environment::with_std())
+}
+
+#[test]
+fn substitutes_parameters_and_defaults() {
+    let root = eval(
+        r#"@define[greet, who, greeting="Hello"]{@arg{greeting}, @arg{who}!}@greet[World]|@greet[you, greeting=Hi]|@greet[who=all]"#,
+    )
+    .unwrap();
+    assert_eq!(flat(&root), "Hello, World!|Hi, you!|Hello, all!");
+
+    let root = eval("@define[twice]{@arg{1}@arg{1}@arg{2}}@twice{ab}{.}").unwrap();
+    assert_eq!(flat(&root), "abab.");
+}
+
+#[test]
+fn rejects_bad_invocations() {
+    let root_cause = |src: &str| root_cause(src, &mut Environment::with_std());
+
+    assert_eq!(
+        root_cause("@define[greet, who]{}@greet{}"),
+        "@greet: Parameter `who` must be given."
+    );
+    assert_eq!(
+        root_cause("@define[greet, who]{}@greet[a, b]"),
+        "@greet takes at most 1 positional square arguments, got 2."
+    );
+    assert_eq!(
+        root_cause("@define[greet, who]{}@greet[a, whom=b]"),
+        "@greet has no parameter `whom`."
+    );
+    assert_eq!(
+        root_cause("@define[m]{@arg{2}}@m{a}"),
+        "arg: No curly argument 2 was given."
+    );
+}
+
+#[test]
+fn nested_definitions_are_hygienic() {
+    // `inner`'s `@arg{1}` is its own argument, which is `outer`'s (evaluated
+    // where `inner` is invoked).
+    let src = "@define[outer]{@define[inner]{[@arg{1}]}@inner{@arg{1}-}}@outer{x}";
+    assert_eq!(flat(&eval(src).unwrap()), "[x-]");
+
+    // `inner` only exists while `outer` is expanded.
+    let err = eval("@define[outer]{@define[inner]{}}@outer{}@inner{}").unwrap_err();
+    assert!(matches!(err, EvalError::UnknownCommand { name, .. } if name == "inner"));
+}
+
+#[test]
+fn limits_recursion() {
+    let mut file = FileContext::new();
+    file.storage::<MacroStorage>().max_depth = 5;
+
+    let err = eval_in(
+        "@define[loop]{@loop{}}@loop{}",
+        &mut Environment::with_std(),
+        &mut file,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.root_cause(),
+        EvalError::Message(m) if m == "Macros nested more than 5 deep (is @loop infinitely recursive?)"
+    ));
+    // One line per expansion, plus the outermost invocation.
+    assert_eq!(err.to_string().matches("in @loop").count(), 6);
+    assert_eq!(file.storage::<MacroStorage>().depth, 0);
+}
+
+#[test]
+fn errors_point_into_the_body() {
+    let err = eval("@define[bad]{\n  @missing{}}\n@bad{}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "LaPoL evaluation error --- Unknown command `missing` (line 2, column 3)\n    \
+         in @bad (line 3, column 1)"
+    );
+}
@@ -0,0 +1,209 @@
+#![cfg(feature = "plugins")]
+
+mod common;
+
+use common::eval;
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::html::{render_html, HtmlOptions},
+};
+use lapol_eval_rs::{
+    plugin::{apply_outputters, Plugin, PluginLimits},
+    Environment,
+};
+
+const MANIFEST: &str = r#"{"name": "test", "commands": [{"name": "hello"},
+    {"name": "fail"}, {"name": "spin"}, {"name": "raw", "lazy": true},
+    {"name": "bogus"}, {"name": "long"}],
+    "outputters": [{"target": "html", "tag": "box"}]}"#;
+const HELLO: &str = r#"{"ok": ["Hello from WASM"]}"#;
+const FAIL: &str = r#"{"err": "it failed"}"#;
+const RAW: &str = r#"{"ok": ["raw"]}"#;
+const PREFIX: &str = r#"{"ok": "<div class='box'>"#;
+const SUFFIX: &str = r#"</div>"}"#;
+
+/// A plugin written in WAT: `lapol_call` dispatches on the first letter of
+/// the command name (`bogus` returns a buffer past the end of the memory,
+/// `long` all of it), `lapol_output` wraps the content in a `div`.
+fn test_plugin() -> Vec<u8> {
+    let mut data = String::new();
+    let mut offset = 16;
+    let mut place = |s: &str| {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        data += &format!("(data (i32.const {}) \"{}\")\n", offset, escaped);
+        let at = (offset, s.len());
+        offset += s.len();
+        at
+    };
+    let manifest = place(MANIFEST);
+    let hello = place(HELLO);
+    let fail = place(FAIL);
+    let raw = place(RAW);
+    let prefix = place(PREFIX);
+    let suffix = place(SUFFIX);
+    let out = 32768;
+
+    let wat = format!(
+        r#"(module
+  (memory (export "memory") 2)
+  (global $heap (mut i32) (i32.const 4096))
+  {data}
+  (func (export "lapol_abi_version") (result i32) (i32.const 1))
+  (func (export "lapol_alloc") (param $len i32) (result i32) (local $p i32)
+    (local.set $p (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $p))
+  (func $pack (param $p i32) (param $l i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $p)) (i64.const 32))
+      (i64.extend_i32_u (local.get $l))))
+  (func (export "lapol_manifest") (result i64)
+    (call $pack (i32.const {manifest_p}) (i32.const {manifest_l})))
+  (func (export "lapol_call") (param $n i32) (param $nl i32) (param $a i32) (param $al i32)
+      (result i64) (local $c i32)
+    (local.set $c (i32.load8_u (local.get $n)))
+    (if (i32.eq (local.get $c) (i32.const 115)) (then (loop $l (br $l))))
+    (if (i32.eq (local.get $c) (i32.const 102))
+      (then (return (call $pack (i32.const {fail_p}) (i32.const {fail_l})))))
+    (if (i32.eq (local.get $c) (i32.const 114))
+      (then (return (call $pack (i32.const {raw_p}) (i32.const {raw_l})))))
+    (if (i32.eq (local.get $c) (i32.const 98))
+      (then (return (call $pack (i32.const 65536) (i32.const 131072)))))
+    (if (i32.eq (local.get $c) (i32.const 108))
+      (then (return (call $pack (i32.const 0) (i32.const 131072)))))
+    (call $pack (i32.const {hello_p}) (i32.const {hello_l})))
+  (func (export "lapol_output") (param $t i32) (param $tl i32) (param $n i32) (param $nl i32)
+      (param $c i32) (param $cl i32) (result i64)
+    (memory.copy (i32.const {out}) (i32.const {prefix_p}) (i32.const {prefix_l}))
+    (memory.copy (i32.const {content}) (local.get $c) (local.get $cl))
+    (memory.copy (i32.add (i32.const {content}) (local.get $cl))
+      (i32.const {suffix_p}) (i32.const {suffix_l}))
+    (call $pack (i32.const {out})
+      (i32.add (i32.const {wrapping}) (local.get $cl)))))"#,
+        data = data,
+        manifest_p = manifest.0,
+        manifest_l = manifest.1,
+        hello_p = hello.0,
+        hello_l = hello.1,
+        fail_p = fail.0,
+        fail_l = fail.1,
+        raw_p = raw.0,
+        raw_l = raw.1,
+        prefix_p = prefix.0,
+        prefix_l = prefix.1,
+        suffix_p = suffix.0,
+        suffix_l = suffix.1,
+        out = out,
+        content = out + prefix.1,
+        wrapping = prefix.1 + suffix.1,
+    );
+    wat::parse_str(wat).unwrap()
+}
+
+fn load(limits: &PluginLimits) -> Plugin {
+    Plugin::from_bytes(&test_plugin(), limits).unwrap()
+}
+
+fn env(plugin: &Plugin) -> Environment {
+    let mut env = Environment::with_std();
+    env.register_module(plugin.module());
+    env.load_module("test").unwrap();
+    env.use_all("test", "").unwrap();
+    env
+}
+
+fn root_cause(src: &str, plugin: &Plugin) -> String {
+    common::root_cause(src, &mut env(plugin))
+}
+
+fn node(tag: &str, elems: Vec<LtrfObj>) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems).into()
+}
+
+#[test]
+fn manifests_are_read() {
+    let plugin = load(&PluginLimits::default());
+    assert_eq!(plugin.name(), "test");
+    let module = plugin.module();
+    let mut names: Vec<_> = module.command_names().collect();
+    names.sort_unstable();
+    assert_eq!(names, ["bogus", "fail", "hello", "long", "raw", "spin"]);
+    assert!(plugin.has_outputter("html", "box"));
+    assert!(!plugin.has_outputter("latex", "box"));
+}
+
+#[test]
+fn plugin_commands_are_called() {
+    let plugin = load(&PluginLimits::default());
+    let root = eval("@hello[a, b=c]{d}, @raw{@missing{}}", &mut env(&plugin)).unwrap();
+    let text: String = root.elems().iter().filter_map(LtrfObj::as_str).collect();
+    assert_eq!(text, "Hello from WASM, raw");
+
+    assert_eq!(root_cause("@fail{}", &plugin), "Plugin test: it failed");
+}
+
+#[test]
+fn plugins_are_limited() {
+    let plugin = load(&PluginLimits {
+        fuel: 100_000,
+        ..PluginLimits::default()
+    });
+    assert!(root_cause("@spin{}", &plugin).contains("fuel"));
+    // Calls get fresh fuel.
+    assert!(eval("@hello{}", &mut env(&plugin)).is_ok());
+
+    let limits = PluginLimits {
+        max_memory: 1 << 16,
+        ..PluginLimits::default()
+    };
+    assert!(Plugin::from_bytes(&test_plugin(), &limits).is_err());
+}
+
+#[test]
+fn plugin_responses_are_checked() {
+    let plugin = load(&PluginLimits::default());
+    assert!(root_cause("@bogus{}", &plugin).contains("outside of its memory"));
+    assert!(eval("@long{}", &mut env(&plugin)).is_err());
+
+    let plugin = load(&PluginLimits {
+        max_response: 1024,
+        ..PluginLimits::default()
+    });
+    assert!(root_cause("@long{}", &plugin).contains("more than the limit of 1024"));
+    assert!(eval("@hello{}", &mut env(&plugin)).is_ok());
+}
+
+#[test]
+fn plugins_cannot_import() {
+    let wasm = wat::parse_str(r#"(module (import "env" "open" (func)))"#).unwrap();
+    let err = Plugin::from_bytes(&wasm, &PluginLimits::default())
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.to_string().contains("imports env::open"));
+}
+
+#[test]
+fn outputters_replace_nodes() {
+    let plugin = load(&PluginLimits::default());
+    let root = LtrfNode::make(
+        "__root",
+        LtrfKv::new(),
+        vec![node(
+            "__p",
+            vec![
+                "x ".into(),
+                node("box", vec!["a ".into(), node("bold", vec!["b".into()])]),
+            ],
+        )],
+    );
+    let options = HtmlOptions::default();
+    let root =
+        apply_outputters(&root, "html", &[plugin], &mut |n| render_html(n, &options)).unwrap();
+    assert_eq!(
+        render_html(&root, &options).unwrap(),
+        "<p>x <div class='box'>a <strong>b</strong></div></p>"
+    );
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "scripting")]
+
+mod common;
+
+use common::eval;
+use lapol_core_rs::ltrf::{LtrfNode, LtrfObj};
+use lapol_eval_rs::{
+    script::{self, ScriptLimits},
+    Environment,
+};
+
+const SCRIPT: &str = r#"
+fn greet(args) {
+    let greeting = args.keyword.greeting ?? "Hello";
+    `${greeting}, ${args.square[0]}!`
+}
+
+fn loud(args) {
+    let out = [];
+    for o in args.curly[0] {
+        out.push(if type_of(o) == "string" { o.to_upper() } else { o });
+    }
+    #{ _tag: "bold", _elems: out }
+}
+
+fn nothing(args) {}
+
+fn forever(args) { loop {} }
+
+fn huge(args) {
+    let s = "x";
+    loop { s += s; }
+}
+
+fn sneaky(args) {
+    import "secrets" as s;
+    s::x
+}
+
+private fn hidden(args) { "hidden" }
+
+fn helper(a, b) { a + b }
+"#;
+
+fn env(limits: &ScriptLimits) -> Environment {
+    let mut env = Environment::with_std();
+    env.register_module(script::load_module("doc::script", SCRIPT, limits).unwrap());
+    env.load_module("doc::script").unwrap();
+    env.use_all("doc::script", "").unwrap();
+    env
+}
+
+fn root_cause(src: &str, limits: &ScriptLimits) -> String {
+    common::root_cause(src, &mut env(limits))
+}
+
+#[test]
+fn script_functions_are_commands() {
+    let mut env = env(&ScriptLimits::default());
+    env.define_fn("it", |a, _| {
+        Ok(vec![LtrfNode::make(
+            "italic",
+            Default::default(),
+            a.curly_or_err(0)?.to_vec(),
+        )
+        .into()])
+    });
+    let root = eval(
+        "@greet[World]|@greet[you, greeting=Hi]|@nothing{}@loud{a @it{b}}",
+        &mut env,
+    )
+    .unwrap();
+    let elems = root.elems();
+    let text: String = elems.iter().filter_map(LtrfObj::as_str).collect();
+    assert_eq!(text, "Hello, World!|Hi, you!|");
+
+    let bold = elems.last().unwrap().as_node().unwrap();
+    assert_eq!(bold.tag(), "bold");
+    assert_eq!(bold.elems()[0].as_str(), Some("A "));
+    let italic = bold.elems()[1].as_node().unwrap();
+    assert_eq!(italic.tag(), "italic");
+    assert_eq!(italic.elems()[0].as_str(), Some("b"));
+}
+
+#[test]
+fn only_public_one_parameter_functions_are_commands() {
+    let module = script::load_module("doc::script", SCRIPT, &ScriptLimits::default()).unwrap();
+    let mut names: Vec<_> = module.command_names().collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        ["forever", "greet", "huge", "loud", "nothing", "sneaky"]
+    );
+}
+
+#[test]
+fn scripts_are_limited() {
+    let limits = ScriptLimits {
+        max_operations: 10_000,
+        max_string_size: 1000,
+        ..ScriptLimits::default()
+    };
+    assert!(root_cause("@forever{}", &limits).contains("Too many operations"));
+    assert!(root_cause("@huge{}", &limits).contains("Length of string"));
+}
+
+#[test]
+fn scripts_are_sandboxed() {
+    let limits = ScriptLimits::default();
+    assert!(root_cause("@sneaky{}", &limits).contains("secrets"));
+
+    let err = script::load_module("m", r#"fn f(args) { eval("1") }"#, &limits)
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Could not compile script module m"));
+}
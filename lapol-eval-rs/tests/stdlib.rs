@@ -0,0 +1,72 @@
+use lapol_core_rs::{ltrf::LtrfObj, process};
+use lapol_eval_rs::{evaluate, stdlib::MainStorage, Environment, FileContext};
+
+const DOC: &str = "@__require{std::main}
+@__using_all[from=std::main]
+@title{The @it{Title}}
+@author{Someone}
+@__doc{
+@maketitle{}
+@sec{Intro}
+Some @bf{bold} text, @count{}-@count{}.
+
+@std::main:bquot{Quoted}
+}
+";
+
+#[test]
+fn evaluates_std_main_document() {
+    let mut env = Environment::with_std();
+    let mut file = FileContext::new();
+    let ast = lapol_parse_rs::parse(DOC).unwrap();
+    let root = evaluate(&ast, &mut env, &mut file).unwrap();
+    let root = process::process_pass(&root).unwrap();
+
+    let doc = root.elems()[0].as_node().unwrap();
+    assert_eq!(doc.tag(), "__doc");
+    let tags: Vec<_> = doc
+        .elems()
+        .iter()
+        .filter_map(LtrfObj::as_node)
+        .map(|n| n.tag())
+        .collect();
+    assert_eq!(tags, ["maketitle", "sec", "__p", "bquot"]);
+
+    let para = doc.elems()[2].as_node().unwrap();
+    assert_eq!(para.elems()[1].as_node().unwrap().tag(), "bold");
+    let rest: String = para.elems()[2..]
+        .iter()
+        .filter_map(LtrfObj::as_str)
+        .collect();
+    assert_eq!(rest, " text, 1-2.");
+
+    let storage = file.get::<MainStorage>().unwrap();
+    assert_eq!(storage.title[0].as_str(), Some("The "));
+    assert_eq!(storage.title[1].as_node().unwrap().tag(), "italic");
+    assert_eq!(storage.author, vec![LtrfObj::from("Someone")]);
+}
+
+#[test]
+fn std_main_needs_require_and_using() {
+    let mut env = Environment::with_std();
+    let ast = lapol_parse_rs::parse("@__doc{@sec{x}}").unwrap();
+    assert!(evaluate(&ast, &mut env, &mut FileContext::new()).is_err());
+
+    let ast = lapol_parse_rs::parse("@__doc{@std::main:sec{x}}").unwrap();
+    assert!(evaluate(&ast, &mut env, &mut FileContext::new()).is_err());
+
+    let ast = lapol_parse_rs::parse("@__require{nope}").unwrap();
+    assert!(evaluate(&ast, &mut env, &mut FileContext::new()).is_err());
+}
+
+#[test]
+fn using_aliases_a_single_command() {
+    let mut env = Environment::with_std();
+    let ast = lapol_parse_rs::parse(
+        "@__require{std::main}@__using[bf, from=std::main, as=strong]@strong{x}",
+    )
+    .unwrap();
+    let root = evaluate(&ast, &mut env, &mut FileContext::new()).unwrap();
+    assert_eq!(root.elems()[0].as_node().unwrap().tag(), "bold");
+    assert!(env.lookup("bf").is_none());
+}
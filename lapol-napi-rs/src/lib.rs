@@ -0,0 +1,129 @@
+//! # LaPoL napi
+//!
+//! `lapol-napi-rs` is a native Node.js addon (built with napi-rs) exposing the
+//! parsing API of `lapol-rs`. Unlike the WASM build, the file contents are
+//! read in place (no copy into WASM memory), and parsing can be done off the
+//! main thread: `parse_file_async` runs on the libuv thread pool, and
+//! `parse_files` parses many files on a `rayon` thread pool.
+//!
+//! lapol-core loads this addon if it has been built, and falls back to
+//! `lapol-rs` otherwise (see `lapol-core/src/internal/parse.ts`).
+
+use lapol_parse_rs::{parse_many, AstNode, ParserError, ParserOptions, SourceMap};
+use napi::{bindgen_prelude::*, Env, JsUnknown, Task};
+use napi_derive::napi;
+use serde::Serialize;
+
+/// Like `lapol-rs`'s `init`. Panics are already turned into JS exceptions by
+/// napi-rs, so there is nothing to set up.
+#[napi]
+pub fn init() {}
+
+/// Parses a file's contents into the AST (as `lapol-rs`'s `parse_file` does,
+/// but throws instead of panicking on errors).
+#[napi(js_name = "parse_file")]
+pub fn parse_file(env: Env, file_path: String, file_content_buffer: Buffer) -> Result<JsUnknown> {
+    let root = parse_buffer(&file_path, &file_content_buffer)?;
+    env.to_js_value(&root)
+}
+
+/// Like `parse_file`, but parses on the libuv thread pool. Returns a Promise.
+#[napi(js_name = "parse_file_async")]
+pub fn parse_file_async(file_path: String, file_content_buffer: Buffer) -> AsyncTask<ParseTask> {
+    AsyncTask::new(ParseTask {
+        file_path,
+        content: file_content_buffer,
+    })
+}
+
+/// Reads and parses all of `file_paths` on a thread pool. Returns a Promise
+/// of an array with, for each file (in order), `{ path, ast }` or
+/// `{ path, error }`.
+#[napi(js_name = "parse_files")]
+pub fn parse_files(file_paths: Vec<String>) -> AsyncTask<ParseManyTask> {
+    AsyncTask::new(ParseManyTask::new(file_paths))
+}
+
+pub struct ParseTask {
+    file_path: String,
+    content: Buffer,
+}
+
+impl Task for ParseTask {
+    type Output = AstNode<'static>;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        parse_buffer(&self.file_path, &self.content)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        env.to_js_value(&output)
+    }
+}
+
+pub struct ParseManyTask {
+    file_paths: Vec<String>,
+}
+
+impl ParseManyTask {
+    pub fn new(file_paths: Vec<String>) -> Self {
+        ParseManyTask { file_paths }
+    }
+}
+
+/// Exactly one of `ast` and `error` is set.
+#[derive(Serialize)]
+pub struct ParsedFile {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ast: Option<AstNode<'static>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Task for ParseManyTask {
+    type Output = Vec<ParsedFile>;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let source_map = SourceMap::new();
+        let output = parse_many(&self.file_paths, &source_map, &ParserOptions::default());
+        Ok(output
+            .files
+            .into_iter()
+            .map(|f| {
+                let (ast, error) = match f.result {
+                    Ok(root) => (Some(root.into_owned()), None),
+                    Err(e) => (None, Some(e.diagnostic(&source_map))),
+                };
+                ParsedFile {
+                    path: f.path.to_string_lossy().into_owned(),
+                    ast,
+                    error,
+                }
+            })
+            .collect())
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        env.to_js_value(&output)
+    }
+}
+
+/// Parses a file's contents (which must be UTF-8). The AST is made owned,
+/// since it can't borrow from the JS buffer once returned.
+pub fn parse_buffer(file_path: &str, content: &[u8]) -> Result<AstNode<'static>> {
+    let text = std::str::from_utf8(content)
+        .map_err(|e| Error::from_reason(format!("{} is not valid UTF-8: {}", file_path, e)))?;
+    lapol_parse_rs::parse(text)
+        .map(AstNode::into_owned)
+        .map_err(|e| parse_error(file_path, text, e))
+}
+
+fn parse_error(file_path: &str, text: &str, e: ParserError) -> Error {
+    // Only needed to resolve the position of the error.
+    let source_map = SourceMap::new();
+    source_map.add(file_path, text.to_owned());
+    Error::from_reason(e.diagnostic(&source_map))
+}
@@ -0,0 +1,41 @@
+use lapol_napi_rs::{parse_buffer, ParseManyTask};
+use lapol_parse_rs::AstNode;
+use napi::Task;
+
+#[test]
+fn parses_buffers() {
+    let root = parse_buffer("doc.lap", b"a @b{c}").unwrap();
+    assert!(matches!(root, AstNode::AstRootNode { ref sub_nodes, .. } if sub_nodes.len() == 2));
+
+    let err = parse_buffer("doc.lap", b"a \xff").unwrap_err();
+    assert!(err.reason.starts_with("doc.lap is not valid UTF-8: "));
+
+    let err = parse_buffer("doc.lap", b"ab\n@c{d").unwrap_err();
+    assert!(err.reason.starts_with("doc.lap:2:"), "{}", err.reason);
+    assert!(err.reason.contains("\n2 | @c{d\n"), "{}", err.reason);
+}
+
+#[test]
+fn parses_many_files_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let paths: Vec<_> = ["0.lap", "bad.lap", "2.lap", "missing.lap"]
+        .iter()
+        .map(|name| dir.path().join(name).to_string_lossy().into_owned())
+        .collect();
+    std::fs::write(&paths[0], "@file{0}").unwrap();
+    std::fs::write(&paths[1], "@file{").unwrap();
+    std::fs::write(&paths[2], "@file{2}").unwrap();
+
+    let files = ParseManyTask::new(paths.clone()).compute().unwrap();
+
+    let got: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(got, paths);
+    for i in [0, 2] {
+        assert!(files[i].ast.is_some() && files[i].error.is_none());
+    }
+    let error = files[1].error.as_ref().unwrap();
+    assert!(files[1].ast.is_none());
+    assert!(error.starts_with(&format!("{}:1:", paths[1])), "{}", error);
+    assert!(files[3].ast.is_none());
+    assert!(files[3].error.is_some());
+}
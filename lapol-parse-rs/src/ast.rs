@@ -7,7 +7,7 @@ use std::borrow::Cow;
 ///
 /// This enum has two variants. `Val` represents a single value passed in (e.g.
 /// "a"), `KeyVal` represents a keyword argument (e.g. "c=true")
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "t", content = "c")]
 pub enum SquareArg<'a> {
     Val(SquareEntry<'a>),
@@ -21,22 +21,26 @@ pub enum SquareArg<'a> {
 /// or a command (represented as an AstNode).
 ///
 /// TODO: Introduce numerical arguments (distinguish from ident).
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "t", content = "c")]
 pub enum SquareEntry<'a> {
     Num(f64),
-    Ident(&'a str),
+    Ident(#[serde(borrow)] Cow<'a, str>),
     Bool(bool),
     QuotedStr(String),
     AstNode(AstNode<'a>),
 }
 
 /// Represents an AST node.
-/// Three node types are used:
+/// Six node types are used:
 /// - `AstRootNode` -> Represents the root of the AST.
 /// - `AstCommandNode` -> Represents a command invocation (at-syntax)
 /// - `AstTextNode` -> Represents arbitrary text.
-#[derive(Debug, Serialize, Deserialize)]
+/// - `AstRawNode` -> Represents verbatim text from a raw block (`@raw#"..."#`)
+/// - `AstParagraphBreakNode` -> Represents a paragraph break (blank lines).
+///   Optional, see `ParserOptions::paragraph_breaks`.
+/// - `AstBlankLineNode` -> Marks a blank line, within a paragraph break.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "t")]
 pub enum AstNode<'a> {
     AstRootNode {
@@ -45,8 +49,8 @@ pub enum AstNode<'a> {
         meta: AstNodeMeta,
     },
     AstCommandNode {
-        #[serde(rename = "commandName")]
-        command_name: &'a str,
+        #[serde(rename = "commandName", borrow)]
+        command_name: Cow<'a, str>,
         #[serde(rename = "squareArgs")]
         square_args: Option<Vec<SquareArg<'a>>>,
         #[serde(rename = "curlyArgs")]
@@ -59,10 +63,42 @@ pub enum AstNode<'a> {
         //source_start_line: usize,
         meta: AstNodeMeta,
     },
+    AstRawNode {
+        /// The untouched text between the fences.
+        #[serde(borrow)]
+        content: Cow<'a, str>,
+        /// Language hint, e.g. `html` in `@raw[html]#"..."#`.
+        #[serde(borrow)]
+        lang: Option<Cow<'a, str>>,
+        meta: AstNodeMeta,
+    },
+    /// Only emitted if enabled in `ParserOptions`. Replaces the `"\n"` text
+    /// nodes (and whitespace between them) that make up a paragraph break.
+    AstParagraphBreakNode {
+        /// The newlines and whitespace making up the break.
+        content: Cow<'a, str>,
+        /// One `AstBlankLineNode` per blank line in the break (so, at least
+        /// one).
+        #[serde(rename = "blankLines")]
+        blank_lines: Vec<AstNode<'a>>,
+        meta: AstNodeMeta,
+    },
+    /// Marks a blank line (a line with only whitespace) within an
+    /// `AstParagraphBreakNode`. `meta` is the start of the line.
+    AstBlankLineNode { meta: AstNodeMeta },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Identifies a source file. When parsing a single string, every node has
+/// `FileId(0)`. Ids are assigned by a `SourceMap`, e.g. when resolving
+/// includes (see `IncludeResolver`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FileId(pub u32);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AstNodeMeta {
+    #[serde(rename = "fileId")]
+    pub file_id: FileId,
     #[serde(rename = "startOffset")]
     pub start_offset: usize,
     #[serde(rename = "startLine")]
@@ -70,3 +106,182 @@ pub struct AstNodeMeta {
     #[serde(rename = "startCol")]
     pub start_col: usize,
 }
+
+/// See `AstNode::visit_node_lists_mut`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeListKind {
+    Root,
+    CurlyArg,
+}
+
+impl<'a> SquareArg<'a> {
+    /// A copy that doesn't borrow from the source (see `AstNode::into_owned`).
+    pub fn into_owned(self) -> SquareArg<'static> {
+        match self {
+            SquareArg::Val(v) => SquareArg::Val(v.into_owned()),
+            SquareArg::KeyVal(k, v) => SquareArg::KeyVal(k.into_owned(), v.into_owned()),
+        }
+    }
+}
+
+impl<'a> SquareEntry<'a> {
+    /// A copy that doesn't borrow from the source (see `AstNode::into_owned`).
+    pub fn into_owned(self) -> SquareEntry<'static> {
+        match self {
+            SquareEntry::Num(n) => SquareEntry::Num(n),
+            SquareEntry::Ident(s) => SquareEntry::Ident(Cow::Owned(s.into_owned())),
+            SquareEntry::Bool(b) => SquareEntry::Bool(b),
+            SquareEntry::QuotedStr(s) => SquareEntry::QuotedStr(s),
+            SquareEntry::AstNode(n) => SquareEntry::AstNode(n.into_owned()),
+        }
+    }
+}
+
+impl<'a> AstNode<'a> {
+    /// A copy of this tree that doesn't borrow from the source, e.g. to keep
+    /// part of it around after the source is dropped.
+    pub fn into_owned(self) -> AstNode<'static> {
+        fn owned(s: Cow<str>) -> Cow<'static, str> {
+            Cow::Owned(s.into_owned())
+        }
+        fn owned_list(nodes: Vec<AstNode>) -> Vec<AstNode<'static>> {
+            nodes.into_iter().map(AstNode::into_owned).collect()
+        }
+
+        match self {
+            AstNode::AstRootNode { sub_nodes, meta } => AstNode::AstRootNode {
+                sub_nodes: owned_list(sub_nodes),
+                meta,
+            },
+            AstNode::AstCommandNode {
+                command_name,
+                square_args,
+                curly_args,
+                meta,
+            } => AstNode::AstCommandNode {
+                command_name: owned(command_name),
+                square_args: square_args
+                    .map(|args| args.into_iter().map(SquareArg::into_owned).collect()),
+                curly_args: curly_args.into_iter().map(owned_list).collect(),
+                meta,
+            },
+            AstNode::AstTextNode { content, meta } => AstNode::AstTextNode {
+                content: owned(content),
+                meta,
+            },
+            AstNode::AstRawNode {
+                content,
+                lang,
+                meta,
+            } => AstNode::AstRawNode {
+                content: owned(content),
+                lang: lang.map(owned),
+                meta,
+            },
+            AstNode::AstParagraphBreakNode {
+                content,
+                blank_lines,
+                meta,
+            } => AstNode::AstParagraphBreakNode {
+                content: owned(content),
+                blank_lines: blank_lines.into_iter().map(AstNode::into_owned).collect(),
+                meta,
+            },
+            AstNode::AstBlankLineNode { meta } => AstNode::AstBlankLineNode { meta },
+        }
+    }
+
+    pub fn meta(&self) -> &AstNodeMeta {
+        match self {
+            AstNode::AstRootNode { meta, .. }
+            | AstNode::AstCommandNode { meta, .. }
+            | AstNode::AstTextNode { meta, .. }
+            | AstNode::AstRawNode { meta, .. }
+            | AstNode::AstParagraphBreakNode { meta, .. }
+            | AstNode::AstBlankLineNode { meta } => meta,
+        }
+    }
+
+    pub fn meta_mut(&mut self) -> &mut AstNodeMeta {
+        match self {
+            AstNode::AstRootNode { meta, .. }
+            | AstNode::AstCommandNode { meta, .. }
+            | AstNode::AstTextNode { meta, .. }
+            | AstNode::AstRawNode { meta, .. }
+            | AstNode::AstParagraphBreakNode { meta, .. }
+            | AstNode::AstBlankLineNode { meta } => meta,
+        }
+    }
+
+    /// Calls `f` on every list of sibling nodes in this tree (the sub nodes of
+    /// a root, and each curly argument), children before parents.
+    pub(crate) fn visit_node_lists_mut(
+        &mut self,
+        f: &mut dyn FnMut(&mut Vec<AstNode<'a>>, NodeListKind),
+    ) {
+        match self {
+            AstNode::AstRootNode { sub_nodes, .. } => {
+                sub_nodes.iter_mut().for_each(|n| n.visit_node_lists_mut(f));
+                f(sub_nodes, NodeListKind::Root);
+            }
+            AstNode::AstCommandNode {
+                square_args,
+                curly_args,
+                ..
+            } => {
+                for arg in square_args.iter_mut().flatten() {
+                    let entries = match arg {
+                        SquareArg::Val(v) => vec![v],
+                        SquareArg::KeyVal(k, v) => vec![k, v],
+                    };
+                    for entry in entries {
+                        if let SquareEntry::AstNode(n) = entry {
+                            n.visit_node_lists_mut(f);
+                        }
+                    }
+                }
+                for arg in curly_args.iter_mut() {
+                    arg.iter_mut().for_each(|n| n.visit_node_lists_mut(f));
+                    f(arg, NodeListKind::CurlyArg);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Sets the file id of this node and all its descendants.
+    pub(crate) fn set_file_id(&mut self, file_id: FileId) {
+        self.meta_mut().file_id = file_id;
+
+        match self {
+            AstNode::AstRootNode { sub_nodes, .. } => {
+                sub_nodes.iter_mut().for_each(|n| n.set_file_id(file_id))
+            }
+            AstNode::AstCommandNode {
+                square_args,
+                curly_args,
+                ..
+            } => {
+                for arg in square_args.iter_mut().flatten() {
+                    let entries = match arg {
+                        SquareArg::Val(v) => vec![v],
+                        SquareArg::KeyVal(k, v) => vec![k, v],
+                    };
+                    for entry in entries {
+                        if let SquareEntry::AstNode(n) = entry {
+                            n.set_file_id(file_id);
+                        }
+                    }
+                }
+                curly_args
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|n| n.set_file_id(file_id));
+            }
+            AstNode::AstParagraphBreakNode { blank_lines, .. } => {
+                blank_lines.iter_mut().for_each(|n| n.set_file_id(file_id))
+            }
+            _ => {}
+        }
+    }
+}
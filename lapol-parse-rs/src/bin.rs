@@ -1,40 +1,75 @@
-use std::{
-    error::Error,
-    fs::File,
-    io::{BufReader, Read},
-    time::Instant,
-};
-
-use lapol_parse_rs::parse;
-
-fn load_file(full_path: &str) -> Result<String, Box<dyn Error>> {
-    let file = File::open(full_path)?;
-    let mut buf_reader = BufReader::new(file);
-    let mut contents = String::new();
-    buf_reader.read_to_string(&mut contents)?;
-    Ok(contents)
-}
+use std::{fs, time::Instant};
+
+use lapol_parse_rs::{parse, SourceMap};
 
-fn parse_file_native(file_path: &str) {
-    println!("Parsing {}", file_path);
-    let file_content = load_file(file_path).unwrap();
+/// Results (and timings) go to stdout, failures to stderr. Returns whether
+/// the file was parsed.
+fn parse_file_native(file_path: &str) -> bool {
+    let source_map = SourceMap::new();
+    let file_content = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file_path, e);
+            return false;
+        }
+    };
+    let file_id = source_map.add(file_path, file_content);
 
     let parse_start = Instant::now();
-    let parsed = parse(&file_content);
+    let parsed = parse(source_map.source(file_id));
     let parse_dur = parse_start.elapsed();
 
-    // println!("Parsed --- output:\n{:#?}", parsed);
-
-    println!("\n----------\nParsing took: {:?}", parse_dur);
+    match parsed {
+        Ok(_) => {
+            println!("Parsed {} in {:?}", file_path, parse_dur);
+            true
+        }
+        Err(e) => {
+            eprintln!("{}", e.diagnostic(&source_map));
+            false
+        }
+    }
 }
 
-pub fn main() {
-    println!("Hello!");
+#[cfg(feature = "parallel")]
+fn parse_files_native(file_paths: &[String]) -> bool {
+    use lapol_parse_rs::{parse_many, ParserOptions};
+
+    let source_map = SourceMap::new();
+    let out = parse_many(file_paths, &source_map, &ParserOptions::default());
 
-    let path =
-        "X:\\programming\\programming\\LaPoL Project\\lapol-parse-rs\\parse_tests\\parse3.lap";
+    for f in &out.files {
+        match &f.result {
+            Ok(_) => println!("Parsed {} in {:?}", f.path.display(), f.parse_time),
+            Err(e) => eprintln!("{}", e.diagnostic(&source_map)),
+        }
+    }
 
-    // let path = "X:\\programming\\programming\\LaPoL Project\\lapol-parse-rs\\parse_tests\\stress_test_0.lap";
+    println!("\n----------\n{:#?}", out.timing);
+    out.files.iter().all(|f| f.result.is_ok())
+}
+
+#[cfg(not(feature = "parallel"))]
+fn parse_files_native(file_paths: &[String]) -> bool {
+    let start = Instant::now();
+    let failed = file_paths.iter().filter(|p| !parse_file_native(p)).count();
+    println!("\n----------\nTotal: {:?}", start.elapsed());
+    failed == 0
+}
+
+/// Usage: `lapol-parse-rs-bin <file.lap>...`
+pub fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
 
-    parse_file_native(path);
+    let ok = match paths.as_slice() {
+        [] => {
+            eprintln!("Usage: lapol-parse-rs-bin <file.lap>...");
+            std::process::exit(2);
+        }
+        [path] => parse_file_native(path),
+        _ => parse_files_native(&paths),
+    };
+    if !ok {
+        std::process::exit(1);
+    }
 }
@@ -1,9 +1,46 @@
+use std::{io, path::PathBuf};
+
 use thiserror::Error as TError;
 
+use crate::{
+    ast::{AstNodeMeta, FileId},
+    source_map::SourceMap,
+};
+
+const NOM_ERROR: &str = "LaPoL parser error --- Probably an issue with your LaPoL code";
+
 #[derive(Debug, TError)]
 pub enum ParserError {
-    #[error(
-        "LaPoL parser error --- Probably an issue with your LaPoL code --- Nom returned an error."
-    )]
-    NomError(String),
+    /// `meta` is where parsing failed, and `kind` what nom was looking for.
+    #[error("{} --- {kind} (line {}, column {})", NOM_ERROR, .meta.start_line, .meta.start_col)]
+    NomError { meta: AstNodeMeta, kind: String },
+    #[error("LaPoL parser error --- Could not read file {path}: {source}")]
+    FileRead { path: PathBuf, source: io::Error },
+    #[error("LaPoL parser error --- Bad @include: {0}")]
+    BadInclude(String),
+    /// The string is the include chain, e.g. `a.lap -> b.lap -> a.lap`.
+    #[error("LaPoL parser error --- Include cycle: {0}")]
+    IncludeCycle(String),
+}
+
+impl ParserError {
+    /// Sets the file a parse error is in (like nodes, they are created with
+    /// `FileId(0)`).
+    pub(crate) fn with_file_id(mut self, file_id: FileId) -> Self {
+        if let ParserError::NomError { meta, .. } = &mut self {
+            meta.file_id = file_id;
+        }
+        self
+    }
+
+    /// The error, with its position resolved through `source_map` (see
+    /// `SourceMap::diagnostic`). The file must be in `source_map`.
+    pub fn diagnostic(&self, source_map: &SourceMap) -> String {
+        match self {
+            ParserError::NomError { meta, kind } => {
+                source_map.diagnostic(meta, &format!("{} --- {}", NOM_ERROR, kind))
+            }
+            e => e.to_string(),
+        }
+    }
 }
@@ -0,0 +1,270 @@
+//! Resolution of `@include{path}` directives.
+//!
+//! `IncludeResolver` parses a root file, and replaces every `@include{path}`
+//! command with the contents of the included file, recursively, so that the
+//! result is a single AST spanning multiple files. Every node's
+//! `AstNodeMeta::file_id` tells which file it came from.
+//!
+//! Include paths are relative to the including file. Including the same file
+//! multiple times is fine, but an include cycle is an error.
+//! So is an include used as a square argument, e.g. `@x[@include{a.lap}]`
+//! (one in a curly argument of a command in a square argument is fine).
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    ast::{AstNode, FileId, SquareArg, SquareEntry},
+    error::ParserError,
+    options::ParserOptions,
+    parse::parse_with_options,
+    source_map::SourceMap,
+};
+
+const INCLUDE_COMMAND: &str = "include";
+
+/// The file system used to load included files. Allows e.g. in-memory files
+/// (for testing), or files provided by JavaScript.
+pub trait FileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Reads files using `std::fs`. (This won't work in wasm!)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeFileSystem;
+
+impl FileSystem for NativeFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+impl FileSystem for HashMap<PathBuf, String> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found"))
+    }
+}
+
+/// Parses a file, resolving includes. Loaded files are registered in a
+/// `SourceMap`, which the resulting AST borrows from.
+pub struct IncludeResolver<'a> {
+    fs: &'a dyn FileSystem,
+    source_map: &'a SourceMap,
+    options: ParserOptions,
+}
+
+impl<'a> IncludeResolver<'a> {
+    pub fn new(fs: &'a dyn FileSystem, source_map: &'a SourceMap, options: ParserOptions) -> Self {
+        IncludeResolver {
+            fs,
+            source_map,
+            options,
+        }
+    }
+
+    /// Parses the file at `root_path`, with all includes spliced in.
+    pub fn resolve(&self, root_path: impl AsRef<Path>) -> Result<AstNode<'a>, ParserError> {
+        let root_path = normalize(root_path.as_ref());
+        let mut chain = Vec::new();
+        let mut root = self.parse_file(&root_path, &mut chain)?;
+
+        if let AstNode::AstRootNode { sub_nodes, .. } = &mut root {
+            let nodes = std::mem::take(sub_nodes);
+            *sub_nodes = self.expand(nodes, &root_path, &mut chain)?;
+        }
+
+        Ok(root)
+    }
+
+    fn load(&self, path: &Path) -> Result<(FileId, &'a str), ParserError> {
+        let id = match self.source_map.lookup(path) {
+            Some(id) => id,
+            None => {
+                let text =
+                    self.fs
+                        .read_to_string(path)
+                        .map_err(|source| ParserError::FileRead {
+                            path: path.to_owned(),
+                            source,
+                        })?;
+                self.source_map.add(path, text)
+            }
+        };
+
+        Ok((id, self.source_map.source(id)))
+    }
+
+    /// Parses a file without expanding its includes. `chain` holds the files
+    /// currently being included, and is used to detect cycles.
+    fn parse_file(
+        &self,
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<AstNode<'a>, ParserError> {
+        if chain.iter().any(|p| p == path) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .skip_while(|p| *p != path)
+                .chain(std::iter::once(&path.to_owned()))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(ParserError::IncludeCycle(cycle.join(" -> ")));
+        }
+
+        let (file_id, text) = self.load(path)?;
+        let mut root =
+            parse_with_options(text, &self.options).map_err(|e| e.with_file_id(file_id))?;
+        root.set_file_id(file_id);
+
+        chain.push(path.to_owned());
+        Ok(root)
+    }
+
+    /// Replaces include commands in `nodes` (and its descendants). `path` is
+    /// the file the nodes come from.
+    fn expand(
+        &self,
+        nodes: Vec<AstNode<'a>>,
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<Vec<AstNode<'a>>, ParserError> {
+        let mut out = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            match node {
+                AstNode::AstCommandNode {
+                    ref command_name, ..
+                } if command_name == INCLUDE_COMMAND => {
+                    let target = include_target(self.source_map, &node, path)?;
+                    let included = match self.parse_file(&target, chain)? {
+                        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+                        _ => unreachable!("parse always returns a root node"),
+                    };
+                    out.extend(self.expand(included, &target, chain)?);
+                    chain.pop();
+                }
+                AstNode::AstCommandNode {
+                    command_name,
+                    square_args,
+                    curly_args,
+                    meta,
+                } => out.push(AstNode::AstCommandNode {
+                    command_name,
+                    square_args: square_args
+                        .map(|args| {
+                            args.into_iter()
+                                .map(|arg| self.expand_square_arg(arg, path, chain))
+                                .collect::<Result<_, _>>()
+                        })
+                        .transpose()?,
+                    curly_args: curly_args
+                        .into_iter()
+                        .map(|arg| self.expand(arg, path, chain))
+                        .collect::<Result<_, _>>()?,
+                    meta,
+                }),
+                n => out.push(n),
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Expands includes in the commands of a square argument. An include
+    /// can't be a square argument itself, as it may expand to many nodes.
+    fn expand_square_arg(
+        &self,
+        arg: SquareArg<'a>,
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<SquareArg<'a>, ParserError> {
+        let mut expand_entry = |entry| match entry {
+            SquareEntry::AstNode(node) => {
+                if let AstNode::AstCommandNode { command_name, .. } = &node {
+                    if command_name == INCLUDE_COMMAND {
+                        let location = self.source_map.location(node.meta());
+                        return Err(ParserError::BadInclude(format!(
+                            "at {} --- @include can't be used in a square argument\n{}",
+                            location,
+                            location.snippet()
+                        )));
+                    }
+                }
+                let node = self
+                    .expand(vec![node], path, chain)?
+                    .pop()
+                    .expect("a node that isn't an include expands to itself");
+                Ok(SquareEntry::AstNode(node))
+            }
+            e => Ok(e),
+        };
+
+        Ok(match arg {
+            SquareArg::Val(v) => SquareArg::Val(expand_entry(v)?),
+            SquareArg::KeyVal(k, v) => SquareArg::KeyVal(expand_entry(k)?, expand_entry(v)?),
+        })
+    }
+}
+
+/// Finds the path of the file included by `node`, an include command that
+/// appears in the file at `path`.
+fn include_target(
+    source_map: &SourceMap,
+    node: &AstNode,
+    path: &Path,
+) -> Result<PathBuf, ParserError> {
+    let location = source_map.location(node.meta());
+
+    let target = match node {
+        AstNode::AstCommandNode {
+            square_args: None,
+            curly_args,
+            ..
+        } => match curly_args.as_slice() {
+            [arg] => match arg.as_slice() {
+                [AstNode::AstTextNode { content, .. }] => content.trim(),
+                _ => "",
+            },
+            _ => "",
+        },
+        _ => "",
+    };
+
+    if target.is_empty() {
+        return Err(ParserError::BadInclude(format!(
+            "at {} --- expected a single curly argument containing the path, e.g. @include{{chapter1.lap}}\n{}",
+            location,
+            location.snippet()
+        )));
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok(normalize(&dir.join(target)))
+}
+
+/// Lexically normalizes a path (removes `.` and resolves `..` where
+/// possible), so that the same file is always identified by the same path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            c => out.push(c),
+        }
+    }
+
+    out
+}
@@ -5,8 +5,24 @@
 
 mod ast;
 mod error;
+mod include;
+mod markup;
+mod options;
+mod paragraphs;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod parse;
+mod source_map;
+mod text_utils;
+mod whitespace;
 
-pub use ast::AstNode;
+pub use ast::{AstNode, AstNodeMeta, FileId, SquareArg, SquareEntry};
 pub use error::ParserError;
-pub use parse::parse;
+pub use include::{FileSystem, IncludeResolver, NativeFileSystem};
+pub use markup::MarkupOptions;
+pub use options::ParserOptions;
+#[cfg(feature = "parallel")]
+pub use parallel::{parse_many, FileParseResult, ParseManyOutput, ParseManyTiming};
+pub use parse::{parse, parse_with_options};
+pub use source_map::{ColumnEncoding, LineCol, Location, SourceFile, SourceMap};
+pub use whitespace::WhitespaceOptions;
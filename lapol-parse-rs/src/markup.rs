@@ -0,0 +1,378 @@
+//! Markdown-style markup layer.
+//!
+//! When enabled (see `ParserOptions::markup`), a small set of inline
+//! shorthands is recognised in text and desugared into ordinary command
+//! nodes, so that lapol-core modules handle them like any other command:
+//!
+//! - `*emph*` -> `@it{emph}`
+//! - `**strong**` -> `@bf{strong}`
+//! - `` `code` `` -> `@code{code}`
+//! - `# heading` at line start -> `@sec{heading}` (`##` -> `@subsec`, etc.)
+//!
+//! The target command names are configurable, see `MarkupOptions`.
+//!
+//! Emphasis may span commands and single line breaks, but not blank lines.
+//! An opening `*` must be followed by a non-whitespace character and a
+//! closing `*` must be preceded by one, so e.g. `2 * 3 * 4` is left alone.
+//! Unmatched delimiters are kept as literal text. Code spans must fit within
+//! a single line of text; use `@raw` for anything more involved.
+
+use std::{borrow::Cow, collections::VecDeque};
+
+use crate::{
+    ast::{AstNode, AstNodeMeta, SquareArg, SquareEntry},
+    text_utils::{meta_at, slice},
+};
+
+/// Configures which commands the markup shorthands desugar into.
+#[derive(Debug, Clone)]
+pub struct MarkupOptions {
+    /// Command for `*emph*`.
+    pub emph_command: String,
+    /// Command for `**strong**`.
+    pub strong_command: String,
+    /// Command for `` `code` ``.
+    pub code_command: String,
+    /// Commands for headings; the first entry is used for `#`, the second for
+    /// `##`, and so on. Lines starting with more `#`'s than there are entries
+    /// are left alone.
+    pub heading_commands: Vec<String>,
+}
+
+impl Default for MarkupOptions {
+    /// Defaults matching the commands exported by `std::main`.
+    fn default() -> Self {
+        MarkupOptions {
+            emph_command: "it".to_owned(),
+            strong_command: "bf".to_owned(),
+            code_command: "code".to_owned(),
+            heading_commands: vec![
+                "sec".to_owned(),
+                "subsec".to_owned(),
+                "subsubsec".to_owned(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Delim {
+    Emph,
+    Strong,
+    Code,
+}
+
+impl Delim {
+    fn as_str(self) -> &'static str {
+        match self {
+            Delim::Emph => "*",
+            Delim::Strong => "**",
+            Delim::Code => "`",
+        }
+    }
+
+    fn command(self, opts: &MarkupOptions) -> &str {
+        match self {
+            Delim::Emph => &opts.emph_command,
+            Delim::Strong => &opts.strong_command,
+            Delim::Code => &opts.code_command,
+        }
+    }
+}
+
+/// Desugars markup in the tree rooted at `node`.
+pub(crate) fn desugar<'a>(node: AstNode<'a>, opts: &MarkupOptions) -> AstNode<'a> {
+    match node {
+        AstNode::AstRootNode { sub_nodes, meta } => AstNode::AstRootNode {
+            sub_nodes: desugar_nodes(sub_nodes, true, opts),
+            meta,
+        },
+        AstNode::AstCommandNode {
+            command_name,
+            square_args,
+            curly_args,
+            meta,
+        } => AstNode::AstCommandNode {
+            command_name,
+            square_args: square_args.map(|args| {
+                args.into_iter()
+                    .map(|arg| match arg {
+                        SquareArg::Val(v) => SquareArg::Val(desugar_square_entry(v, opts)),
+                        SquareArg::KeyVal(k, v) => SquareArg::KeyVal(
+                            desugar_square_entry(k, opts),
+                            desugar_square_entry(v, opts),
+                        ),
+                    })
+                    .collect()
+            }),
+            curly_args: curly_args
+                .into_iter()
+                .map(|arg| desugar_nodes(arg, false, opts))
+                .collect(),
+            meta,
+        },
+        n => n,
+    }
+}
+
+fn desugar_square_entry<'a>(entry: SquareEntry<'a>, opts: &MarkupOptions) -> SquareEntry<'a> {
+    match entry {
+        SquareEntry::AstNode(n) => SquareEntry::AstNode(desugar(n, opts)),
+        e => e,
+    }
+}
+
+/// `at_line_start` tells us whether the first node starts a line (true for
+/// the root, false for curly arguments).
+fn desugar_nodes<'a>(
+    nodes: Vec<AstNode<'a>>,
+    at_line_start: bool,
+    opts: &MarkupOptions,
+) -> Vec<AstNode<'a>> {
+    let nodes = nodes.into_iter().map(|n| desugar(n, opts)).collect();
+    let nodes = desugar_headings(nodes, at_line_start, opts);
+    desugar_inline(nodes, opts)
+}
+
+fn desugar_headings<'a>(
+    nodes: Vec<AstNode<'a>>,
+    at_line_start: bool,
+    opts: &MarkupOptions,
+) -> Vec<AstNode<'a>> {
+    let mut out = Vec::with_capacity(nodes.len());
+    let mut line_start = at_line_start;
+    let mut nodes = nodes.into_iter().peekable();
+
+    while let Some(node) = nodes.next() {
+        let level = match &node {
+            AstNode::AstTextNode { content, .. } if line_start => heading_level(content, opts),
+            _ => None,
+        };
+        line_start = is_newline(&node);
+
+        let (content, meta) = match (level, node) {
+            (Some(_), AstNode::AstTextNode { content, meta }) => (content, meta),
+            (_, n) => {
+                out.push(n);
+                continue;
+            }
+        };
+        let level = level.unwrap();
+
+        let prefix_len = content.len() - content[level..].trim_start().len();
+        let mut body = Vec::new();
+        push_text(
+            &mut body,
+            slice(&content, prefix_len..content.len()),
+            meta_at(&meta, &content, prefix_len),
+        );
+        while let Some(n) = nodes.next_if(|n| !is_newline(n)) {
+            body.push(n);
+        }
+
+        out.push(make_command(
+            &opts.heading_commands[level - 1],
+            desugar_inline(body, opts),
+            meta,
+        ));
+    }
+
+    out
+}
+
+/// Returns the heading level if `content` starts with `#`'s and a space.
+fn heading_level(content: &str, opts: &MarkupOptions) -> Option<usize> {
+    let level = content.len() - content.trim_start_matches('#').len();
+    let valid = level > 0
+        && level <= opts.heading_commands.len()
+        && content[level..].starts_with(' ')
+        && !content[level..].trim().is_empty();
+    if valid {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+fn desugar_inline<'a>(nodes: Vec<AstNode<'a>>, opts: &MarkupOptions) -> Vec<AstNode<'a>> {
+    let mut out = Vec::with_capacity(nodes.len());
+    let mut queue: VecDeque<AstNode<'a>> = nodes.into();
+
+    while let Some(node) = queue.pop_front() {
+        let (content, meta) = match node {
+            AstNode::AstTextNode { content, meta } if !is_newline_text(&content) => (content, meta),
+            n => {
+                out.push(n);
+                continue;
+            }
+        };
+
+        let (pos, delim) = match find_opener(&content) {
+            Some(o) => o,
+            None => {
+                push_text(&mut out, content, meta);
+                continue;
+            }
+        };
+
+        let end = pos + delim.as_str().len();
+        let opener_meta = meta_at(&meta, &content, pos);
+        push_text(&mut out, slice(&content, 0..pos), meta.clone());
+        queue.push_front(AstNode::AstTextNode {
+            content: slice(&content, end..content.len()),
+            meta: meta_at(&meta, &content, end),
+        });
+
+        match take_until_closer(&mut queue, delim) {
+            Some(inner) => {
+                let inner = if delim == Delim::Code {
+                    inner
+                } else {
+                    desugar_inline(inner, opts)
+                };
+                out.push(make_command(delim.command(opts), inner, opener_meta));
+            }
+            None => push_text(&mut out, slice(&content, pos..end), opener_meta),
+        }
+    }
+
+    out
+}
+
+/// Finds the first opening delimiter in `content`.
+fn find_opener(content: &str) -> Option<(usize, Delim)> {
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '`' => return Some((i, Delim::Code)),
+            '*' if content[i..].starts_with("**") => {
+                if followed_by_non_whitespace(content, i + 2) {
+                    return Some((i, Delim::Strong));
+                }
+                chars.next();
+            }
+            '*' if followed_by_non_whitespace(content, i + 1) => return Some((i, Delim::Emph)),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The end of a text node counts as non-whitespace, since the next node may be
+/// a command (e.g. `*@cmd{...}*`).
+fn followed_by_non_whitespace(content: &str, i: usize) -> bool {
+    !matches!(content[i..].chars().next(), Some(c) if c.is_whitespace())
+}
+
+/// Looks for the closing `delim` in `queue`. If found, removes (and returns)
+/// all nodes up to it, and the delimiter itself. If not found, `queue` is
+/// left untouched.
+fn take_until_closer<'a>(
+    queue: &mut VecDeque<AstNode<'a>>,
+    delim: Delim,
+) -> Option<Vec<AstNode<'a>>> {
+    let (node_idx, byte_idx) = find_closer(queue, delim)?;
+
+    let mut inner: Vec<AstNode<'a>> = queue.drain(..node_idx).collect();
+
+    let (content, meta) = match queue.pop_front() {
+        Some(AstNode::AstTextNode { content, meta }) => (content, meta),
+        _ => unreachable!("find_closer only returns positions in text nodes"),
+    };
+    let end = byte_idx + delim.as_str().len();
+
+    push_text(&mut inner, slice(&content, 0..byte_idx), meta.clone());
+    queue.push_front(AstNode::AstTextNode {
+        content: slice(&content, end..content.len()),
+        meta: meta_at(&meta, &content, end),
+    });
+
+    Some(inner)
+}
+
+fn find_closer(queue: &VecDeque<AstNode>, delim: Delim) -> Option<(usize, usize)> {
+    // Whether the character right before the current position is whitespace.
+    let mut prev_is_whitespace = true;
+    // Newlines seen since the last non-whitespace text. Two means a blank line.
+    let mut newlines = 0;
+
+    for (node_idx, node) in queue.iter().enumerate() {
+        let content = match node {
+            AstNode::AstTextNode { content, .. } if is_newline_text(content) => {
+                newlines += 1;
+                if delim == Delim::Code || newlines >= 2 {
+                    return None;
+                }
+                prev_is_whitespace = true;
+                continue;
+            }
+            AstNode::AstTextNode { content, .. } => content,
+            _ => {
+                newlines = 0;
+                prev_is_whitespace = false;
+                continue;
+            }
+        };
+
+        if !content.trim().is_empty() {
+            newlines = 0;
+        }
+
+        let mut chars = content.char_indices();
+        while let Some((i, c)) = chars.next() {
+            let at_double = content[i..].starts_with("**");
+            let matches = match delim {
+                Delim::Code => c == '`',
+                Delim::Strong => at_double && !prev_is_whitespace,
+                Delim::Emph => c == '*' && !at_double && !prev_is_whitespace,
+            };
+            if matches {
+                return Some((node_idx, i));
+            }
+            if at_double {
+                chars.next();
+            }
+            prev_is_whitespace = c.is_whitespace();
+        }
+    }
+
+    None
+}
+
+fn is_newline(node: &AstNode) -> bool {
+    matches!(node, AstNode::AstTextNode { content, .. } if is_newline_text(content))
+}
+
+/// Accepts `"\r\n"` as well as `"\n"`, like the paragraph and whitespace
+/// passes.
+fn is_newline_text(content: &str) -> bool {
+    content == "\n" || content == "\r\n"
+}
+
+fn make_command<'a>(name: &str, arg: Vec<AstNode<'a>>, meta: AstNodeMeta) -> AstNode<'a> {
+    AstNode::AstCommandNode {
+        command_name: Cow::Owned(name.to_owned()),
+        square_args: None,
+        curly_args: vec![arg],
+        meta,
+    }
+}
+
+/// Pushes a text node, merging it into the last node if that is also a
+/// (non-newline) text node, like the parser does.
+fn push_text<'a>(out: &mut Vec<AstNode<'a>>, content: Cow<'a, str>, meta: AstNodeMeta) {
+    if content.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some(AstNode::AstTextNode {
+            content: last_content,
+            ..
+        }) if !is_newline_text(last_content) && !is_newline_text(&content) => {
+            last_content.to_mut().push_str(&content)
+        }
+        _ => out.push(AstNode::AstTextNode { content, meta }),
+    }
+}
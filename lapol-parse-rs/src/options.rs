@@ -0,0 +1,17 @@
+use crate::{markup::MarkupOptions, whitespace::WhitespaceOptions};
+
+/// Options controlling how LaPoL code is parsed. See `parse_with_options`.
+///
+/// The default options parse plain LaPoL, like `parse` does.
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    /// If set, enables the Markdown-style markup layer (`*emph*`,
+    /// `**strong**`, etc.). See `MarkupOptions`.
+    pub markup: Option<MarkupOptions>,
+    /// If true, runs of blank lines are replaced by `AstParagraphBreakNode`s
+    /// instead of being left as `"\n"` (and whitespace) text nodes.
+    pub paragraph_breaks: bool,
+    /// Whitespace normalisation (dedenting, etc.). Off by default. See
+    /// `WhitespaceOptions`.
+    pub whitespace: WhitespaceOptions,
+}
@@ -0,0 +1,114 @@
+//! Parse-time paragraph detection.
+//!
+//! The parser keeps `"\n"` text nodes separate, so that lapol-core can find
+//! blank lines and build paragraphs. When enabled (see
+//! `ParserOptions::paragraph_breaks`), this pass instead replaces each run of
+//! two or more newlines (with only whitespace between them) by an
+//! `AstParagraphBreakNode` (with an `AstBlankLineNode` per blank line), so
+//! downstream passes don't have to rediscover paragraph boundaries.
+
+use std::borrow::Cow;
+
+use crate::ast::{AstNode, AstNodeMeta};
+
+pub(crate) fn detect_paragraph_breaks(root: &mut AstNode) {
+    root.visit_node_lists_mut(&mut |nodes, _| {
+        if nodes.iter().filter(|n| is_newline(n)).count() >= 2 {
+            *nodes = replace_breaks(std::mem::take(nodes));
+        }
+    });
+}
+
+struct PendingBreak {
+    content: String,
+    blank_lines: Vec<AstNode<'static>>,
+    meta: AstNodeMeta,
+    /// Start of the line currently being scanned, if it has any whitespace.
+    line_start: Option<AstNodeMeta>,
+}
+
+fn replace_breaks(nodes: Vec<AstNode>) -> Vec<AstNode> {
+    let breaks = find_breaks(&nodes);
+    let mut breaks = breaks.into_iter().peekable();
+
+    let mut out = Vec::with_capacity(nodes.len());
+    let mut pending: Option<PendingBreak> = None;
+
+    for (i, node) in nodes.into_iter().enumerate() {
+        let (start, end) = match breaks.peek() {
+            Some(&(start, end)) if i >= start => (start, end),
+            _ => {
+                out.push(node);
+                continue;
+            }
+        };
+
+        let p = pending.get_or_insert_with(|| PendingBreak {
+            content: String::new(),
+            blank_lines: Vec::new(),
+            meta: node.meta().clone(),
+            line_start: None,
+        });
+
+        if let AstNode::AstTextNode { content, .. } = &node {
+            p.content.push_str(content);
+        }
+        if !is_newline(&node) {
+            p.line_start.get_or_insert_with(|| node.meta().clone());
+        } else if i != start {
+            let meta = p.line_start.take().unwrap_or_else(|| node.meta().clone());
+            p.blank_lines.push(AstNode::AstBlankLineNode { meta });
+        }
+
+        if i == end {
+            let p = pending.take().unwrap();
+            out.push(AstNode::AstParagraphBreakNode {
+                content: Cow::Owned(p.content),
+                blank_lines: p.blank_lines,
+                meta: p.meta,
+            });
+            breaks.next();
+        }
+    }
+
+    out
+}
+
+/// Returns the (inclusive) index ranges of paragraph breaks: runs starting
+/// and ending in a newline, containing at least two newlines, and otherwise
+/// only whitespace.
+fn find_breaks(nodes: &[AstNode]) -> Vec<(usize, usize)> {
+    let mut breaks = Vec::new();
+    let mut i = 0;
+
+    while i < nodes.len() {
+        if !is_newline(&nodes[i]) {
+            i += 1;
+            continue;
+        }
+
+        let mut last_newline = i;
+        let mut j = i + 1;
+        while j < nodes.len() && (is_newline(&nodes[j]) || is_whitespace_text(&nodes[j])) {
+            if is_newline(&nodes[j]) {
+                last_newline = j;
+            }
+            j += 1;
+        }
+
+        if last_newline > i {
+            breaks.push((i, last_newline));
+        }
+        i = last_newline + 1;
+    }
+
+    breaks
+}
+
+fn is_newline(node: &AstNode) -> bool {
+    matches!(node, AstNode::AstTextNode { content, .. } if content == "\n" || content == "\r\n")
+}
+
+fn is_whitespace_text(node: &AstNode) -> bool {
+    matches!(node, AstNode::AstTextNode { content, .. } if content.trim().is_empty())
+}
@@ -0,0 +1,140 @@
+//! Parsing many files at once, using a `rayon` thread pool.
+//!
+//! Only available with the `parallel` feature (and not in wasm).
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    ast::{AstNode, FileId},
+    error::ParserError,
+    options::ParserOptions,
+    parse::parse_with_options,
+    source_map::SourceMap,
+};
+
+/// The outcome of parsing one of the files passed to `parse_many`.
+#[derive(Debug)]
+pub struct FileParseResult<'a> {
+    pub path: PathBuf,
+    /// `None` if the file could not be read.
+    pub file_id: Option<FileId>,
+    pub result: Result<AstNode<'a>, ParserError>,
+    /// Time spent parsing this file (not including reading it).
+    pub parse_time: Duration,
+}
+
+/// Aggregate timing of a `parse_many` call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseManyTiming {
+    /// Wall time spent reading all files.
+    pub read: Duration,
+    /// Wall time spent parsing all files.
+    pub parse: Duration,
+    /// Sum of the per-file parse times. Compare with `parse` to see how well
+    /// parsing parallelized.
+    pub parse_cpu: Duration,
+    pub total: Duration,
+}
+
+#[derive(Debug)]
+pub struct ParseManyOutput<'a> {
+    /// One entry per input path, in the same order.
+    pub files: Vec<FileParseResult<'a>>,
+    pub timing: ParseManyTiming,
+}
+
+impl<'a> ParseManyOutput<'a> {
+    /// The errors (reading or parsing) that occurred, with their file paths.
+    pub fn errors(&self) -> impl Iterator<Item = (&Path, &ParserError)> {
+        self.files
+            .iter()
+            .filter_map(|f| f.result.as_ref().err().map(|e| (f.path.as_path(), e)))
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+/// Reads and parses `paths` concurrently. The files are registered in
+/// `source_map` (which the resulting ASTs borrow from), and a failure to read
+/// or parse one file does not affect the others.
+pub fn parse_many<'a, P: AsRef<Path> + Sync>(
+    paths: &[P],
+    source_map: &'a SourceMap,
+    options: &ParserOptions,
+) -> ParseManyOutput<'a> {
+    let start = Instant::now();
+
+    let texts: Vec<_> = paths
+        .par_iter()
+        .map(|p| std::fs::read_to_string(p.as_ref()))
+        .collect();
+    let read = start.elapsed();
+
+    // `SourceMap` is not thread safe, so we register the files on this
+    // thread. The texts it hands out can then be shared freely.
+    let loaded: Vec<_> = paths
+        .iter()
+        .zip(texts)
+        .map(|(p, text)| {
+            let path = p.as_ref().to_owned();
+            let loaded = text
+                .map(|text| {
+                    let id = source_map.add(path.clone(), text);
+                    (id, source_map.source(id))
+                })
+                .map_err(|source| ParserError::FileRead {
+                    path: path.clone(),
+                    source,
+                });
+            (path, loaded)
+        })
+        .collect();
+
+    let parse_start = Instant::now();
+    let files: Vec<_> = loaded
+        .into_par_iter()
+        .map(|(path, loaded)| match loaded {
+            Ok((file_id, text)) => {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("parse_file", path = %path.display()).entered();
+                let file_start = Instant::now();
+                let result = parse_with_options(text, options)
+                    .map(|mut root| {
+                        root.set_file_id(file_id);
+                        root
+                    })
+                    .map_err(|e| e.with_file_id(file_id));
+                FileParseResult {
+                    path,
+                    file_id: Some(file_id),
+                    result,
+                    parse_time: file_start.elapsed(),
+                }
+            }
+            Err(e) => FileParseResult {
+                path,
+                file_id: None,
+                result: Err(e),
+                parse_time: Duration::default(),
+            },
+        })
+        .collect();
+    let parse = parse_start.elapsed();
+
+    ParseManyOutput {
+        timing: ParseManyTiming {
+            read,
+            parse,
+            parse_cpu: files.iter().map(|f| f.parse_time).sum(),
+            total: start.elapsed(),
+        },
+        files,
+    }
+}
@@ -3,7 +3,7 @@ use nom::{
     bytes::complete::{is_a, is_not, tag, tag_no_case},
     character::complete::{anychar, multispace1, none_of},
     combinator::{map, opt, peek, recognize, value},
-    error::ParseError,
+    error::{ErrorKind, ParseError},
     multi::{many0, separated_list0},
     number::complete::double,
     sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
@@ -15,7 +15,10 @@ use std::borrow::{Borrow, Cow};
 
 use std::fmt::Debug;
 
-use crate::parse::ast_meta_utils::ast_meta_from_span;
+use crate::{
+    markup, options::ParserOptions, paragraphs, parse::ast_meta_utils::ast_meta_from_span,
+    whitespace,
+};
 
 use self::string::parse_string;
 
@@ -23,9 +26,11 @@ use super::ast::{AstNode, SquareArg, SquareEntry};
 
 mod ast_meta_utils;
 mod identifier;
+mod raw;
 mod string;
 
 use identifier::identifier;
+use raw::raw_block;
 
 type Span<'a> = LocatedSpan<&'a str>;
 
@@ -41,11 +46,24 @@ const DEFAULT_ESCAPE_MATCH: EscapeMatch = EscapeMatch {
     escape: Cow::Borrowed(""),
 };
 
-const ALLOWED_ESCAPE_SYMBOLS: &'static str = "<([";
+const ALLOWED_ESCAPE_SYMBOLS: &str = "<([";
+
+/// Errors reported by the parser itself, rather than by nom's combinators
+/// (see `error_kind_message`).
+const UNMATCHED_CLOSE: ErrorKind = ErrorKind::Eof;
+const UNCLOSED_CURLY: ErrorKind = ErrorKind::Complete;
+
+fn error_kind_message(kind: ErrorKind) -> String {
+    match kind {
+        UNMATCHED_CLOSE => "closing brace without a matching opening brace".to_owned(),
+        UNCLOSED_CURLY => "curly argument is never closed".to_owned(),
+        kind => format!("expected {}", kind.description()),
+    }
+}
 
 fn generic_open_curly<'a, E: ParseError<Span<'a>>>(
     i: Span<'a>,
-) -> IResult<Span, (Span, EscapeMatch), E> {
+) -> IResult<Span<'a>, (Span<'a>, EscapeMatch<'a>), E> {
     let (r, m) = recognize(pair(
         opt(pair(tag("|"), opt(is_a(ALLOWED_ESCAPE_SYMBOLS)))),
         tag("{"),
@@ -69,7 +87,7 @@ fn escape_matching_char(open_char: char) -> char {
     }
 }
 
-fn get_matching_close_curly(open_curly_form: &str) -> Cow<str> {
+fn get_matching_close_curly(open_curly_form: &str) -> Cow<'_, str> {
     let mut ocf = open_curly_form.chars();
     let n = ocf
         .next()
@@ -100,7 +118,7 @@ fn get_matching_escape(open_curly_form: &str) -> &str {
     let (i, l) = open_curly_form
         .char_indices()
         .next_back()
-        .expect(&format!("Bad open_curly_form {}", open_curly_form));
+        .unwrap_or_else(|| panic!("Bad open_curly_form {}", open_curly_form));
     debug_assert!(l == '{');
     open_curly_form.split_at(i).0
 }
@@ -189,7 +207,7 @@ fn text<'a, E: ParseError<Span<'a>> + Debug>(
             }
         } else {
             // Handle EOF
-            if *rest.fragment() == "" {
+            if rest.fragment().is_empty() {
                 break;
             }
 
@@ -197,7 +215,8 @@ fn text<'a, E: ParseError<Span<'a>> + Debug>(
                 // Order matters!
                 one_newline,
                 |i| comment(em, i),
-                map(|i| command(em, i), |v| Some(v)),
+                map(|i| raw_block(em, i), Some),
+                map(|i| command(em, i), Some),
                 generic_text,
             ))(rest)?;
 
@@ -208,12 +227,14 @@ fn text<'a, E: ParseError<Span<'a>> + Debug>(
         }
     }
 
-    debug_assert!(
-        // If not root, we broke due to brace balance becoming 0.
-        !root_context && brace_balance == 0
-        // If root, we should parse until EOF, and ensure braces are balanced.
-            || root_context && brace_balance == 1 && rest.len() == 0
-    );
+    // If not root, we should break due to brace balance becoming 0. If root,
+    // we should parse until EOF, and ensure braces are balanced.
+    if root_context && brace_balance <= 0 {
+        return Err(nom::Err::Failure(E::from_error_kind(rest, UNMATCHED_CLOSE)));
+    }
+    if !root_context && brace_balance > 0 {
+        return Err(nom::Err::Failure(E::from_error_kind(i, UNCLOSED_CURLY)));
+    }
 
     Ok((rest, contents))
 }
@@ -293,7 +314,9 @@ fn comment<'a, E: ParseError<Span<'a>>>(
     alt((|i| block_comment(em, i), |i| line_comment(em, i)))(i)
 }
 
-fn one_newline<'a, E: ParseError<Span<'a>>>(i: Span<'a>) -> IResult<Span<'a>, Option<AstNode>, E> {
+fn one_newline<'a, E: ParseError<Span<'a>>>(
+    i: Span<'a>,
+) -> IResult<Span<'a>, Option<AstNode<'a>>, E> {
     let (r, m) = alt((preceded(tag("\r"), tag("\n")), tag("\n")))(i)?;
     Ok((
         r,
@@ -310,24 +333,25 @@ fn comma_sep<'a, E: ParseError<Span<'a>>>(i: Span<'a>) -> IResult<Span<'a>, (),
 
 fn square_entry<'a, E: ParseError<Span<'a>> + Debug>(
     i: Span<'a>,
-) -> IResult<Span<'a>, SquareEntry, E> {
+) -> IResult<Span<'a>, SquareEntry<'a>, E> {
     delimited(
         opt(multispace1),
         alt((
-            map(bool, |b| SquareEntry::Bool(b)),
-            map(double, |n| SquareEntry::Num(n)),
-            map(identifier, |s| SquareEntry::Ident(s.borrow())),
-            map(parse_string, |s| SquareEntry::QuotedStr(s)),
-            map(
-                |i| command(&DEFAULT_ESCAPE_MATCH, i),
-                |c| SquareEntry::AstNode(c),
-            ),
+            map(bool, SquareEntry::Bool),
+            map(double, SquareEntry::Num),
+            map(identifier, |s| {
+                SquareEntry::Ident(Cow::Borrowed(s.borrow()))
+            }),
+            map(parse_string, SquareEntry::QuotedStr),
+            map(|i| command(&DEFAULT_ESCAPE_MATCH, i), SquareEntry::AstNode),
         )),
         opt(multispace1),
     )(i)
 }
 
-fn square_arg<'a, E: ParseError<Span<'a>> + Debug>(i: Span<'a>) -> IResult<Span<'a>, SquareArg, E> {
+fn square_arg<'a, E: ParseError<Span<'a>> + Debug>(
+    i: Span<'a>,
+) -> IResult<Span<'a>, SquareArg<'a>, E> {
     alt((
         //
         map(
@@ -339,7 +363,7 @@ fn square_arg<'a, E: ParseError<Span<'a>> + Debug>(i: Span<'a>) -> IResult<Span<
             ),
             |p| SquareArg::KeyVal(p.0, p.1),
         ),
-        map(square_entry, |e| SquareArg::Val(e)),
+        map(square_entry, SquareArg::Val),
     ))(i)
 }
 
@@ -353,6 +377,8 @@ fn curly_argument<'a, E: ParseError<Span<'a>> + Debug>(
     i: Span<'a>,
 ) -> IResult<Span<'a>, Vec<AstNode<'a>>, E> {
     let (rest, (_, em)) = generic_open_curly(i)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("curly_arg", offset = i.location_offset()).entered();
     let (rest, nodes) = text(false, &em, rest)?;
     let (rest, _) = tag(em.close.borrow())(rest)?;
 
@@ -374,11 +400,13 @@ fn command<'a, E: ParseError<Span<'a>> + Debug>(
     let (rest, _) = tag("@")(rest)?;
     let (rest, _) = peek(none_of("%|{"))(rest)?;
     // We can't just use ? because if a command fails to match, we don't
-    // want the command getting treated as some arbitrary text, we WANT a panic.
-    let (rest, o) = command_contents::<E>(i, rest).expect(
-        "Malformed command --- once the command syntax @ matches, a command being malformed is an error."
-    );
-    return Ok((rest, o));
+    // want the command getting treated as some arbitrary text: once the
+    // command syntax @ matches, a command being malformed is an error (a
+    // failure, so that `alt` doesn't try anything else).
+    command_contents::<E>(i, rest).map_err(|e| match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        e => e,
+    })
 }
 
 /// start_span is used only for finding AST meta data.
@@ -387,6 +415,13 @@ fn command_contents<'a, E: ParseError<Span<'a>> + Debug>(
     i: Span<'a>,
 ) -> IResult<Span<'a>, AstNode<'a>, E> {
     let (rest, command_name) = identifier(i)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+        "command",
+        name = *command_name.fragment(),
+        offset = start_span.location_offset()
+    )
+    .entered();
 
     let (rest, end_here_opt) = opt(preceded(
         // Whitespace with potential comments
@@ -401,7 +436,7 @@ fn command_contents<'a, E: ParseError<Span<'a>> + Debug>(
         return Ok((
             rest,
             AstNode::AstCommandNode {
-                command_name: &command_name,
+                command_name: Cow::Borrowed(command_name.fragment()),
                 square_args: None,
                 curly_args: Vec::new(),
                 meta: ast_meta_from_span(start_span),
@@ -439,7 +474,7 @@ fn command_contents<'a, E: ParseError<Span<'a>> + Debug>(
         return Ok((
             rest,
             AstNode::AstCommandNode {
-                command_name: &command_name,
+                command_name: Cow::Borrowed(command_name.fragment()),
                 square_args,
                 curly_args: Vec::new(),
                 meta: ast_meta_from_span(start_span),
@@ -468,7 +503,7 @@ fn command_contents<'a, E: ParseError<Span<'a>> + Debug>(
     Ok((
         rest,
         AstNode::AstCommandNode {
-            command_name: &command_name,
+            command_name: Cow::Borrowed(command_name.fragment()),
             square_args,
             curly_args,
             meta: ast_meta_from_span(start_span),
@@ -489,11 +524,15 @@ fn command_contents<'a, E: ParseError<Span<'a>> + Debug>(
     )) */
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "root", level = "debug", skip_all)
+)]
 fn parse_root<'a, E: ParseError<Span<'a>> + Debug>(
     i: Span<'a>,
 ) -> IResult<Span<'a>, AstNode<'a>, E> {
     let (r, nodes) = text(true, &DEFAULT_ESCAPE_MATCH, i)?;
-    assert!(*r.fragment() == ""); // We are at EOF.
+    assert!(r.fragment().is_empty()); // We are at EOF.
     Ok((
         r,
         AstNode::AstRootNode {
@@ -506,21 +545,68 @@ fn parse_root<'a, E: ParseError<Span<'a>> + Debug>(
 /// Takes in a reference to a string containing the input LaPoL code,
 /// returns an AST (See `AstNode`).
 ///
+/// Equivalent to `parse_with_options` with the default options.
+pub fn parse(input: &str) -> Result<AstNode<'_>, super::error::ParserError> {
+    parse_with_options(input, &ParserOptions::default())
+}
+
+/// Like `parse`, but configurable through `ParserOptions`.
+///
+/// With the `tracing` feature, emits a `parse` span (at the `debug` level),
+/// with spans for the root and each pass over the AST within it, and spans
+/// for each command and curly argument (at the `trace` level).
+///
 /// TODO: Support configurable use of Nom VerboseError (by default it is
 /// too slow)
-pub fn parse<'a>(input: &'a str) -> Result<AstNode<'a>, super::error::ParserError> {
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse", level = "debug", skip_all, fields(len = input.len()))
+)]
+pub fn parse_with_options<'a>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> Result<AstNode<'a>, super::error::ParserError> {
     let i = Span::new(input);
 
     let out = parse_root::<nom::error::Error<Span>>(i);
 
     match out {
-        Ok((_, root)) => Ok(root),
+        Ok((_, mut root)) => {
+            if !options.whitespace.is_noop() {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("whitespace").entered();
+                whitespace::normalize_whitespace(&mut root, &options.whitespace);
+            }
+            // Markup goes before paragraph breaks, as it relies on blank lines
+            // being "\n" nodes.
+            let mut root = match &options.markup {
+                Some(markup_options) => {
+                    #[cfg(feature = "tracing")]
+                    let _span = tracing::debug_span!("markup").entered();
+                    markup::desugar(root, markup_options)
+                }
+                None => root,
+            };
+            if options.paragraph_breaks {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("paragraphs").entered();
+                paragraphs::detect_paragraph_breaks(&mut root);
+            }
+            Ok(root)
+        }
         Err(e) => {
-            println!("Nom Error: {:#?}", e);
-            Err(super::error::ParserError::NomError(format!(
-                "Nom error: {:#?}",
-                e
-            )))
+            #[cfg(feature = "tracing")]
+            tracing::debug!(error = ?e, "Parsing failed");
+            Err(match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => super::error::ParserError::NomError {
+                    meta: ast_meta_from_span(e.input),
+                    kind: error_kind_message(e.code),
+                },
+                nom::Err::Incomplete(_) => super::error::ParserError::NomError {
+                    meta: ast_meta_from_span(i),
+                    kind: "incomplete input".to_owned(),
+                },
+            })
         }
     }
 
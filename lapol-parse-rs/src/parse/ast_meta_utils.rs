@@ -1,9 +1,10 @@
-use crate::ast::AstNodeMeta;
+use crate::ast::{AstNodeMeta, FileId};
 
 use super::Span;
 
 pub(super) fn ast_meta_from_span(i: Span) -> AstNodeMeta {
     AstNodeMeta {
+        file_id: FileId::default(),
         start_offset: i.location_offset(),
         start_line: i.location_line() as usize,
         start_col: i.get_utf8_column(), // TODO: Performance?
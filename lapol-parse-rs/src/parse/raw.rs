@@ -0,0 +1,64 @@
+//! Raw blocks: `@raw#"..."#`.
+//!
+//! A raw block embeds text verbatim --- no commands, comments or escapes are
+//! recognized inside. The user picks the terminator, so any content can be
+//! embedded by choosing a fence that does not occur in it:
+//!
+//! - `@raw#"..."#`, `@raw##"..."##`, ... (Like Rust raw strings)
+//! - `@raw#END"..."END#` (Heredoc-style, with an arbitrary delimiter word)
+//!
+//! A language hint may be given in square braces, e.g. `@raw[html]#"..."#`.
+
+use std::borrow::Cow;
+
+use nom::{
+    bytes::complete::{is_a, tag, take_until},
+    character::complete::alphanumeric1,
+    combinator::{cut, opt},
+    error::ParseError,
+    sequence::{delimited, pair, terminated},
+    IResult,
+};
+
+use crate::ast::AstNode;
+
+use super::{ast_meta_utils::ast_meta_from_span, identifier::identifier, EscapeMatch, Span};
+
+/// Parses the opening fence, i.e. `#`'s followed by an optional delimiter
+/// word and a `"`. Returns the matching closing fence.
+fn open_fence<'a, E: ParseError<Span<'a>>>(i: Span<'a>) -> IResult<Span<'a>, String, E> {
+    let (rest, (hashes, word)) = terminated(pair(is_a("#"), opt(alphanumeric1)), tag("\""))(i)?;
+
+    let mut close = String::with_capacity(1 + word.map_or(0, |w| w.len()) + hashes.len());
+    close.push('"');
+    if let Some(w) = word {
+        close.push_str(w.fragment());
+    }
+    close.push_str(hashes.fragment());
+
+    Ok((rest, close))
+}
+
+pub(super) fn raw_block<'a, E: ParseError<Span<'a>>>(
+    em: &EscapeMatch,
+    i: Span<'a>,
+) -> IResult<Span<'a>, AstNode<'a>, E> {
+    let (rest, _) = tag(em.escape.as_ref())(i)?;
+    let (rest, _) = tag("@raw")(rest)?;
+    let (rest, lang) = opt(delimited(tag("["), identifier, tag("]")))(rest)?;
+    let (rest, close) = open_fence(rest)?;
+
+    // Once the opening fence matches, a missing terminator is an error (we
+    // don't want to silently fall back to treating this as a command).
+    let (rest, content) = cut(take_until(close.as_str()))(rest)?;
+    let (rest, _) = tag(close.as_str())(rest)?;
+
+    Ok((
+        rest,
+        AstNode::AstRawNode {
+            content: Cow::Borrowed(content.fragment()),
+            lang: lang.map(|l| Cow::Borrowed(*l.fragment())),
+            meta: ast_meta_from_span(i),
+        },
+    ))
+}
@@ -0,0 +1,246 @@
+//! Source file registry.
+//!
+//! `AstNodeMeta` positions are only meaningful relative to the text they were
+//! parsed from. A `SourceMap` owns the source texts of a project, assigns
+//! each a `FileId`, and resolves positions back to a file, line, column and
+//! snippet.
+//!
+//! Files can be added through a shared reference, and the texts are never
+//! moved or dropped while the map lives, so ASTs parsed from them can borrow
+//! from the map.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use elsa::FrozenVec;
+
+use crate::ast::{AstNodeMeta, FileId};
+
+/// How columns are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnEncoding {
+    /// Bytes of UTF-8.
+    Utf8,
+    /// UTF-16 code units (e.g. for the Language Server Protocol or
+    /// JavaScript string indices).
+    Utf16,
+    /// Unicode scalar values (Rust `char`s). This is what `AstNodeMeta` uses.
+    Chars,
+}
+
+/// A resolved position. Both `line` and `col` start at 1, like in
+/// `AstNodeMeta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// A file registered in a `SourceMap`.
+#[derive(Debug)]
+pub struct SourceFile {
+    path: PathBuf,
+    text: String,
+    /// Byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, text: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        SourceFile {
+            path,
+            text,
+            line_starts,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The (1-based) line containing the byte `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        }
+    }
+
+    /// The text of the (1-based) `line`, without the line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |&next| next - 1);
+        Some(self.text[start..end].trim_end_matches('\r'))
+    }
+
+    /// Converts a byte `offset` into a line and column.
+    ///
+    /// Panics if `offset` is out of bounds or not on a char boundary.
+    pub fn line_col(&self, offset: usize, encoding: ColumnEncoding) -> LineCol {
+        let line = self.line_of(offset);
+        let before = &self.text[self.line_starts[line - 1]..offset];
+        let col = match encoding {
+            ColumnEncoding::Utf8 => before.len(),
+            ColumnEncoding::Utf16 => before.encode_utf16().count(),
+            ColumnEncoding::Chars => before.chars().count(),
+        };
+        LineCol { line, col: col + 1 }
+    }
+
+    /// Converts a line and column back into a byte offset. Columns past the
+    /// end of the line are clamped to it.
+    pub fn offset(&self, pos: LineCol, encoding: ColumnEncoding) -> Option<usize> {
+        let start = *self.line_starts.get(pos.line.checked_sub(1)?)?;
+        let line = self.line_text(pos.line)?;
+
+        let mut remaining = pos.col.checked_sub(1)?;
+        for (i, c) in line.char_indices() {
+            let width = match encoding {
+                ColumnEncoding::Utf8 => c.len_utf8(),
+                ColumnEncoding::Utf16 => c.len_utf16(),
+                ColumnEncoding::Chars => 1,
+            };
+            if remaining < width {
+                return Some(start + i);
+            }
+            remaining -= width;
+        }
+
+        Some(start + line.len())
+    }
+}
+
+/// Owns the source texts of a project. See the module docs.
+#[derive(Default)]
+pub struct SourceMap {
+    /// Indexed by `FileId`.
+    files: FrozenVec<Box<SourceFile>>,
+    ids: RefCell<HashMap<PathBuf, FileId>>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a file. If a file with the same path was already added, its
+    /// id is returned instead (and `text` is ignored).
+    pub fn add(&self, path: impl Into<PathBuf>, text: String) -> FileId {
+        let path = path.into();
+        if let Some(id) = self.lookup(&path) {
+            return id;
+        }
+
+        let id = FileId(self.files.len() as u32);
+        self.ids.borrow_mut().insert(path.clone(), id);
+        self.files.push(Box::new(SourceFile::new(path, text)));
+        id
+    }
+
+    pub fn lookup(&self, path: &Path) -> Option<FileId> {
+        self.ids.borrow().get(path).copied()
+    }
+
+    pub fn get(&self, file_id: FileId) -> Option<&SourceFile> {
+        self.files.get(file_id.0 as usize)
+    }
+
+    /// Like `get`, but panics on an unknown id.
+    pub fn file(&self, file_id: FileId) -> &SourceFile {
+        self.get(file_id)
+            .unwrap_or_else(|| panic!("Unknown file id {:?}", file_id))
+    }
+
+    pub fn source(&self, file_id: FileId) -> &str {
+        self.file(file_id).text()
+    }
+
+    pub fn path(&self, file_id: FileId) -> &Path {
+        self.file(file_id).path()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.len() == 0
+    }
+
+    /// Resolves the start of a node, e.g. for a diagnostic.
+    pub fn location(&self, meta: &AstNodeMeta) -> Location<'_> {
+        let file = self.file(meta.file_id);
+        Location {
+            file,
+            pos: file.line_col(meta.start_offset, ColumnEncoding::Chars),
+        }
+    }
+
+    /// Formats a diagnostic about the node at `meta`: `path:line:col:
+    /// message`, then the source line (see `Location::snippet`).
+    pub fn diagnostic(&self, meta: &AstNodeMeta, message: &str) -> String {
+        let location = self.location(meta);
+        format!("{}: {}\n{}", location, message, location.snippet())
+    }
+}
+
+impl fmt::Debug for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceMap")
+            .field("files", &self.ids.borrow())
+            .finish()
+    }
+}
+
+/// A position in a file. Displays as `path:line:col`; use `snippet` for the
+/// line itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Location<'a> {
+    pub file: &'a SourceFile,
+    pub pos: LineCol,
+}
+
+impl<'a> Location<'a> {
+    /// The source line, with a `^` marker under the column.
+    pub fn snippet(&self) -> String {
+        let line = self.file.line_text(self.pos.line).unwrap_or("");
+        let gutter = self.pos.line.to_string();
+        format!(
+            "{} | {}\n{} | {}^",
+            gutter,
+            line,
+            " ".repeat(gutter.len()),
+            " ".repeat(self.pos.col - 1)
+        )
+    }
+}
+
+impl<'a> fmt::Display for Location<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file.path().display(),
+            self.pos.line,
+            self.pos.col
+        )
+    }
+}
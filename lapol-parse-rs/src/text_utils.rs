@@ -0,0 +1,32 @@
+//! Helpers for working with the contents of text nodes.
+
+use std::borrow::Cow;
+
+use crate::ast::AstNodeMeta;
+
+/// Sub-slice of a text node's content, borrowing if possible.
+pub(crate) fn slice<'a>(content: &Cow<'a, str>, range: std::ops::Range<usize>) -> Cow<'a, str> {
+    match content {
+        Cow::Borrowed(s) => Cow::Borrowed(&s[range]),
+        Cow::Owned(s) => Cow::Owned(s[range].to_owned()),
+    }
+}
+
+/// Meta of the position `idx` bytes into a text node starting at `meta`.
+pub(crate) fn meta_at(meta: &AstNodeMeta, content: &str, idx: usize) -> AstNodeMeta {
+    let before = &content[..idx];
+    match before.rfind('\n') {
+        Some(nl) => AstNodeMeta {
+            file_id: meta.file_id,
+            start_offset: meta.start_offset + idx,
+            start_line: meta.start_line + before.matches('\n').count(),
+            start_col: before[nl + 1..].chars().count() + 1,
+        },
+        None => AstNodeMeta {
+            file_id: meta.file_id,
+            start_offset: meta.start_offset + idx,
+            start_line: meta.start_line,
+            start_col: meta.start_col + before.chars().count(),
+        },
+    }
+}
@@ -0,0 +1,185 @@
+//! Whitespace normalisation.
+//!
+//! By default, all whitespace in the source ends up in the AST verbatim. In
+//! particular, indentation inside curly arguments (e.g. nested lists in the
+//! source) leaks into text nodes. `WhitespaceOptions` enables some
+//! Scribble-inspired normalisation.
+//!
+//! Normalisation only changes text node contents. Node positions in
+//! `AstNodeMeta` always refer to the original source.
+
+use crate::{
+    ast::{AstNode, NodeListKind},
+    text_utils::{meta_at, slice},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct WhitespaceOptions {
+    /// Strip the indentation common to all lines (but the first) of a
+    /// multi-line curly argument. Also, if the first line (right after `{`)
+    /// or the last line (right before `}`) is blank, remove it. So
+    ///
+    /// ```text
+    /// @list{
+    ///     @item{a}
+    ///       b
+    /// }
+    /// ```
+    ///
+    /// has the curly argument `"@item{a}\n  b"`.
+    pub dedent: bool,
+    /// Collapse runs of spaces and tabs into a single space.
+    pub collapse_spaces: bool,
+    /// Names of block-level commands (e.g. `sec`). Spaces and tabs right
+    /// before and after these commands are removed (newlines are kept).
+    pub block_commands: Vec<String>,
+}
+
+impl WhitespaceOptions {
+    /// Whether these options leave whitespace untouched.
+    pub fn is_noop(&self) -> bool {
+        !self.dedent && !self.collapse_spaces && self.block_commands.is_empty()
+    }
+}
+
+pub(crate) fn normalize_whitespace(root: &mut AstNode, opts: &WhitespaceOptions) {
+    root.visit_node_lists_mut(&mut |nodes, kind| {
+        if opts.dedent && kind == NodeListKind::CurlyArg {
+            dedent(nodes);
+        }
+        if !opts.block_commands.is_empty() {
+            trim_around_blocks(nodes, &opts.block_commands);
+        }
+        if opts.collapse_spaces {
+            nodes.iter_mut().for_each(collapse_spaces);
+        }
+        nodes.retain(|n| !matches!(n, AstNode::AstTextNode { content, .. } if content.is_empty()));
+    });
+}
+
+fn dedent(nodes: &mut Vec<AstNode>) {
+    let newlines: Vec<usize> = (0..nodes.len())
+        .filter(|&i| is_newline(&nodes[i]))
+        .collect();
+    if newlines.is_empty() {
+        return;
+    }
+
+    let lines: Vec<_> = (0..=newlines.len())
+        .map(|k| {
+            let start = if k == 0 { 0 } else { newlines[k - 1] + 1 };
+            let end = newlines.get(k).copied().unwrap_or(nodes.len());
+            start..end
+        })
+        .collect();
+
+    let min_indent = lines[1..]
+        .iter()
+        .filter(|l| !is_blank(&nodes[(*l).clone()]))
+        .map(|l| leading_whitespace(nodes.get(l.start)))
+        .min()
+        .unwrap_or(0);
+
+    for line in &lines[1..] {
+        if line.is_empty() {
+            continue;
+        }
+        if let AstNode::AstTextNode { content, meta } = &mut nodes[line.start] {
+            let strip = min_indent.min(leading_whitespace_len(content));
+            *meta = meta_at(meta, content, strip);
+            *content = slice(content, strip..content.len());
+        }
+    }
+
+    // Blank first and last lines go away, along with their newline.
+    let mut remove = vec![false; nodes.len()];
+    let first = &lines[0];
+    if is_blank(&nodes[first.clone()]) {
+        first.clone().for_each(|i| remove[i] = true);
+        remove[newlines[0]] = true;
+    }
+    let last = &lines[lines.len() - 1];
+    if is_blank(&nodes[last.clone()]) {
+        last.clone().for_each(|i| remove[i] = true);
+        remove[newlines[newlines.len() - 1]] = true;
+    }
+
+    let mut remove = remove.into_iter();
+    nodes.retain(|_| !remove.next().unwrap());
+}
+
+fn trim_around_blocks(nodes: &mut [AstNode], block_commands: &[String]) {
+    for i in 0..nodes.len() {
+        let is_block = matches!(
+            &nodes[i],
+            AstNode::AstCommandNode { command_name, .. }
+                if block_commands.iter().any(|b| b == command_name)
+        );
+        if !is_block {
+            continue;
+        }
+
+        if i > 0 {
+            if let AstNode::AstTextNode { content, .. } = &mut nodes[i - 1] {
+                if content != "\n" {
+                    let end = content.trim_end_matches(is_space).len();
+                    *content = slice(content, 0..end);
+                }
+            }
+        }
+        if let Some(AstNode::AstTextNode { content, meta }) = nodes.get_mut(i + 1) {
+            let start = leading_whitespace_len(content);
+            *meta = meta_at(meta, content, start);
+            *content = slice(content, start..content.len());
+        }
+    }
+}
+
+fn collapse_spaces(node: &mut AstNode) {
+    if let AstNode::AstTextNode { content, .. } = node {
+        if !content.contains("  ") && !content.contains('\t') {
+            return;
+        }
+
+        let mut out = String::with_capacity(content.len());
+        let mut prev_space = false;
+        for c in content.chars() {
+            if is_space(c) {
+                if !prev_space {
+                    out.push(' ');
+                }
+                prev_space = true;
+            } else {
+                out.push(c);
+                prev_space = false;
+            }
+        }
+        *content.to_mut() = out;
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_newline(node: &AstNode) -> bool {
+    matches!(node, AstNode::AstTextNode { content, .. } if content == "\n" || content == "\r\n")
+}
+
+/// Whether a line (given as its nodes, without the newline) is blank.
+fn is_blank(line: &[AstNode]) -> bool {
+    line.iter()
+        .all(|n| matches!(n, AstNode::AstTextNode { content, .. } if content.chars().all(is_space)))
+}
+
+fn leading_whitespace_len(content: &str) -> usize {
+    content.len() - content.trim_start_matches(is_space).len()
+}
+
+/// Indentation of a line, given its first node.
+fn leading_whitespace(first: Option<&AstNode>) -> usize {
+    match first {
+        Some(AstNode::AstTextNode { content, .. }) => leading_whitespace_len(content),
+        _ => 0,
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use lapol_parse_rs::{
+    AstNode, FileId, IncludeResolver, ParserError, ParserOptions, SourceMap, SquareArg, SquareEntry,
+};
+
+fn fs(files: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+    files
+        .iter()
+        .map(|(p, c)| (PathBuf::from(p), c.to_string()))
+        .collect()
+}
+
+#[test]
+fn includes_are_spliced_with_file_ids() {
+    let fs = fs(&[
+        ("book/main.lap", "a @include{ch/one.lap} b"),
+        ("book/ch/one.lap", "@x{@include{../two.lap}}"),
+        ("book/two.lap", "two"),
+    ]);
+    let source_map = SourceMap::new();
+    let resolver = IncludeResolver::new(&fs, &source_map, ParserOptions::default());
+    let root = resolver.resolve("book/main.lap").unwrap();
+
+    let sub_nodes = match &root {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+        _ => unreachable!(),
+    };
+    assert_eq!(sub_nodes.len(), 3);
+
+    let inner = match &sub_nodes[1] {
+        AstNode::AstCommandNode { curly_args, .. } => &curly_args[0][0],
+        n => panic!("Expected command node, got {:?}", n),
+    };
+    match inner {
+        AstNode::AstTextNode { content, meta } => {
+            assert_eq!(content, "two");
+            assert_eq!(source_map.path(meta.file_id), Path::new("book/two.lap"));
+        }
+        n => panic!("Expected text node, got {:?}", n),
+    }
+    assert_eq!(sub_nodes[1].meta().file_id, FileId(1));
+}
+
+#[test]
+fn include_cycles_are_reported() {
+    let fs = fs(&[("a.lap", "@include{b.lap}"), ("b.lap", "@include{./a.lap}")]);
+    let source_map = SourceMap::new();
+    let resolver = IncludeResolver::new(&fs, &source_map, ParserOptions::default());
+
+    match resolver.resolve("a.lap") {
+        Err(ParserError::IncludeCycle(chain)) => assert_eq!(chain, "a.lap -> b.lap -> a.lap"),
+        r => panic!("Expected include cycle, got {:?}", r),
+    }
+}
+
+#[test]
+fn missing_includes_are_reported() {
+    let fs = fs(&[("a.lap", "@include{nope.lap}")]);
+    let source_map = SourceMap::new();
+    let resolver = IncludeResolver::new(&fs, &source_map, ParserOptions::default());
+
+    assert!(matches!(
+        resolver.resolve("a.lap"),
+        Err(ParserError::FileRead { .. })
+    ));
+}
+
+#[test]
+fn parse_errors_point_to_the_included_file() {
+    let fs = fs(&[("a.lap", "@include{b.lap}"), ("b.lap", "ok\n@x{y")]);
+    let source_map = SourceMap::new();
+    let resolver = IncludeResolver::new(&fs, &source_map, ParserOptions::default());
+
+    let e = resolver.resolve("a.lap").unwrap_err();
+    assert_eq!(
+        e.diagnostic(&source_map),
+        "b.lap:2:4: LaPoL parser error --- Probably an issue with your LaPoL code --- curly \
+         argument is never closed\n2 | @x{y\n  |    ^"
+    );
+}
+
+#[test]
+fn includes_in_square_arguments() {
+    let fs = fs(&[
+        ("a.lap", "@x[k=@y{@include{b.lap}}]"),
+        ("b.lap", "bee"),
+        ("bad.lap", "@x[@include{b.lap}]"),
+    ]);
+    let source_map = SourceMap::new();
+    let resolver = IncludeResolver::new(&fs, &source_map, ParserOptions::default());
+
+    let root = resolver.resolve("a.lap").unwrap();
+    let value = match &root {
+        AstNode::AstRootNode { sub_nodes, .. } => match &sub_nodes[0] {
+            AstNode::AstCommandNode {
+                square_args: Some(args),
+                ..
+            } => match &args[0] {
+                SquareArg::KeyVal(_, SquareEntry::AstNode(value)) => value,
+                a => panic!("Expected a command value, got {:?}", a),
+            },
+            n => panic!("Expected command node, got {:?}", n),
+        },
+        _ => unreachable!(),
+    };
+    match value {
+        AstNode::AstCommandNode { curly_args, .. } => match curly_args[0].as_slice() {
+            [AstNode::AstTextNode { content, meta }] => {
+                assert_eq!(content, "bee");
+                assert_eq!(source_map.path(meta.file_id), Path::new("b.lap"));
+            }
+            a => panic!("Expected the included text, got {:?}", a),
+        },
+        n => panic!("Expected command node, got {:?}", n),
+    }
+
+    match resolver.resolve("bad.lap") {
+        Err(ParserError::BadInclude(e)) => {
+            assert!(e.starts_with("at bad.lap:1:4 --- @include can't be used in a square argument"))
+        }
+        r => panic!("Expected a bad include, got {:?}", r),
+    }
+}
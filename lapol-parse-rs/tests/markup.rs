@@ -0,0 +1,77 @@
+use lapol_parse_rs::{parse_with_options, AstNode, MarkupOptions, ParserOptions};
+
+fn markup_options() -> ParserOptions {
+    ParserOptions {
+        markup: Some(MarkupOptions::default()),
+        ..ParserOptions::default()
+    }
+}
+
+/// Renders the tree back into (roughly) LaPoL syntax, for easy comparisons.
+fn show(node: &AstNode) -> String {
+    match node {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes.iter().map(show).collect(),
+        AstNode::AstCommandNode {
+            command_name,
+            curly_args,
+            ..
+        } => {
+            let mut s = format!("@{}", command_name);
+            for arg in curly_args {
+                s.push('{');
+                s.extend(arg.iter().map(show));
+                s.push('}');
+            }
+            s
+        }
+        AstNode::AstTextNode { content, .. } => content.to_string(),
+        AstNode::AstRawNode { content, .. } => content.to_string(),
+        AstNode::AstParagraphBreakNode { content, .. } => content.to_string(),
+        AstNode::AstBlankLineNode { .. } => String::new(),
+    }
+}
+
+fn desugared(input: &str) -> String {
+    show(&parse_with_options(input, &markup_options()).unwrap())
+}
+
+#[test]
+fn inline_shorthands() {
+    assert_eq!(
+        desugared("a *b* **c** `d*e*` f"),
+        "a @it{b} @bf{c} @code{d*e*} f"
+    );
+    assert_eq!(desugared("*a **b** c*"), "@it{a @bf{b} c}");
+    assert_eq!(desugared("*@x{y}*"), "@it{@x{y}}");
+}
+
+#[test]
+fn unmatched_delimiters_are_text() {
+    assert_eq!(desugared("2 * 3 * 4"), "2 * 3 * 4");
+    assert_eq!(desugared("*a\n\nb*"), "*a\n\nb*");
+    assert_eq!(desugared("`a\nb`"), "`a\nb`");
+}
+
+#[test]
+fn headings() {
+    assert_eq!(
+        desugared("# Title\ntext # not\n## *Sub*\n####### deep"),
+        "@sec{Title}\ntext # not\n@subsec{@it{Sub}}\n####### deep"
+    );
+    assert_eq!(desugared("@x{# no}"), "@x{# no}");
+}
+
+/// Line ends are `"\n"` nodes, also in CRLF files.
+#[test]
+fn crlf_line_ends() {
+    assert_eq!(
+        desugared("intro\r\n# Title\r\ntext *a*\r\n\r\n*b\r\n\r\nc*"),
+        "intro\n@sec{Title}\ntext @it{a}\n\n*b\n\nc*"
+    );
+}
+
+#[test]
+fn markup_is_off_by_default() {
+    let root = lapol_parse_rs::parse("*a*").unwrap();
+    assert_eq!(show(&root), "*a*");
+}
@@ -0,0 +1,12 @@
+use lapol_parse_rs::{parse, AstNode};
+
+#[test]
+fn owned_tree_outlives_the_source() {
+    let source = String::from("Text @cmd[a, k=\"v\", @x{}]{b @raw[html]#\"<i>\"#}");
+    let borrowed = parse(&source).unwrap();
+    let expected = format!("{:?}", borrowed);
+
+    let owned: AstNode<'static> = borrowed.into_owned();
+    drop(source);
+    assert_eq!(format!("{:?}", owned), expected);
+}
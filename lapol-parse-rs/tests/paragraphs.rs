@@ -0,0 +1,62 @@
+use lapol_parse_rs::{parse, parse_with_options, AstNode, ParserOptions};
+
+fn with_breaks() -> ParserOptions {
+    ParserOptions {
+        paragraph_breaks: true,
+        ..ParserOptions::default()
+    }
+}
+
+fn sub_nodes<'a>(root: &'a AstNode<'a>) -> &'a [AstNode<'a>] {
+    match root {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+        _ => panic!("Expected root node"),
+    }
+}
+
+#[test]
+fn blank_lines_become_paragraph_breaks() {
+    let input = "a\n  \n\n  b\nc @x{d\n\ne}";
+    let root = parse_with_options(input, &with_breaks()).unwrap();
+    let nodes = sub_nodes(&root);
+
+    match &nodes[1] {
+        AstNode::AstParagraphBreakNode {
+            content,
+            blank_lines,
+            meta,
+        } => {
+            assert_eq!(content, "\n  \n\n");
+            assert_eq!((meta.start_line, meta.start_col), (1, 2));
+            let starts: Vec<_> = blank_lines
+                .iter()
+                .map(|b| {
+                    assert!(matches!(b, AstNode::AstBlankLineNode { .. }));
+                    (b.meta().start_line, b.meta().start_col)
+                })
+                .collect();
+            assert_eq!(starts, vec![(2, 1), (3, 1)]);
+        }
+        n => panic!("Expected paragraph break, got {:?}", n),
+    }
+
+    // A single newline is not a paragraph break.
+    assert!(matches!(&nodes[3], AstNode::AstTextNode { content, .. } if content == "\n"));
+
+    // Curly arguments are handled too.
+    match nodes.last().unwrap() {
+        AstNode::AstCommandNode { curly_args, .. } => assert!(matches!(
+            curly_args[0][1],
+            AstNode::AstParagraphBreakNode { .. }
+        )),
+        n => panic!("Expected command, got {:?}", n),
+    }
+}
+
+#[test]
+fn paragraph_breaks_are_off_by_default() {
+    let root = parse("a\n\nb").unwrap();
+    assert!(sub_nodes(&root)
+        .iter()
+        .all(|n| !matches!(n, AstNode::AstParagraphBreakNode { .. })));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "parallel")]
+
+use lapol_parse_rs::{parse_many, AstNode, ParserError, ParserOptions, SourceMap};
+
+#[test]
+fn parse_many_returns_results_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    let paths: Vec<_> = (0..8)
+        .map(|i| {
+            let p = dir.join(format!("{}.lap", i));
+            std::fs::write(&p, format!("@file{{{}}}", i)).unwrap();
+            p
+        })
+        .chain(std::iter::once(dir.join("missing.lap")))
+        .collect();
+
+    let source_map = SourceMap::new();
+    let out = parse_many(&paths, &source_map, &ParserOptions::default());
+
+    assert_eq!(out.files.len(), 9);
+    for (i, f) in out.files[..8].iter().enumerate() {
+        let root = f.result.as_ref().unwrap();
+        assert_eq!(root.meta().file_id, f.file_id.unwrap());
+        assert_eq!(source_map.path(f.file_id.unwrap()), paths[i]);
+        assert!(matches!(root, AstNode::AstRootNode { .. }));
+    }
+    assert!(matches!(
+        out.errors().collect::<Vec<_>>().as_slice(),
+        [(_, ParserError::FileRead { .. })]
+    ));
+}
@@ -0,0 +1,41 @@
+use lapol_parse_rs::{parse, AstNode};
+
+fn root_sub_nodes<'a>(root: &'a AstNode<'a>) -> &'a [AstNode<'a>] {
+    match root {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+        _ => panic!("Expected root node"),
+    }
+}
+
+#[test]
+fn raw_block_keeps_content_untouched() {
+    let root = parse(r##"a @raw[html]#"<b>@bold{x} }{ %</b>"# b"##).unwrap();
+    let nodes = root_sub_nodes(&root);
+
+    assert_eq!(nodes.len(), 3);
+    match &nodes[1] {
+        AstNode::AstRawNode { content, lang, .. } => {
+            assert_eq!(*content, "<b>@bold{x} }{ %</b>");
+            assert_eq!(lang.as_deref(), Some("html"));
+        }
+        n => panic!("Expected raw node, got {:?}", n),
+    }
+}
+
+#[test]
+fn raw_block_with_delimiter_word() {
+    let root = parse(r###"@raw##END"quote: "# and "## "END##"###).unwrap();
+
+    match &root_sub_nodes(&root)[0] {
+        AstNode::AstRawNode { content, lang, .. } => {
+            assert_eq!(*content, r###"quote: "# and "## "###);
+            assert_eq!(lang.as_deref(), None);
+        }
+        n => panic!("Expected raw node, got {:?}", n),
+    }
+}
+
+#[test]
+fn unterminated_raw_block_is_an_error() {
+    assert!(parse(r#"@raw#"never closed"#).is_err());
+}
@@ -0,0 +1,70 @@
+use lapol_parse_rs::{parse, ColumnEncoding, LineCol, ParserError, SourceMap};
+
+#[test]
+fn offsets_convert_to_line_col_in_each_encoding() {
+    let source_map = SourceMap::new();
+    let id = source_map.add("a.lap", "ab\r\n€𝄞x\n".to_owned());
+    let file = source_map.file(id);
+
+    // Offset of "x": 4 (line 1) + 3 (€) + 4 (𝄞).
+    let offset = 11;
+    assert_eq!(
+        file.line_col(offset, ColumnEncoding::Utf8),
+        LineCol { line: 2, col: 8 }
+    );
+    assert_eq!(
+        file.line_col(offset, ColumnEncoding::Utf16),
+        LineCol { line: 2, col: 4 }
+    );
+    assert_eq!(
+        file.line_col(offset, ColumnEncoding::Chars),
+        LineCol { line: 2, col: 3 }
+    );
+
+    for encoding in [
+        ColumnEncoding::Utf8,
+        ColumnEncoding::Utf16,
+        ColumnEncoding::Chars,
+    ] {
+        assert_eq!(
+            file.offset(file.line_col(offset, encoding), encoding),
+            Some(offset)
+        );
+    }
+
+    assert_eq!(file.line_text(1), Some("ab"));
+    assert_eq!(file.line_count(), 3);
+}
+
+#[test]
+fn locations_agree_with_ast_meta() {
+    let source_map = SourceMap::new();
+    let id = source_map.add("a.lap", "hello\n  @cmd{x}".to_owned());
+    let root = parse(source_map.source(id)).unwrap();
+
+    let cmd = match &root {
+        lapol_parse_rs::AstNode::AstRootNode { sub_nodes, .. } => sub_nodes.last().unwrap(),
+        _ => unreachable!(),
+    };
+    let location = source_map.location(cmd.meta());
+
+    assert_eq!(location.to_string(), "a.lap:2:3");
+    assert_eq!(location.pos.col, cmd.meta().start_col);
+    assert_eq!(location.snippet(), "2 |   @cmd{x}\n  |   ^");
+}
+
+#[test]
+fn parse_errors_have_a_position() {
+    let source_map = SourceMap::new();
+    let id = source_map.add("a.lap", "@b{x}\n  y }".to_owned());
+
+    let e = parse(source_map.source(id)).unwrap_err();
+    match &e {
+        ParserError::NomError { meta, .. } => assert_eq!((meta.start_line, meta.start_col), (2, 5)),
+        e => panic!("Expected a nom error, got {:?}", e),
+    }
+    assert!(e.diagnostic(&source_map).starts_with("a.lap:2:5: "));
+    assert!(e
+        .diagnostic(&source_map)
+        .ends_with("\n2 |   y }\n  |     ^"));
+}
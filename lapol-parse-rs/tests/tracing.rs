@@ -0,0 +1,85 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use lapol_parse_rs::{parse_with_options, MarkupOptions, ParserOptions};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+/// Records the spans created, as `name` or `name:command`.
+#[derive(Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<String>>>,
+    next_id: AtomicU64,
+}
+
+struct CommandName<'a>(&'a mut String);
+
+impl Visit for CommandName<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            *self.0 += ":";
+            *self.0 += value;
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn Debug) {}
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut name = span.metadata().name().to_owned();
+        span.record(&mut CommandName(&mut name));
+        self.spans.lock().unwrap().push(name);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn parse_phases_commands_and_curly_args_have_spans() {
+    let recorder = Recorder::default();
+    let spans = recorder.spans.clone();
+    let options = ParserOptions {
+        markup: Some(MarkupOptions::default()),
+        ..ParserOptions::default()
+    };
+    tracing::subscriber::with_default(recorder, || {
+        parse_with_options("Hi @b{x @i{y}} *z*", &options).unwrap();
+    });
+
+    assert_eq!(
+        *spans.lock().unwrap(),
+        [
+            "parse",
+            "root",
+            "command:b",
+            "curly_arg",
+            "command:i",
+            "curly_arg",
+            "markup"
+        ]
+    );
+}
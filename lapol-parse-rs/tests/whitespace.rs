@@ -0,0 +1,109 @@
+use lapol_parse_rs::{parse, parse_with_options, AstNode, ParserOptions, WhitespaceOptions};
+
+fn with_whitespace(whitespace: WhitespaceOptions) -> ParserOptions {
+    ParserOptions {
+        whitespace,
+        ..ParserOptions::default()
+    }
+}
+
+fn dedent() -> ParserOptions {
+    with_whitespace(WhitespaceOptions {
+        dedent: true,
+        ..WhitespaceOptions::default()
+    })
+}
+
+fn sub_nodes<'a>(root: &'a AstNode<'a>) -> &'a [AstNode<'a>] {
+    match root {
+        AstNode::AstRootNode { sub_nodes, .. } => sub_nodes,
+        _ => panic!("Expected root node"),
+    }
+}
+
+/// Text of a node list, with commands shown as `@name{...}`.
+fn show(nodes: &[AstNode]) -> String {
+    nodes
+        .iter()
+        .map(|n| match n {
+            AstNode::AstTextNode { content, .. } => content.to_string(),
+            AstNode::AstCommandNode {
+                command_name,
+                curly_args,
+                ..
+            } => {
+                let args: String = curly_args
+                    .iter()
+                    .map(|a| format!("{{{}}}", show(a)))
+                    .collect();
+                format!("@{}{}", command_name, args)
+            }
+            n => panic!("Unexpected node {:?}", n),
+        })
+        .collect()
+}
+
+#[test]
+fn dedent_strips_common_indentation() {
+    let input = "@list{\n    @item{a}\n      b\n\n    c\n  }";
+    let root = parse_with_options(input, &dedent()).unwrap();
+    assert_eq!(show(sub_nodes(&root)), "@list{@item{a}\n  b\n\nc}");
+}
+
+#[test]
+fn dedent_keeps_source_positions() {
+    let input = "@x{\n    ab\n}";
+    let root = parse_with_options(input, &dedent()).unwrap();
+    match &sub_nodes(&root)[0] {
+        AstNode::AstCommandNode { curly_args, .. } => match &curly_args[0][..] {
+            [AstNode::AstTextNode { content, meta }] => {
+                assert_eq!(content, "ab");
+                assert_eq!((meta.start_line, meta.start_col), (2, 5));
+                assert_eq!(&input[meta.start_offset..meta.start_offset + 2], "ab");
+            }
+            n => panic!("Unexpected nodes {:?}", n),
+        },
+        n => panic!("Expected command, got {:?}", n),
+    }
+}
+
+#[test]
+fn dedent_is_relative_to_each_argument() {
+    let input = "@a{\n  x @b{\n      y\n      z\n  }\n  w\n}";
+    let root = parse_with_options(input, &dedent()).unwrap();
+    assert_eq!(show(sub_nodes(&root)), "@a{x @b{y\nz}\nw}");
+}
+
+#[test]
+fn dedent_leaves_single_line_arguments_and_root_alone() {
+    let input = "  a\n    b @x{ c }";
+    let root = parse_with_options(input, &dedent()).unwrap();
+    assert_eq!(show(sub_nodes(&root)), input);
+}
+
+#[test]
+fn collapse_spaces() {
+    let options = with_whitespace(WhitespaceOptions {
+        collapse_spaces: true,
+        ..WhitespaceOptions::default()
+    });
+    let root = parse_with_options("a  \t b\n  @x{c   d}", &options).unwrap();
+    assert_eq!(show(sub_nodes(&root)), "a b\n @x{c d}");
+}
+
+#[test]
+fn trim_around_block_commands() {
+    let options = with_whitespace(WhitespaceOptions {
+        block_commands: vec!["sec".to_owned()],
+        ..WhitespaceOptions::default()
+    });
+    let root = parse_with_options("a  @sec{T}  b\n  @sec{U}\n@it{v}  w", &options).unwrap();
+    assert_eq!(show(sub_nodes(&root)), "a@sec{T}b\n@sec{U}\n@it{v}  w");
+}
+
+#[test]
+fn whitespace_is_untouched_by_default() {
+    let input = "@x{\n    a  b\n}";
+    let root = parse(input).unwrap();
+    assert_eq!(show(sub_nodes(&root)), input);
+}
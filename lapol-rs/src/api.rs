@@ -0,0 +1,58 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// The version of the API of lapol-rs (the functions and classes it exports,
+/// and the errors they throw). Bumped on breaking changes, so callers can
+/// check they were built against a compatible lapol-rs (see `capabilities`).
+pub const API_VERSION: u32 = 1;
+
+/// What this build of lapol-rs provides.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub api_version: u32,
+    /// The crate version.
+    pub version: &'static str,
+    /// E.g. `parser` (the `Parser` class), `render:html`, or
+    /// `debugEndpoints` if those are compiled in.
+    pub features: Vec<&'static str>,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        let mut features = vec![
+            "parse",
+            "parser",
+            "parseJob",
+            "evaluate",
+            "process",
+            "render:html",
+            "render:latex",
+            "render:markdown",
+            "render:text",
+            "outputRequirements",
+        ];
+        if cfg!(feature = "debug-endpoints") {
+            features.push("debugEndpoints");
+        }
+
+        Capabilities {
+            api_version: API_VERSION,
+            version: env!("CARGO_PKG_VERSION"),
+            features,
+        }
+    }
+}
+
+/// The version of lapol-rs (the crate version).
+#[wasm_bindgen]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_owned()
+}
+
+/// `{ apiVersion, version, features }`, see `Capabilities`.
+#[wasm_bindgen]
+pub fn capabilities() -> JsValue {
+    #[allow(deprecated)]
+    JsValue::from_serde(&Capabilities::current()).expect("Capabilities are always serializable")
+}
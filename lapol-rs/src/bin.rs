@@ -1,9 +1,36 @@
 use std::time::Instant;
 
-mod parse;
+use lapol_core_rs::{
+    ltrf::LtrfNode,
+    output::{
+        epub::{split_on_sections, Epub, EpubChapter, EpubMetadata},
+        html::{self, HtmlOptions},
+        latex::{self, LatexOptions},
+        markdown::{self, MarkdownOptions},
+        text::{self, TextOptions},
+    },
+};
 
 /// This binary is mostly used for testing / debugging the library code.
 pub fn main() {
+    #[cfg(feature = "tracing")]
+    if let Ok(filter) = std::env::var("LAPOL_LOG") {
+        if let Err(e) = lapol_rs::init_stderr_tracing(&filter) {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(target @ ("html" | "latex" | "text" | "markdown")) =
+        args.first().map(String::as_str)
+    {
+        return render(target, &args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("epub") {
+        return epub(&args[1..]);
+    }
+
     println!("You may want to run 'chcp 65001' before running this!");
 
     //parse::parse_file(
@@ -15,11 +42,138 @@ pub fn main() {
     /*let path =
         "X:\\programming\\programming\\LaPoL Project\\lapol\\test_scratch\\stress_test_0.lap";*/
 
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        std::process::exit(1);
+    });
+
     //loop {
     let parse_start = Instant::now();
-    parse::parse_file_native(path);
+    if let Err(e) = lapol_parse_rs::parse(&text) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     let parse_dur = parse_start.elapsed();
 
     println!("Parsing took {:?}", parse_dur);
     //}
 }
+
+/// `lapol-rs-bin html <ltrf.json> [--pretty] [--standalone]`
+/// `lapol-rs-bin latex <ltrf.json> [--standalone]`
+/// `lapol-rs-bin text <ltrf.json> [--width=N | --no-wrap]`
+/// `lapol-rs-bin markdown <ltrf.json> [--width=N | --no-wrap]`
+///
+/// Renders a processed LTRF tree (in the JSON format of `dbgStringify`) to
+/// stdout.
+fn render(target: &str, args: &[String]) {
+    let path = match args.iter().find(|a| !a.starts_with("--")) {
+        Some(p) => p,
+        None => {
+            eprintln!("Usage: lapol-rs-bin {} <ltrf.json> [flags...]", target);
+            std::process::exit(2);
+        }
+    };
+    let flag = |f: &str| args.iter().any(|a| a == f);
+    let width = match args.iter().find_map(|a| a.strip_prefix("--width=")) {
+        _ if flag("--no-wrap") => None,
+        Some(w) => Some(w.parse().unwrap_or_else(|_| {
+            eprintln!("Bad width: {}", w);
+            std::process::exit(2);
+        })),
+        None => Some(80),
+    };
+
+    let root = load_ltrf(path);
+
+    let out = match target {
+        "html" => html::render_html(
+            &root,
+            &HtmlOptions {
+                pretty: flag("--pretty"),
+                standalone: flag("--standalone"),
+                ..HtmlOptions::default()
+            },
+        ),
+        "latex" => latex::render_latex(
+            &root,
+            &LatexOptions {
+                standalone: flag("--standalone"),
+                ..LatexOptions::default()
+            },
+        ),
+        "text" => text::render_text(
+            &root,
+            &TextOptions {
+                width,
+                ..TextOptions::default()
+            },
+        ),
+        _ => markdown::render_markdown(
+            &root,
+            &MarkdownOptions {
+                width,
+                ..MarkdownOptions::default()
+            },
+        ),
+    };
+
+    match out {
+        Ok(out) => print!("{}", out),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `lapol-rs-bin epub <out.epub> <ltrf.json>... [--title=T] [--author=A] [--split]`
+///
+/// Packages processed LTRF trees as an EPUB, with one chapter per file, or
+/// one chapter per section with `--split`.
+fn epub(args: &[String]) {
+    let paths: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    if paths.len() < 2 {
+        eprintln!("Usage: lapol-rs-bin epub <out.epub> <ltrf.json>... [flags...]");
+        std::process::exit(2);
+    }
+    let opt = |f: &str| args.iter().find_map(|a| a.strip_prefix(f));
+    let split = args.iter().any(|a| a == "--split");
+
+    let mut metadata = EpubMetadata::new(opt("--title=").unwrap_or("Untitled"));
+    metadata.authors.extend(opt("--author=").map(str::to_owned));
+    let mut book = Epub::new(metadata);
+
+    let options = HtmlOptions::default();
+    for path in &paths[1..] {
+        let root = load_ltrf(path);
+        let parts = if split {
+            split_on_sections(&root)
+        } else {
+            vec![root]
+        };
+        for part in parts {
+            let chapter = EpubChapter::from_ltrf(&part, path, &options).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            book.chapters.push(chapter);
+        }
+    }
+
+    if let Err(e) = book.write_to_file(paths[0]) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn load_ltrf(path: &str) -> LtrfNode {
+    let json = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        std::process::exit(1);
+    });
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        eprintln!("Bad LTRF JSON in {}: {}", path, e);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,44 @@
+//! Endpoints for testing the JS <-> WASM bindings. Only with the
+//! `debug-endpoints` feature, they are not part of the API.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name=log)]
+    fn js_console_log(s: &str);
+
+}
+
+#[wasm_bindgen]
+pub fn greet() {
+    js_console_log("Hello, lapol-rs!");
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileReadContent {
+    #[serde(rename = "fileContent")]
+    pub file_content: String,
+}
+
+#[wasm_bindgen(js_name = receiveStr)]
+pub fn receive_str(str: &str) {
+    js_console_log("Received string");
+    js_console_log(&format!("First char {}", str.chars().next().unwrap()));
+}
+
+#[wasm_bindgen(js_name = receiveVal)]
+pub fn receive_val(val: &JsValue) {
+    #[allow(deprecated)]
+    let e: FileReadContent = val.into_serde().unwrap();
+    js_console_log(&format!("Received {:?}", e));
+}
+
+#[wasm_bindgen(js_name = receiveBuffer)]
+pub fn receive_buffer(buff: &[u8]) {
+    js_console_log("Received Buffer");
+    js_console_log(&format!("First elem {}", buff[0]));
+    let my_str = std::str::from_utf8(buff).unwrap();
+    js_console_log(&format!("First char {}", my_str.chars().next().unwrap()));
+}
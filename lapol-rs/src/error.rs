@@ -0,0 +1,36 @@
+use std::fmt::Display;
+
+use wasm_bindgen::JsValue;
+
+/// What went wrong, when a function of lapol-rs throws. Errors are JS
+/// `Error`s whose `name` is that of their kind (e.g. `LapolParseError`), and
+/// whose `message` describes the problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    /// Bad arguments: invalid JSON, options, or UTF-8.
+    Input,
+    /// The LaPoL code could not be parsed.
+    Parse,
+    /// The native evaluator failed (e.g. an unknown command).
+    Eval,
+    /// Processing or rendering the LTRF tree failed.
+    Output,
+}
+
+impl ErrorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Input => "LapolInputError",
+            ErrorKind::Parse => "LapolParseError",
+            ErrorKind::Eval => "LapolEvalError",
+            ErrorKind::Output => "LapolOutputError",
+        }
+    }
+}
+
+/// The JS `Error` to throw.
+pub(crate) fn error(kind: ErrorKind, message: impl Display) -> JsValue {
+    let e = js_sys::Error::new(&message.to_string());
+    e.set_name(kind.name());
+    e.into()
+}
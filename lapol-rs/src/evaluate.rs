@@ -0,0 +1,46 @@
+use lapol_eval_rs::{Environment, FileContext, Value};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{error, ErrorKind};
+
+/// Parses and evaluates a document with the native evaluator (see
+/// `lapol_eval_rs`), which only has the built-in modules (`std::core` and
+/// `std::main`).
+///
+/// `variables_json` is a JSON object of the variables to define first (see
+/// `@get` and `@if`), e.g. `{"target": "html", "edition": 2}`: numbers,
+/// booleans and strings.
+///
+/// Returns the LTRF root as JSON (see `processLtrf`).
+#[wasm_bindgen(js_name = evaluateSource)]
+pub fn evaluate_source(source: &str, variables_json: &str) -> Result<String, JsValue> {
+    let variables: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(variables_json)
+            .map_err(|e| error(ErrorKind::Input, format!("Bad variables JSON: {}", e)))?;
+
+    let mut env = Environment::with_std();
+    for (name, value) in variables {
+        let value = match value {
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => Value::Num(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::Str(s),
+            v => {
+                return Err(error(
+                    ErrorKind::Input,
+                    format!(
+                        "Variable {} must be a number, boolean or string, not {}",
+                        name, v
+                    ),
+                ))
+            }
+        };
+        env.define_var(name, value);
+    }
+
+    let ast = lapol_parse_rs::parse(source).map_err(|e| error(ErrorKind::Parse, e))?;
+    let root = lapol_eval_rs::evaluate(&ast, &mut env, &mut FileContext::new())
+        .map_err(|e| error(ErrorKind::Eval, e))?;
+
+    Ok(serde_json::to_string(&root).expect("LTRF trees are always serializable"))
+}
@@ -0,0 +1,268 @@
+//! The whole pipeline (parse, evaluate, process, output) for one file.
+//!
+//! Mirrors `lapol-core/src/internal/compile.ts`, with the outputters of
+//! `std::main` (see `lapol-core/src/std/*_output.ts`).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lapol_core_rs::{
+    ltrf::{LtrfKv, LtrfNode, LtrfObj},
+    output::{
+        html::{self, HtmlElement, HtmlOptions},
+        latex::{self, LatexOptions},
+        requirements::OutputRequirementReceiver,
+    },
+    process, LapolError,
+};
+use lapol_eval_rs::{stdlib::MainStorage, Environment, EvalError, FileContext, Value};
+use lapol_parse_rs::SourceMap;
+use thiserror::Error as TError;
+
+#[derive(Debug, TError)]
+pub enum BuildError {
+    #[error("Could not read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not write {path}: {source}")]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An error in the document, with its position (see
+    /// `SourceMap::diagnostic`).
+    #[error("{0}")]
+    Source(String),
+    #[error(transparent)]
+    Eval(#[from] EvalError),
+    #[error(transparent)]
+    Lapol(#[from] LapolError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Html,
+    Latex,
+}
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "html" => Some(Target::Html),
+            "latex" => Some(Target::Latex),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Html => "html",
+            Target::Latex => "latex",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Target::Html => "html",
+            Target::Latex => "tex",
+        }
+    }
+}
+
+/// Files required by the HTML output, relative to the LaPoL folder.
+const HTML_DEPS: &[(&str, &str)] = &[
+    ("deps/hello-css/dist/all.css", "deps/hello-css-all.css"),
+    ("deps-lapol/lapol-default.css", "deps/lapol-default.css"),
+    ("deps/hello-css/fonts/LICENSE", "deps/fonts/LICENSE"),
+    (
+        "deps/hello-css/fonts/libre-baskerville.woff2",
+        "deps/fonts/libre-baskerville.woff2",
+    ),
+    (
+        "deps/hello-css/fonts/libre-baskerville-bold.woff2",
+        "deps/fonts/libre-baskerville-bold.woff2",
+    ),
+    (
+        "deps/hello-css/fonts/libre-baskerville-italic.woff2",
+        "deps/fonts/libre-baskerville-italic.woff2",
+    ),
+];
+
+/// Files required by the LaTeX output, relative to the LaPoL folder.
+const LATEX_DEPS: &[(&str, &str)] = &[
+    (
+        "deps-lapol/lapol_default_article.cls",
+        "deps/lapol_default_article.cls",
+    ),
+    ("deps-lapol/lapol_default.sty", "deps/lapol_default.sty"),
+];
+
+pub struct BuildOptions {
+    pub targets: Vec<Target>,
+    pub out_dir: PathBuf,
+    /// Where the `deps` and `deps-lapol` folders are.
+    pub lapol_dir: PathBuf,
+    /// Variables defined before evaluation (see `@get`), besides `target`.
+    pub variables: Vec<(String, Value)>,
+    /// Script modules (name and source) the document can `@__require`.
+    #[cfg(feature = "scripting")]
+    pub scripts: Vec<(String, String)>,
+    /// Their commands can be `@__require`d, and their outputters are used
+    /// for the tags they handle.
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<lapol_eval_rs::plugin::Plugin>,
+}
+
+/// Builds `input` to each target, in `options.out_dir`. Returns the paths of
+/// the written files.
+///
+/// The file is evaluated once per target, with the variable `target` set to
+/// its name, so it can have target-specific content (`@if[target=latex]`).
+pub fn build_file(input: &Path, options: &BuildOptions) -> Result<Vec<PathBuf>, BuildError> {
+    let source = fs::read_to_string(input).map_err(|source| BuildError::Read {
+        path: input.to_owned(),
+        source,
+    })?;
+    let source_map = SourceMap::new();
+    let file_id = source_map.add(input, source);
+    let ast = lapol_parse_rs::parse(source_map.source(file_id))
+        .map_err(|e| BuildError::Source(e.diagnostic(&source_map)))?;
+
+    let stem = input.file_stem().unwrap_or_default();
+    let mut written = Vec::new();
+    let mut reqs = OutputRequirementReceiver::new();
+
+    for target in &options.targets {
+        let mut env = Environment::with_std();
+        #[cfg(feature = "scripting")]
+        for (name, source) in &options.scripts {
+            let limits = lapol_eval_rs::script::ScriptLimits::default();
+            env.register_module(lapol_eval_rs::script::load_module(name, source, &limits)?);
+        }
+        #[cfg(feature = "plugins")]
+        for plugin in &options.plugins {
+            env.register_module(plugin.module());
+        }
+        env.define_var("target", Value::Ident(target.name().to_owned()));
+        for (name, value) in &options.variables {
+            env.define_var(name.clone(), value.clone());
+        }
+        let mut file = FileContext::new();
+        let root = lapol_eval_rs::evaluate(&ast, &mut env, &mut file)
+            .map_err(|e| BuildError::Source(e.diagnostic(&source_map)))?;
+        let processed = process::process_pass(&root)?;
+        #[cfg(feature = "plugins")]
+        let processed = apply_plugin_outputters(&processed, *target, &options.plugins)?;
+        let main = file.get::<MainStorage>().cloned().unwrap_or_default();
+
+        let (code, deps) = match target {
+            Target::Html => (output_html(&processed, &main)?, HTML_DEPS),
+            Target::Latex => (output_latex(&processed, &main)?, LATEX_DEPS),
+        };
+
+        let mut file_name = stem.to_owned();
+        file_name.push(".");
+        file_name.push(target.extension());
+        let path = options.out_dir.join(file_name);
+        write(&path, &code)?;
+        written.push(path);
+
+        for (src, tgt) in deps {
+            let src = options.lapol_dir.join(src);
+            // Missing styling isn't worth failing the build over.
+            if src.is_file() {
+                reqs.require_file(src, tgt)?;
+            } else {
+                eprintln!("Warning: {} not found, not copied.", src.display());
+            }
+        }
+    }
+
+    reqs.copy_to(&options.out_dir)?;
+    Ok(written)
+}
+
+/// Replaces the nodes plugins output by their code. Their contents are
+/// rendered with the default options.
+#[cfg(feature = "plugins")]
+fn apply_plugin_outputters(
+    root: &LtrfNode,
+    target: Target,
+    plugins: &[lapol_eval_rs::plugin::Plugin],
+) -> Result<LtrfNode, EvalError> {
+    let html_options = HtmlOptions::default();
+    let latex_options = LatexOptions::default();
+    lapol_eval_rs::plugin::apply_outputters(root, target.name(), plugins, &mut |n| match target {
+        Target::Html => html::render_html(n, &html_options),
+        Target::Latex => latex::render_latex(n, &latex_options),
+    })
+}
+
+fn output_html(root: &LtrfNode, main: &MainStorage) -> Result<String, LapolError> {
+    let mut options = HtmlOptions {
+        standalone: true,
+        ..HtmlOptions::default()
+    };
+    options
+        .tags
+        .insert("maketitle".to_owned(), HtmlElement::block("header").into());
+    options.tags.insert(
+        "__title".to_owned(),
+        HtmlElement::block("h1").with_attr("class", "title").into(),
+    );
+    options.tags.insert(
+        "__author".to_owned(),
+        HtmlElement::block("p")
+            .with_attr("class", "subtitle")
+            .into(),
+    );
+
+    // The title and author aren't part of the LTRF tree, so they are put in
+    // the `maketitle` nodes.
+    let mut title = vec![node("__title", &main.title)];
+    if !main.author.is_empty() {
+        title.push(node("__author", &main.author));
+    }
+    let root = fill_maketitle(root, &title);
+
+    html::render_html(&root, &options)
+}
+
+fn output_latex(root: &LtrfNode, main: &MainStorage) -> Result<String, LapolError> {
+    let options = LatexOptions {
+        standalone: true,
+        title: main.title.clone(),
+        author: main.author.clone(),
+        ..LatexOptions::default()
+    };
+    latex::render_latex(root, &options)
+}
+
+fn fill_maketitle(n: &LtrfNode, title: &[LtrfObj]) -> LtrfNode {
+    if n.tag() == "maketitle" {
+        return n.with_elems(title.to_vec());
+    }
+    n.map_elems(|e, _| match e {
+        LtrfObj::Node(child) => fill_maketitle(child, title).into(),
+        s => s.clone(),
+    })
+}
+
+fn node(tag: &str, elems: &[LtrfObj]) -> LtrfObj {
+    LtrfNode::make(tag, LtrfKv::new(), elems.to_vec()).into()
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), BuildError> {
+    let err = |source| BuildError::Write {
+        path: path.to_owned(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(err)?;
+    }
+    fs::write(path, contents).map_err(err)
+}
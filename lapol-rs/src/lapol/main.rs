@@ -0,0 +1,190 @@
+//! `lapol`, the native compiler.
+//!
+//! Runs the whole pipeline without Node, for documents that only use the
+//! built-in modules (`std::core` and `std::main`). Documents using custom JS
+//! modules still need lapol-core.
+
+use std::path::PathBuf;
+
+mod build;
+
+use build::{BuildOptions, Target};
+use lapol_eval_rs::Value;
+
+const USAGE: &str = "\
+Usage: lapol build <file.lap>... [--target=html|latex]... [--var=NAME=VALUE]... [--out-dir=DIR]
+                   [--lapol-dir=DIR]
+
+Options:
+  --target=T       Output target (may be repeated). Defaults to html. The document can test it
+                   with @if[target=latex]{...}.
+  --var=NAME=VALUE Defines a variable (see @get and @if), e.g. --var=edition=2. Values are
+                   numbers, true or false, or else strings.
+  --script=FILE    Loads a Rhai script as a module named after the file (`macros.rhai` is
+                   `macros`, for @__require{macros}). Scripts run sandboxed, with resource
+                   limits. Only if built with the `scripting` feature.
+  --plugin=FILE    Loads a WASM plugin (see `lapol_eval_rs::plugin`): its commands are in the
+                   module it names, and it may output some tags. Plugins run sandboxed, with
+                   resource limits. Only if built with the `plugins` feature.
+  --out-dir=DIR    Where to write the output and its dependencies. Defaults to ./out.
+  --lapol-dir=DIR  Folder holding LaPoL's `deps` and `deps-lapol`. Defaults to $LAPOL_DIR,
+                   or else the LaPoL source tree this binary was built from.
+
+Set LAPOL_LOG to output diagnostics on stderr, e.g. LAPOL_LOG=debug or
+LAPOL_LOG=lapol_parse_rs=trace (see `tracing_subscriber::filter::Targets`). Only if built
+with the `tracing` feature (on by default).";
+
+pub fn main() {
+    #[cfg(feature = "tracing")]
+    if let Ok(filter) = std::env::var("LAPOL_LOG") {
+        if let Err(e) = lapol_rs::init_stderr_tracing(&filter) {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        Some("--help") | Some("-h") => println!("{}", USAGE),
+        _ => usage_error(),
+    }
+}
+
+fn build(args: &[String]) {
+    let mut inputs = Vec::new();
+    let mut targets = Vec::new();
+    let mut variables = Vec::new();
+    #[cfg(feature = "scripting")]
+    let mut scripts = Vec::new();
+    #[cfg(feature = "plugins")]
+    let mut plugins = Vec::new();
+    let mut out_dir = PathBuf::from("out");
+    let mut lapol_dir = std::env::var_os("LAPOL_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/..")));
+
+    for arg in args {
+        if let Some(t) = arg.strip_prefix("--target=") {
+            match Target::from_name(t) {
+                Some(t) => targets.push(t),
+                None => {
+                    eprintln!("Unknown target: {} (expected html or latex)", t);
+                    std::process::exit(2);
+                }
+            }
+        } else if let Some(v) = arg.strip_prefix("--var=") {
+            match v.split_once('=') {
+                Some((name, value)) => variables.push((name.to_owned(), parse_value(value))),
+                None => usage_error(),
+            }
+        } else if let Some(path) = arg.strip_prefix("--script=") {
+            #[cfg(feature = "scripting")]
+            scripts.push(load_script(path));
+            #[cfg(not(feature = "scripting"))]
+            {
+                eprintln!("Can't load {}: lapol was built without scripting.", path);
+                std::process::exit(2);
+            }
+        } else if let Some(path) = arg.strip_prefix("--plugin=") {
+            #[cfg(feature = "plugins")]
+            plugins.push(load_plugin(path));
+            #[cfg(not(feature = "plugins"))]
+            {
+                eprintln!("Can't load {}: lapol was built without plugins.", path);
+                std::process::exit(2);
+            }
+        } else if let Some(d) = arg.strip_prefix("--out-dir=") {
+            out_dir = d.into();
+        } else if let Some(d) = arg.strip_prefix("--lapol-dir=") {
+            lapol_dir = d.into();
+        } else if arg.starts_with("--") {
+            usage_error();
+        } else {
+            inputs.push(PathBuf::from(arg));
+        }
+    }
+
+    if inputs.is_empty() {
+        usage_error();
+    }
+    if targets.is_empty() {
+        targets.push(Target::Html);
+    }
+
+    let options = BuildOptions {
+        targets,
+        out_dir,
+        lapol_dir,
+        variables,
+        #[cfg(feature = "scripting")]
+        scripts,
+        #[cfg(feature = "plugins")]
+        plugins,
+    };
+
+    let mut failed = false;
+    for input in &inputs {
+        match build::build_file(input, &options) {
+            Ok(written) => {
+                for path in written {
+                    println!("Wrote {}", path.display());
+                }
+            }
+            Err(e) => {
+                match e {
+                    // Already starts with the path.
+                    build::BuildError::Source(_) => eprintln!("{}", e),
+                    _ => eprintln!("{}: {}", input.display(), e),
+                }
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// The module name (the file stem) and source of a script.
+#[cfg(feature = "scripting")]
+fn load_script(path: &str) -> (String, String) {
+    let path = std::path::Path::new(path);
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    match std::fs::read_to_string(path) {
+        Ok(source) => (name.into_owned(), source),
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(path: &str) -> lapol_eval_rs::plugin::Plugin {
+    let limits = lapol_eval_rs::plugin::PluginLimits::default();
+    match lapol_eval_rs::plugin::Plugin::load(std::path::Path::new(path), &limits) {
+        Ok(plugin) => plugin,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Like a square argument entry: a number, a boolean, or else a string.
+fn parse_value(s: &str) -> Value {
+    match s {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => s
+            .parse()
+            .map(Value::Num)
+            .unwrap_or_else(|_| Value::Str(s.to_owned())),
+    }
+}
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
@@ -1,51 +1,40 @@
 mod panic_hook;
 
-mod parse;
-pub use parse::parse_file;
+mod api;
+pub use api::{capabilities, version, Capabilities, API_VERSION};
 
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
+mod error;
+pub use error::ErrorKind;
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console, js_name=log)]
-    fn js_console_log(s: &str);
+#[cfg(feature = "tracing")]
+mod logging;
+#[cfg(feature = "tracing")]
+pub use logging::{init_stderr_tracing, init_tracing};
 
-}
+mod parse;
+pub use parse::{parse_file, ParseJob, Parser};
 
-#[wasm_bindgen]
-/// Before using any functions, it is important to initialize lapol-rs.
-pub fn init() {
-    panic_hook::set_panic_hook()
-}
+mod process;
+pub use process::process_ltrf;
 
-#[wasm_bindgen]
-pub fn greet() {
-    js_console_log("Hello, lapol-rs!");
-}
+mod evaluate;
+pub use evaluate::evaluate_source;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct FileReadContent {
-    #[serde(rename = "fileContent")]
-    pub file_content: String,
-}
+mod output;
+pub use output::{render_html, render_latex, render_markdown, render_text};
 
-#[wasm_bindgen(js_name = receiveStr)]
-pub fn receive_str(str: &str) {
-    js_console_log(&format!("Received string"));
-    js_console_log(&format!("First char {}", str.chars().nth(0).unwrap()));
-}
+mod requirements;
+pub use requirements::{content_hash, OutputRequirements};
 
-#[wasm_bindgen(js_name = receiveVal)]
-pub fn receive_val(val: &JsValue) {
-    let e: FileReadContent = val.into_serde().unwrap();
-    js_console_log(&format!("Received {:?}", e));
-}
+#[cfg(feature = "debug-endpoints")]
+mod debug;
+#[cfg(feature = "debug-endpoints")]
+pub use debug::{greet, receive_buffer, receive_str, receive_val, FileReadContent};
+
+use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen(js_name = receiveBuffer)]
-pub fn receive_buffer(buff: &[u8]) {
-    js_console_log(&format!("Received Buffer"));
-    js_console_log(&format!("First elem {}", buff[0]));
-    let my_str = std::str::from_utf8(buff).unwrap();
-    js_console_log(&format!("First char {}", my_str.chars().nth(0).unwrap()));
+#[wasm_bindgen]
+/// Before using any functions, it is important to initialize lapol-rs.
+pub fn init() {
+    panic_hook::set_panic_hook()
 }
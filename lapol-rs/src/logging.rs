@@ -0,0 +1,96 @@
+//! Diagnostics, with `tracing`: the parser emits spans for its phases (at the
+//! `debug` level), and for each command and curly argument (at the `trace`
+//! level). Nothing is output until a subscriber is set up, with
+//! `initTracing` in WASM or `init_stderr_tracing` natively.
+//!
+//! Filters select what is output, like `RUST_LOG`: a level (e.g. `debug`),
+//! and/or levels per crate (e.g. `lapol_parse_rs=trace,warn`).
+
+use std::{io, str::FromStr};
+
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::{format::FmtSpan, MakeWriter},
+    prelude::*,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{error, ErrorKind};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+}
+
+// `fmt()` only outputs `info` and above by default; `targets` does the
+// filtering instead.
+
+/// Outputs diagnostics matching `filter` (see the module docs) with
+/// `console.debug`. Can only be called once.
+#[wasm_bindgen(js_name = initTracing)]
+pub fn init_tracing(filter: &str) -> Result<(), JsValue> {
+    let targets = parse_filter(filter).map_err(|e| error(ErrorKind::Input, e))?;
+    // There is no clock in WASM, hence no timestamps nor span timings.
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(Console)
+        .with_max_level(LevelFilter::TRACE)
+        .with_ansi(false)
+        .without_time()
+        .finish()
+        .with(targets);
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| error(ErrorKind::Input, e))
+}
+
+/// Outputs diagnostics matching `filter` (see the module docs) on stderr,
+/// with the time spent in each span when it closes. Can only be called once.
+pub fn init_stderr_tracing(filter: &str) -> Result<(), String> {
+    let targets = parse_filter(filter)?;
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_max_level(LevelFilter::TRACE)
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .finish()
+        .with(targets);
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())
+}
+
+fn parse_filter(filter: &str) -> Result<Targets, String> {
+    Targets::from_str(filter).map_err(|e| format!("Bad tracing filter {:?}: {}", filter, e))
+}
+
+/// Makes a `ConsoleLine` per event.
+struct Console;
+
+impl<'a> MakeWriter<'a> for Console {
+    type Writer = ConsoleLine;
+
+    fn make_writer(&'a self) -> ConsoleLine {
+        ConsoleLine(Vec::new())
+    }
+}
+
+/// Buffers an event, which is output when dropped.
+struct ConsoleLine(Vec<u8>);
+
+impl io::Write for ConsoleLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ConsoleLine {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.0);
+        let line = line.trim_end();
+        if !line.is_empty() {
+            console_debug(line);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use lapol_core_rs::{
+    ltrf::LtrfNode,
+    output::{
+        html::{self, HtmlOptions},
+        latex::{self, LatexOptions},
+        markdown::{self, MarkdownOptions},
+        text::{self, TextOptions},
+    },
+};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{error, ErrorKind};
+
+/// Renders a processed LTRF root node (as JSON, see `processLtrf`) as HTML,
+/// using the default tag mappings (see `lapol_core_rs::output::html`).
+#[wasm_bindgen(js_name = renderHtml)]
+pub fn render_html(
+    ltrf_root_json: &str,
+    pretty: bool,
+    standalone: bool,
+) -> Result<String, JsValue> {
+    let root = parse_ltrf(ltrf_root_json)?;
+
+    let options = HtmlOptions {
+        pretty,
+        standalone,
+        ..HtmlOptions::default()
+    };
+
+    html::render_html(&root, &options).map_err(|e| error(ErrorKind::Output, e))
+}
+
+/// Renders a processed LTRF root node (as JSON, see `processLtrf`) as LaTeX,
+/// using the default tag mappings (see `lapol_core_rs::output::latex`).
+///
+/// `title_json` and `author_json` are JSON arrays of LTRF objects, used by
+/// standalone documents.
+#[wasm_bindgen(js_name = renderLatex)]
+pub fn render_latex(
+    ltrf_root_json: &str,
+    standalone: bool,
+    title_json: &str,
+    author_json: &str,
+) -> Result<String, JsValue> {
+    let root = parse_ltrf(ltrf_root_json)?;
+
+    let options = LatexOptions {
+        standalone,
+        title: serde_json::from_str(title_json)
+            .map_err(|e| error(ErrorKind::Input, format!("Bad title JSON: {}", e)))?,
+        author: serde_json::from_str(author_json)
+            .map_err(|e| error(ErrorKind::Input, format!("Bad author JSON: {}", e)))?,
+        ..LatexOptions::default()
+    };
+
+    latex::render_latex(&root, &options).map_err(|e| error(ErrorKind::Output, e))
+}
+
+/// Renders a processed LTRF root node (as JSON, see `processLtrf`) as plain
+/// text, wrapped at `width` columns (if given).
+#[wasm_bindgen(js_name = renderText)]
+pub fn render_text(ltrf_root_json: &str, width: Option<u32>) -> Result<String, JsValue> {
+    let root = parse_ltrf(ltrf_root_json)?;
+
+    let options = TextOptions {
+        width: width.map(|w| w as usize),
+        ..TextOptions::default()
+    };
+
+    text::render_text(&root, &options).map_err(|e| error(ErrorKind::Output, e))
+}
+
+/// Renders a processed LTRF root node (as JSON, see `processLtrf`) as
+/// CommonMark, wrapped at `width` columns (if given).
+#[wasm_bindgen(js_name = renderMarkdown)]
+pub fn render_markdown(ltrf_root_json: &str, width: Option<u32>) -> Result<String, JsValue> {
+    let root = parse_ltrf(ltrf_root_json)?;
+
+    let options = MarkdownOptions {
+        width: width.map(|w| w as usize),
+        ..MarkdownOptions::default()
+    };
+
+    markdown::render_markdown(&root, &options).map_err(|e| error(ErrorKind::Output, e))
+}
+
+fn parse_ltrf(json: &str) -> Result<LtrfNode, JsValue> {
+    serde_json::from_str(json).map_err(|e| error(ErrorKind::Input, format!("Bad LTRF JSON: {}", e)))
+}
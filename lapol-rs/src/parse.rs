@@ -1,42 +1,148 @@
-use std::{
-    error::Error,
-    fs::File,
-    io::{BufReader, Read},
-};
-
+use lapol_parse_rs::{AstNode, MarkupOptions, ParserOptions, SourceMap, WhitespaceOptions};
+use serde::Deserialize;
 use wasm_bindgen::prelude::*;
 
-// This won't work in wasm!
-pub(crate) fn load_file(full_path: &str) -> Result<String, Box<dyn Error>> {
-    let file = File::open(full_path)?;
-    let mut buf_reader = BufReader::new(file);
-    let mut contents = String::new();
-    buf_reader.read_to_string(&mut contents)?;
-    Ok(contents)
+use crate::error::{error, ErrorKind};
+
+/// Parses a file's contents (UTF-8) into the AST, with the default options
+/// (see `Parser` to set them). Throws a `LapolParseError` on invalid code.
+#[wasm_bindgen]
+pub fn parse_file(file_path: &str, file_content_buffer: &[u8]) -> Result<JsValue, JsValue> {
+    Parser::default().parse(file_path, file_content_buffer)
+}
+
+/// Parser options, as given to `new Parser(...)`. All are optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+struct ParserConfig {
+    /// Enables the Markdown-style markup (with the `std::main` commands).
+    markup: bool,
+    paragraph_breaks: bool,
+    dedent: bool,
+    collapse_spaces: bool,
+    block_commands: Vec<String>,
+}
+
+/// A parser holding its options across calls.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct Parser {
+    options: ParserOptions,
 }
 
-#[allow(dead_code)]
 #[wasm_bindgen]
-pub fn parse_file(file_path: &str, file_content_buffer: &[u8]) -> JsValue {
-    println!("Parsing {}", file_path);
+impl Parser {
+    /// `options_json` is a JSON object, e.g. `{"markup": true,
+    /// "paragraphBreaks": true}`, with any of `markup`, `paragraphBreaks`,
+    /// `dedent`, `collapseSpaces` (booleans) and `blockCommands` (an array of
+    /// command names). See `lapol_parse_rs::ParserOptions`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options_json: Option<String>) -> Result<Parser, JsValue> {
+        let config: ParserConfig = match options_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| error(ErrorKind::Input, format!("Bad parser options: {}", e)))?,
+            None => ParserConfig::default(),
+        };
+
+        Ok(Parser {
+            options: ParserOptions {
+                markup: if config.markup {
+                    Some(MarkupOptions::default())
+                } else {
+                    None
+                },
+                paragraph_breaks: config.paragraph_breaks,
+                whitespace: WhitespaceOptions {
+                    dedent: config.dedent,
+                    collapse_spaces: config.collapse_spaces,
+                    block_commands: config.block_commands,
+                },
+            },
+        })
+    }
 
-    let file_content = std::str::from_utf8(file_content_buffer)
-        .expect("Failed to turn file_content_buffer into str.");
+    /// Parses a file's contents (UTF-8) into the AST.
+    pub fn parse(&self, file_path: &str, file_content: &[u8]) -> Result<JsValue, JsValue> {
+        let file_content = decode(file_path, file_content)?;
+        let root = self.parse_str(file_path, file_content)?;
 
-    // let tok_start = Instant::now();
+        #[allow(deprecated)]
+        Ok(JsValue::from_serde(&root).expect("ASTs are always serializable"))
+    }
 
-    let root_node = lapol_parse_rs::parse(&file_content).unwrap();
+    /// Like `parse`, but returns a `ParseJob`, to run in a worker.
+    #[wasm_bindgen(js_name = parseJob)]
+    pub fn parse_job(&self, file_path: &str, file_content: &[u8]) -> Result<ParseJob, JsValue> {
+        let file_content = decode(file_path, file_content)?;
+        let root = self.parse_str(file_path, file_content)?;
+
+        Ok(ParseJob {
+            json: serde_json::to_vec(&root).expect("ASTs are always serializable"),
+            pos: 0,
+        })
+    }
+}
 
-    JsValue::from_serde(&root_node).unwrap()
+impl Parser {
+    fn parse_str<'a>(
+        &self,
+        file_path: &str,
+        file_content: &'a str,
+    ) -> Result<AstNode<'a>, JsValue> {
+        lapol_parse_rs::parse_with_options(file_content, &self.options).map_err(|e| {
+            // Only needed to resolve the position of the error.
+            let source_map = SourceMap::new();
+            source_map.add(file_path, file_content.to_owned());
+            error(ErrorKind::Parse, e.diagnostic(&source_map))
+        })
+    }
 }
 
-#[allow(dead_code)]
-pub fn parse_file_native(file_path: &str) {
-    println!("Parsing {}", file_path);
+fn decode<'a>(file_path: &str, file_content: &'a [u8]) -> Result<&'a str, JsValue> {
+    std::str::from_utf8(file_content).map_err(|e| {
+        error(
+            ErrorKind::Input,
+            format!("{} is not valid UTF-8: {}", file_path, e),
+        )
+    })
+}
+
+/// A parse meant to be run in a worker thread, so that big files don't block
+/// the main thread's event loop. The AST is serialized to JSON (UTF-8), which
+/// is then taken out in chunks, e.g. to post them back as transferable
+/// buffers.
+#[wasm_bindgen(js_name = ParseJob)]
+pub struct ParseJob {
+    json: Vec<u8>,
+    pos: usize,
+}
 
-    let file_content = load_file(file_path).unwrap();
+#[wasm_bindgen(js_class = ParseJob)]
+impl ParseJob {
+    /// Parses `file_content`, with the default options (see
+    /// `Parser.parseJob`).
+    #[wasm_bindgen(constructor)]
+    pub fn new(file_path: &str, file_content: &[u8]) -> Result<ParseJob, JsValue> {
+        Parser::default().parse_job(file_path, file_content)
+    }
 
-    let root_node = lapol_parse_rs::parse(&file_content).unwrap();
+    /// The length of the JSON, in bytes.
+    #[wasm_bindgen(getter, js_name = byteLength)]
+    pub fn byte_length(&self) -> usize {
+        self.json.len()
+    }
 
-    // println!("root node dbg: {:#?}", root_node);
+    /// The next (at most) `max_bytes` bytes of the JSON, or `undefined` once
+    /// it has all been taken. Chunks may split UTF-8 characters, so they must
+    /// be decoded together.
+    #[wasm_bindgen(js_name = nextChunk)]
+    pub fn next_chunk(&mut self, max_bytes: usize) -> Option<Vec<u8>> {
+        if self.pos == self.json.len() {
+            return None;
+        }
+        let end = self.json.len().min(self.pos + max_bytes.max(1));
+        let chunk = self.json[self.pos..end].to_vec();
+        self.pos = end;
+        Some(chunk)
+    }
 }
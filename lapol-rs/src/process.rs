@@ -0,0 +1,20 @@
+use lapol_core_rs::{ltrf::LtrfNode, process};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{error, ErrorKind};
+
+/// Runs the native processing passes (see `lapol_core_rs::process`) over an
+/// LTRF root node.
+///
+/// Takes and returns JSON (the format of lapol-core's `dbgStringify`; use
+/// `ltrfObjFromJson` to get an `LtrfNode` back).
+#[wasm_bindgen(js_name = processLtrf)]
+pub fn process_ltrf(ltrf_root_json: &str) -> Result<String, JsValue> {
+    let root: LtrfNode = serde_json::from_str(ltrf_root_json)
+        .map_err(|e| error(ErrorKind::Input, format!("Bad LTRF JSON: {}", e)))?;
+
+    let out = process::process_pass(&root).map_err(|e| error(ErrorKind::Output, e))?;
+
+    Ok(serde_json::to_string(&out).expect("LTRF trees are always serializable"))
+}
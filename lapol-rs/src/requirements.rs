@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use lapol_core_rs::output::requirements::{self, OutputFileSystem, OutputRequirementReceiver};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{error, ErrorKind};
+
+/// Output requirements (see `lapol_core_rs::output::requirements`): targets
+/// are normalized, and binding a target to two files with different contents
+/// is an error. `copyTo` only writes the files that changed, atomically.
+///
+/// In WASM, files are accessed through Node's `fs` module (so this needs
+/// Node, e.g. the package built by `wasm-pack build --target nodejs`).
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct OutputRequirements {
+    inner: OutputRequirementReceiver,
+}
+
+#[wasm_bindgen]
+impl OutputRequirements {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(js_name = requireFile)]
+    pub fn require_file(&mut self, input: &str, target: &str) -> Result<(), JsValue> {
+        self.inner
+            .require_file_with(output_fs(), input, target)
+            .map_err(|e| error(ErrorKind::Output, e))
+    }
+
+    /// The required files, as a JSON array of `[target, source]` pairs,
+    /// sorted by target. Targets always use `/` as the separator.
+    pub fn files(&self) -> String {
+        let files: Vec<(String, String)> = self
+            .inner
+            .files()
+            .map(|(target, source)| (slash_path(target), source.to_string_lossy().into_owned()))
+            .collect();
+        serde_json::to_string(&files).expect("Strings are always serializable")
+    }
+
+    /// Copies the required files into `outputDir` (see
+    /// `OutputRequirementReceiver::copy_to`). Returns the targets, as JSON:
+    /// `{ "copied": [...], "unchanged": [...] }`.
+    #[wasm_bindgen(js_name = copyTo)]
+    pub fn copy_to(&self, output_dir: &str) -> Result<String, JsValue> {
+        let report = self
+            .inner
+            .copy_to_with(output_fs(), output_dir)
+            .map_err(|e| error(ErrorKind::Output, e))?;
+
+        let targets = |t: &[PathBuf]| t.iter().map(|t| slash_path(t)).collect::<Vec<_>>();
+        let json = serde_json::json!({
+            "copied": targets(&report.copied),
+            "unchanged": targets(&report.unchanged),
+        });
+        Ok(json.to_string())
+    }
+}
+
+/// SHA-256 of `contents`, as lowercase hex.
+#[wasm_bindgen(js_name = contentHash)]
+pub fn content_hash(contents: &[u8]) -> String {
+    requirements::content_hash(contents)
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(target_arch = "wasm32")]
+fn output_fs() -> &'static dyn OutputFileSystem {
+    &node_fs::NodeFileSystem
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn output_fs() -> &'static dyn OutputFileSystem {
+    &requirements::NativeFileSystem
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod node_fs {
+    use std::{io, path::Path};
+
+    use js_sys::{Object, Reflect, Uint8Array};
+    use lapol_core_rs::output::requirements::OutputFileSystem;
+    use wasm_bindgen::{prelude::*, JsCast};
+
+    #[wasm_bindgen(module = "fs")]
+    extern "C" {
+        #[wasm_bindgen(js_name = readFileSync, catch)]
+        fn read_file_sync(path: &str) -> Result<Uint8Array, JsValue>;
+        #[wasm_bindgen(js_name = writeFileSync, catch)]
+        fn write_file_sync(path: &str, data: &[u8]) -> Result<(), JsValue>;
+        #[wasm_bindgen(js_name = renameSync, catch)]
+        fn rename_sync(from: &str, to: &str) -> Result<(), JsValue>;
+        #[wasm_bindgen(js_name = unlinkSync, catch)]
+        fn unlink_sync(path: &str) -> Result<(), JsValue>;
+        #[wasm_bindgen(js_name = mkdirSync, catch)]
+        fn mkdir_sync(path: &str, options: &JsValue) -> Result<JsValue, JsValue>;
+    }
+
+    /// Node's `fs` (only used in WASM, where `std::fs` doesn't work).
+    pub(crate) struct NodeFileSystem;
+
+    impl OutputFileSystem for NodeFileSystem {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            read_file_sync(&path.to_string_lossy())
+                .map(|buf| buf.to_vec())
+                .map_err(io_error)
+        }
+
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            write_file_sync(&path.to_string_lossy(), contents).map_err(io_error)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            rename_sync(&from.to_string_lossy(), &to.to_string_lossy()).map_err(io_error)
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            unlink_sync(&path.to_string_lossy()).map_err(io_error)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            let options = Object::new();
+            Reflect::set(&options, &"recursive".into(), &true.into()).map_err(io_error)?;
+            mkdir_sync(&path.to_string_lossy(), &options)
+                .map(|_| ())
+                .map_err(io_error)
+        }
+
+        fn process_id(&self) -> u32 {
+            Reflect::get(&js_sys::global(), &"process".into())
+                .and_then(|process| Reflect::get(&process, &"pid".into()))
+                .ok()
+                .and_then(|pid| pid.as_f64())
+                .map_or(0, |pid| pid as u32)
+        }
+    }
+
+    /// Node's errors are JS `Error`s, whose message has the code (e.g.
+    /// `ENOENT`) and the path.
+    fn io_error(e: JsValue) -> io::Error {
+        let message = e
+            .dyn_ref::<js_sys::Error>()
+            .map(|e| String::from(e.message()))
+            .unwrap_or_else(|| format!("{:?}", e));
+        io::Error::other(message)
+    }
+}
@@ -0,0 +1,60 @@
+use lapol_rs::{Capabilities, Parser, API_VERSION};
+
+fn json(mut job: lapol_rs::ParseJob) -> String {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = job.next_chunk(1 << 16) {
+        bytes.extend(chunk);
+    }
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn capabilities_list_the_api() {
+    let c = Capabilities::current();
+    assert_eq!(c.api_version, API_VERSION);
+    assert_eq!(c.version, lapol_rs::version());
+    assert!(c.features.contains(&"parser"));
+    assert_eq!(
+        c.features.contains(&"debugEndpoints"),
+        cfg!(feature = "debug-endpoints")
+    );
+}
+
+#[test]
+fn parsers_keep_their_options() {
+    let src = "Some *emphasis*.";
+    let plain = Parser::new(None).unwrap();
+    let markup = Parser::new(Some(r#"{"markup": true}"#.to_owned())).unwrap();
+
+    for _ in 0..2 {
+        assert!(!json(plain.parse_job("a.lap", src.as_bytes()).unwrap()).contains(r#""it""#));
+        assert!(json(markup.parse_job("a.lap", src.as_bytes()).unwrap()).contains(r#""it""#));
+    }
+}
+
+#[test]
+fn output_requirements_copy_changed_files() {
+    let src = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let css = src.path().join("style.css");
+    let css_copy = src.path().join("style-copy.css");
+    std::fs::write(&css, "body {}").unwrap();
+    std::fs::write(&css_copy, "body {}").unwrap();
+
+    let mut reqs = lapol_rs::OutputRequirements::new();
+    reqs.require_file(css.to_str().unwrap(), "deps/style.css")
+        .unwrap();
+    // Same contents, so not a conflict.
+    reqs.require_file(css_copy.to_str().unwrap(), "./deps/style.css")
+        .unwrap();
+
+    let out_dir = out.path().to_str().unwrap();
+    assert_eq!(
+        reqs.copy_to(out_dir).unwrap(),
+        r#"{"copied":["deps/style.css"],"unchanged":[]}"#
+    );
+    assert_eq!(
+        reqs.copy_to(out_dir).unwrap(),
+        r#"{"copied":[],"unchanged":["deps/style.css"]}"#
+    );
+}
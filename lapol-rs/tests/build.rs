@@ -0,0 +1,145 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::{fs, path::Path, process::Command};
+
+fn lapol(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_lapol"))
+        .current_dir(dir)
+        .args(args)
+        .env("LAPOL_DIR", concat!(env!("CARGO_MANIFEST_DIR"), "/.."))
+        .output()
+        .unwrap()
+}
+
+const DOC: &str = "@__require{std::main}
+@__using_all[from=std::main]
+@title{A <Title>}
+@author{Someone}
+@__doc{
+@maketitle{}
+@sec{Intro}
+Some @bf{bold} and @std::main:it{italic} text.
+
+Second paragraph.
+}
+";
+
+#[test]
+fn builds_html_and_latex() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("doc.lap"), DOC).unwrap();
+
+    let out = lapol(
+        dir.path(),
+        &["build", "doc.lap", "--target=html", "--target=latex"],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let html = fs::read_to_string(dir.path().join("out/doc.html")).unwrap();
+    assert!(html.contains(
+        r#"<header><h1 class="title">A &lt;Title&gt;</h1><p class="subtitle">Someone</p></header><h2>Intro</h2><p>Some <strong>bold</strong> and <em>italic</em> text.</p><p>Second paragraph.</p>"#
+    ));
+
+    let tex = fs::read_to_string(dir.path().join("out/doc.tex")).unwrap();
+    assert!(tex.contains(r"\title{A \textless{}Title\textgreater{}}"));
+    assert!(tex.contains(r"\section{Intro}"));
+
+    assert!(dir.path().join("out/deps/lapol-default.css").is_file());
+    assert!(dir.path().join("out/deps/lapol_default.sty").is_file());
+}
+
+#[test]
+fn unknown_commands_are_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    // `sec` isn't in scope without `__using_all`.
+    fs::write(dir.path().join("doc.lap"), "@__doc{@sec{x}}").unwrap();
+
+    let out = lapol(dir.path(), &["build", "doc.lap"]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains(
+        "doc.lap:1:8: LaPoL evaluation error --- Unknown command `sec`\n1 | @__doc{@sec{x}}\n  |        ^"
+    ));
+    assert!(!dir.path().join("out/doc.html").exists());
+}
+
+#[test]
+fn variables_and_target_are_set() {
+    let dir = tempfile::tempdir().unwrap();
+    let doc = "@__doc{@if[target=latex]{Print}{Web} edition @get{edition}.}";
+    fs::write(dir.path().join("doc.lap"), doc).unwrap();
+
+    let out = lapol(
+        dir.path(),
+        &[
+            "build",
+            "doc.lap",
+            "--target=html",
+            "--target=latex",
+            "--var=edition=2",
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let html = fs::read_to_string(dir.path().join("out/doc.html")).unwrap();
+    assert!(html.contains("Web edition 2."));
+    let tex = fs::read_to_string(dir.path().join("out/doc.tex")).unwrap();
+    assert!(tex.contains("Print edition 2."));
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn loads_scripts() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("shout.rhai"),
+        r#"fn shout(args) { args.curly[0][0].to_upper() }"#,
+    )
+    .unwrap();
+    let doc = "@__require{shout}@__using_all[from=shout]@__doc{@shout{hi}}";
+    fs::write(dir.path().join("doc.lap"), doc).unwrap();
+
+    let out = lapol(dir.path(), &["build", "doc.lap", "--script=shout.rhai"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let html = fs::read_to_string(dir.path().join("out/doc.html")).unwrap();
+    assert!(html.contains("HI"));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn lapol_log_outputs_parser_spans() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("doc.lap"), "@__doc{Hi}").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lapol"))
+        .current_dir(dir.path())
+        .args(["build", "doc.lap"])
+        .env("LAPOL_DIR", concat!(env!("CARGO_MANIFEST_DIR"), "/.."))
+        .env("LAPOL_LOG", "lapol_parse_rs=debug")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("parse{len=10}"), "{}", stderr);
+    assert!(stderr.contains("close time.busy"), "{}", stderr);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lapol"))
+        .current_dir(dir.path())
+        .args(["build", "doc.lap"])
+        .env("LAPOL_LOG", "lapol_parse_rs=loud")
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+}
@@ -0,0 +1,21 @@
+use lapol_rs::ParseJob;
+
+#[test]
+fn chunks_make_up_the_ast_json() {
+    let src = "Some text, @b{bold} and é.\n\nA second paragraph.";
+    let mut job = ParseJob::new("a.lap", src.as_bytes()).unwrap();
+
+    let mut json = Vec::new();
+    let mut chunks = 0;
+    while let Some(chunk) = job.next_chunk(7) {
+        assert!(!chunk.is_empty() && chunk.len() <= 7);
+        json.extend(chunk);
+        chunks += 1;
+    }
+    assert_eq!(json.len(), job.byte_length());
+    assert_eq!(chunks, json.len().div_ceil(7));
+    assert!(job.next_chunk(7).is_none());
+
+    let expected = serde_json::to_vec(&lapol_parse_rs::parse(src).unwrap()).unwrap();
+    assert_eq!(json, expected);
+}